@@ -1,25 +1,38 @@
-use classic_cyberswap::querier::{query_balance, query_pair_info_from_pair};
+use classic_cyberswap::querier::{
+    query_balance, query_contract_version, query_denom_metadata_decimals,
+    query_pair_info_from_pair, query_token_minter,
+};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coin, to_binary, Addr, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Reply,
-    ReplyOn, Response, StdError, StdResult, SubMsg, WasmMsg,
+    attr, coin, instantiate2_address, to_binary, Addr, Binary, Coin, CosmosMsg, Deps, DepsMut,
+    Empty, Env, MessageInfo, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, WasmMsg,
 };
 use cw2::set_contract_version;
 use cw20::Cw20ExecuteMsg;
 
+use crate::error::ContractError;
 use crate::response::MsgInstantiateContractResponse;
 use crate::state::{
-    add_allow_native_token, pair_key, read_pairs, Config, TmpPairInfo, ALLOW_NATIVE_TOKENS, CONFIG,
-    PAIRS, TMP_PAIR_INFO,
+    add_allow_native_token, count_pairs, index_pairs, pair_key, read_allow_native_tokens,
+    read_allowed_creators, read_blocked_assets, read_default_fee_config, read_deprecated_pairs,
+    read_pair_fee_configs, read_pairs, read_pairs_by_asset, remove_pair, store_pair, Config,
+    OwnershipProposal, PairMigration, TmpPairInfo, ALLOWED_CREATORS, ALLOW_NATIVE_TOKENS,
+    BLOCKED_ASSETS, CONFIG, DEFAULT_FEE_CONFIG, DEPRECATED_PAIRS, OWNERSHIP_PROPOSAL, PAIRS,
+    PAIRS_BY_ADDRESS, PAIRS_BY_LIQUIDITY_TOKEN, PAIR_COUNT, PAIR_FEE_CONFIGS, PAIR_INDEXING,
+    PAIR_MIGRATION, PENDING_PAIRS,
 };
 
 use classic_bindings::{TerraMsg, TerraQuery};
 
 use classic_cyberswap::asset::{Asset, AssetInfo, AssetInfoRaw, PairInfo, PairInfoRaw};
 use classic_cyberswap::factory::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, NativeTokenDecimalsResponse,
-    PairsResponse, QueryMsg,
+    AllowedCreatorsResponse, BlockedAssetsResponse, ConfigResponse, ExecuteMsg, FeeConfig,
+    FeeConfigResponse, InstantiateMsg, IsBlockedResponse, MigrateMsg, NativeTokenDecimals,
+    NativeTokenDecimalsListResponse, NativeTokenDecimalsResponse, OutdatedPair,
+    OutdatedPairsResponse, OwnershipProposalResponse, PairCountResponse, PairCreationMode,
+    PairFeeConfig, PairFeeConfigsResponse, PairMigrationResponse, PairsResponse,
+    PredictPairAddressResponse, QueryMsg,
 };
 use classic_cyberswap::pair::{
     ExecuteMsg as PairExecuteMsg, InstantiateMsg as PairInstantiateMsg,
@@ -27,6 +40,7 @@ use classic_cyberswap::pair::{
 };
 use classic_cyberswap::util::migrate_version;
 use protobuf::Message;
+use sha2::{Digest, Sha256};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cyberswap-factory";
@@ -34,6 +48,12 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const CREATE_PAIR_REPLY_ID: u64 = 1;
 
+// ownership proposals can be claimed for at most 14 days
+const MAX_OWNERSHIP_PROPOSAL_TTL: u64 = 14 * 24 * 60 * 60;
+
+/// Total swap fee cannot exceed 10%
+const MAX_TOTAL_FEE_BPS: u16 = 1_000;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut<TerraQuery>,
@@ -47,6 +67,9 @@ pub fn instantiate(
         owner: deps.api.addr_canonicalize(info.sender.as_str())?,
         token_code_id: msg.token_code_id,
         pair_code_id: msg.pair_code_id,
+        pair_creation_mode: PairCreationMode::Permissionless,
+        creation_fee: None,
+        fee_treasury: None,
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -60,48 +83,91 @@ pub fn execute(
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> StdResult<Response<TerraMsg>> {
+) -> Result<Response<TerraMsg>, ContractError> {
     match msg {
         ExecuteMsg::UpdateConfig {
-            owner,
             token_code_id,
             pair_code_id,
-        } => execute_update_config(deps, env, info, owner, token_code_id, pair_code_id),
+            pair_creation_mode,
+            creation_fee,
+            fee_treasury,
+            add_allowed_creators,
+            remove_allowed_creators,
+            default_fee_config,
+            pair_fee_configs,
+            remove_pair_fee_configs,
+        } => execute_update_config(
+            deps,
+            env,
+            info,
+            token_code_id,
+            pair_code_id,
+            pair_creation_mode,
+            creation_fee,
+            fee_treasury,
+            add_allowed_creators,
+            remove_allowed_creators,
+            default_fee_config,
+            pair_fee_configs,
+            remove_pair_fee_configs,
+        ),
         ExecuteMsg::CreatePair { assets } => execute_create_pair(deps, env, info, assets),
         ExecuteMsg::AddNativeTokenDecimals { denom, decimals } => {
             execute_add_native_token_decimals(deps, env, info, denom, decimals)
         }
+        ExecuteMsg::AddNativeTokenDecimalsBatch { tokens } => {
+            execute_add_native_token_decimals_batch(deps, env, info, tokens)
+        }
+        ExecuteMsg::RemoveNativeTokenDecimals { denom } => {
+            execute_remove_native_token_decimals(deps, env, info, denom)
+        }
         ExecuteMsg::MigratePair { contract, code_id } => {
             execute_migrate_pair(deps, env, info, contract, code_id)
         }
+        ExecuteMsg::DeregisterPair { asset_infos } => {
+            execute_deregister_pair(deps, env, info, asset_infos)
+        }
+        ExecuteMsg::RegisterPair { pair_addr } => execute_register_pair(deps, env, info, pair_addr),
+        ExecuteMsg::MigratePairs {
+            start_after,
+            limit,
+            code_id,
+            reset,
+        } => execute_migrate_pairs(deps, env, info, start_after, limit, code_id, reset),
+        ExecuteMsg::IndexPairs { limit } => execute_index_pairs(deps, env, info, limit),
+        ExecuteMsg::UpdateBlocklist { add, remove } => {
+            execute_update_blocklist(deps, env, info, add, remove)
+        }
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            execute_propose_new_owner(deps, env, info, owner, expires_in)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => execute_drop_ownership_proposal(deps, env, info),
+        ExecuteMsg::ClaimOwnership {} => execute_claim_ownership(deps, env, info),
     }
 }
 
 // Only owner can execute it
+#[allow(clippy::too_many_arguments)]
 pub fn execute_update_config(
     deps: DepsMut<TerraQuery>,
     _env: Env,
     info: MessageInfo,
-    owner: Option<String>,
     token_code_id: Option<u64>,
     pair_code_id: Option<u64>,
-) -> StdResult<Response<TerraMsg>> {
+    pair_creation_mode: Option<PairCreationMode>,
+    creation_fee: Option<Asset>,
+    fee_treasury: Option<String>,
+    add_allowed_creators: Option<Vec<String>>,
+    remove_allowed_creators: Option<Vec<String>>,
+    default_fee_config: Option<FeeConfig>,
+    pair_fee_configs: Option<Vec<PairFeeConfig>>,
+    remove_pair_fee_configs: Option<Vec<String>>,
+) -> Result<Response<TerraMsg>, ContractError> {
     let mut config: Config = CONFIG.load(deps.storage)?;
 
     // permission check
     if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
-        return Err(StdError::generic_err("unauthorized"));
-    }
-
-    if let Some(owner) = owner {
-        // validate address format
-        let _ = deps
-            .api
-            .addr_validate(&owner)
-            .unwrap_or(Addr::unchecked(owner.clone()));
-        config.owner = deps.api.addr_canonicalize(&owner)?;
-        //TODO: let _ = deps.api.addr_validate(&owner)?;
-        //TODO: config.owner = deps.api.addr_canonicalize(&owner)?;
+        return Err(ContractError::Unauthorized {});
     }
 
     if let Some(token_code_id) = token_code_id {
@@ -112,22 +178,255 @@ pub fn execute_update_config(
         config.pair_code_id = pair_code_id;
     }
 
+    if let Some(pair_creation_mode) = pair_creation_mode {
+        config.pair_creation_mode = pair_creation_mode;
+    }
+
+    if let Some(creation_fee) = creation_fee {
+        config.creation_fee = if creation_fee.amount.is_zero() {
+            None
+        } else {
+            Some(creation_fee.to_raw(deps.api)?)
+        };
+    }
+
+    if let Some(fee_treasury) = fee_treasury {
+        deps.api.addr_validate(&fee_treasury)?;
+        config.fee_treasury = Some(deps.api.addr_canonicalize(&fee_treasury)?);
+    }
+
+    if config.creation_fee.is_some() && config.fee_treasury.is_none() {
+        return Err(ContractError::FeeTreasuryNotSet {});
+    }
+
+    for creator in add_allowed_creators.unwrap_or_default() {
+        deps.api.addr_validate(&creator)?;
+        let creator = deps.api.addr_canonicalize(&creator)?;
+        ALLOWED_CREATORS.save(deps.storage, creator.as_slice(), &Empty {})?;
+    }
+
+    for creator in remove_allowed_creators.unwrap_or_default() {
+        let creator = deps.api.addr_canonicalize(&creator)?;
+        ALLOWED_CREATORS.remove(deps.storage, creator.as_slice());
+    }
+
+    // every fee change is recorded in the attributes
+    let mut attributes = vec![attr("action", "update_config")];
+    if let Some(fee_config) = default_fee_config {
+        assert_fee_config(&config, &fee_config)?;
+
+        attributes.extend(vec![
+            attr(
+                "default_total_fee_bps",
+                fee_config.total_fee_bps.to_string(),
+            ),
+            attr(
+                "default_protocol_fee_bps",
+                fee_config.protocol_fee_bps.to_string(),
+            ),
+        ]);
+        DEFAULT_FEE_CONFIG.save(deps.storage, &fee_config)?;
+    }
+
+    for pair_fee_config in pair_fee_configs.unwrap_or_default() {
+        let fee_config = &pair_fee_config.fee_config;
+        assert_fee_config(&config, fee_config)?;
+
+        // only registered pairs can be overridden
+        let pair_addr = deps.api.addr_canonicalize(&pair_fee_config.pair_addr)?;
+        PAIRS_BY_ADDRESS.load(deps.storage, pair_addr.as_slice())?;
+
+        attributes.extend(vec![
+            attr("pair_addr", pair_fee_config.pair_addr.as_str()),
+            attr("total_fee_bps", fee_config.total_fee_bps.to_string()),
+            attr("protocol_fee_bps", fee_config.protocol_fee_bps.to_string()),
+        ]);
+        PAIR_FEE_CONFIGS.save(deps.storage, pair_addr.as_slice(), fee_config)?;
+    }
+
+    for pair_addr in remove_pair_fee_configs.unwrap_or_default() {
+        let raw_pair_addr = deps.api.addr_canonicalize(&pair_addr)?;
+        PAIR_FEE_CONFIGS.remove(deps.storage, raw_pair_addr.as_slice());
+        attributes.push(attr("removed_pair_fee_config", pair_addr));
+    }
+
     CONFIG.save(deps.storage, &config)?;
 
-    Ok(Response::new().add_attribute("action", "update_config"))
+    Ok(Response::new().add_attributes(attributes))
 }
 
-// Anyone can execute it to create swap pair
+fn assert_fee_config(config: &Config, fee_config: &FeeConfig) -> Result<(), ContractError> {
+    if fee_config.total_fee_bps > MAX_TOTAL_FEE_BPS
+        || fee_config.protocol_fee_bps > fee_config.total_fee_bps
+    {
+        return Err(ContractError::InvalidFeeConfig {
+            max: MAX_TOTAL_FEE_BPS,
+        });
+    }
+
+    if fee_config.protocol_fee_bps > 0 && config.fee_treasury.is_none() {
+        return Err(ContractError::FeeTreasuryNotSet {});
+    }
+
+    Ok(())
+}
+
+// Only owner can execute it
+pub fn execute_update_blocklist(
+    deps: DepsMut<TerraQuery>,
+    _env: Env,
+    info: MessageInfo,
+    add: Option<Vec<AssetInfo>>,
+    remove: Option<Vec<AssetInfo>>,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut attributes = vec![attr("action", "update_blocklist")];
+    for asset_info in add.unwrap_or_default() {
+        let raw_info = asset_info.to_raw(deps.api)?;
+        BLOCKED_ASSETS.save(deps.storage, raw_info.as_bytes(), &raw_info)?;
+        attributes.push(attr("blocked", asset_info.to_string()));
+    }
+
+    for asset_info in remove.unwrap_or_default() {
+        let raw_info = asset_info.to_raw(deps.api)?;
+        BLOCKED_ASSETS.remove(deps.storage, raw_info.as_bytes());
+        attributes.push(attr("unblocked", asset_info.to_string()));
+    }
+
+    Ok(Response::new().add_attributes(attributes))
+}
+
+// Only owner can execute it
+pub fn execute_propose_new_owner(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    expires_in: u64,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if expires_in > MAX_OWNERSHIP_PROPOSAL_TTL {
+        return Err(ContractError::InvalidOwnershipProposalTtl {
+            max: MAX_OWNERSHIP_PROPOSAL_TTL,
+        });
+    }
+
+    let new_owner = deps.api.addr_validate(&owner)?;
+    let new_owner_raw = deps.api.addr_canonicalize(new_owner.as_str())?;
+    if new_owner_raw == config.owner {
+        return Err(StdError::generic_err("New owner cannot be the current owner").into());
+    }
+
+    let expires_at = env.block.time.seconds() + expires_in;
+    OWNERSHIP_PROPOSAL.save(
+        deps.storage,
+        &OwnershipProposal {
+            owner: new_owner_raw,
+            expires_at,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "propose_new_owner"),
+        ("new_owner", new_owner.as_str()),
+        ("expires_at", &expires_at.to_string()),
+    ]))
+}
+
+// Only owner can execute it
+pub fn execute_drop_ownership_proposal(
+    deps: DepsMut<TerraQuery>,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    OWNERSHIP_PROPOSAL.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "drop_ownership_proposal"))
+}
+
+// Only the proposed owner can execute it
+pub fn execute_claim_ownership(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let proposal = OWNERSHIP_PROPOSAL
+        .may_load(deps.storage)?
+        .ok_or(ContractError::OwnershipProposalNotFound {})?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != proposal.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if env.block.time.seconds() > proposal.expires_at {
+        return Err(ContractError::OwnershipProposalExpired {});
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let previous_owner = deps.api.addr_humanize(&config.owner)?;
+    config.owner = proposal.owner;
+    CONFIG.save(deps.storage, &config)?;
+    OWNERSHIP_PROPOSAL.remove(deps.storage);
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "claim_ownership"),
+        ("previous_owner", previous_owner.as_str()),
+        ("new_owner", info.sender.as_str()),
+    ]))
+}
+
+// Who can create a swap pair depends on the pair creation mode
 pub fn execute_create_pair(
     deps: DepsMut<TerraQuery>,
     env: Env,
     info: MessageInfo,
     assets: [Asset; 2],
-) -> StdResult<Response<TerraMsg>> {
+) -> Result<Response<TerraMsg>, ContractError> {
     let config: Config = CONFIG.load(deps.storage)?;
 
+    let creation_fee = assert_pair_creator(deps.as_ref(), &info, &config)?;
+
     if assets[0].info == assets[1].info {
-        return Err(StdError::generic_err("same asset"));
+        return Err(StdError::generic_err("same asset").into());
+    }
+
+    for asset in assets.iter() {
+        if BLOCKED_ASSETS.has(deps.storage, asset.info.to_raw(deps.api)?.as_bytes()) {
+            return Err(ContractError::AssetBlocked {
+                asset: asset.info.to_string(),
+            });
+        }
+    }
+
+    // the initial liquidity is provided in the reply, for both assets or none
+    if assets[0].amount.is_zero() != assets[1].amount.is_zero() {
+        return Err(ContractError::InvalidInitialLiquidity {});
+    }
+
+    assert_sent_funds(&info, &assets, creation_fee.as_ref().map(|(fee, _)| fee))?;
+    let mut messages: Vec<CosmosMsg<TerraMsg>> = vec![];
+    if let Some((fee, fee_treasury)) = creation_fee {
+        messages.push(creation_fee_msg(deps.as_ref(), &info, fee, fee_treasury)?);
     }
 
     let asset_1_decimal = match assets[0]
@@ -135,7 +434,7 @@ pub fn execute_create_pair(
         .query_decimals(env.contract.address.clone(), &deps.querier)
     {
         Ok(decimal) => decimal,
-        Err(_) => return Err(StdError::generic_err("asset1 is invalid")),
+        Err(_) => return Err(StdError::generic_err("asset1 is invalid").into()),
     };
 
     let asset_2_decimal = match assets[1]
@@ -143,7 +442,7 @@ pub fn execute_create_pair(
         .query_decimals(env.contract.address.clone(), &deps.querier)
     {
         Ok(decimal) => decimal,
-        Err(_) => return Err(StdError::generic_err("asset2 is invalid")),
+        Err(_) => return Err(StdError::generic_err("asset2 is invalid").into()),
     };
 
     let raw_assets = [assets[0].to_raw(deps.api)?, assets[1].to_raw(deps.api)?];
@@ -158,13 +457,13 @@ pub fn execute_create_pair(
 
     let pair_key = pair_key(&raw_infos);
     if let Ok(Some(_)) = PAIRS.may_load(deps.storage, &pair_key) {
-        return Err(StdError::generic_err("Pair already exists"));
+        return Err(StdError::generic_err("Pair already exists").into());
     }
 
-    TMP_PAIR_INFO.save(
+    PENDING_PAIRS.save(
         deps.storage,
+        &pair_key,
         &TmpPairInfo {
-            pair_key,
             assets: raw_assets,
             asset_decimals,
             sender: info.sender,
@@ -176,10 +475,11 @@ pub fn execute_create_pair(
             ("action", "create_pair"),
             ("pair", &format!("{}-{}", assets[0].info, assets[1].info)),
         ])
+        .add_messages(messages)
         .add_submessage(SubMsg {
             id: CREATE_PAIR_REPLY_ID,
             gas_limit: None,
-            msg: CosmosMsg::Wasm(WasmMsg::Instantiate {
+            msg: CosmosMsg::Wasm(WasmMsg::Instantiate2 {
                 code_id: config.pair_code_id,
                 funds: vec![],
                 admin: Some(env.contract.address.to_string()),
@@ -189,32 +489,140 @@ pub fn execute_create_pair(
                     token_code_id: config.token_code_id,
                     asset_decimals,
                 })?,
+                salt: pair_salt(&pair_key),
             }),
             reply_on: ReplyOn::Success,
         }))
 }
 
+/// Derives the Instantiate2 salt of a pair from its pair key,
+/// which can be longer than the 64 bytes a salt is limited to
+pub fn pair_salt(pair_key: &[u8]) -> Binary {
+    Binary::from(Sha256::digest(pair_key).to_vec())
+}
+
+/// Checks the sender against the pair creation mode and
+/// returns the creation fee it has to pay along with the treasury receiving it
+fn assert_pair_creator(
+    deps: Deps<TerraQuery>,
+    info: &MessageInfo,
+    config: &Config,
+) -> Result<Option<(Asset, Addr)>, ContractError> {
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender == config.owner {
+        return Ok(None);
+    }
+
+    match config.pair_creation_mode {
+        PairCreationMode::OwnerOnly => Err(ContractError::PairCreationOwnerOnly {}),
+        PairCreationMode::Allowlist => {
+            if ALLOWED_CREATORS.has(deps.storage, sender.as_slice()) {
+                Ok(None)
+            } else {
+                Err(ContractError::CreatorNotAllowlisted {})
+            }
+        }
+        PairCreationMode::Permissionless => match (&config.creation_fee, &config.fee_treasury) {
+            (Some(fee), Some(fee_treasury)) => Ok(Some((
+                fee.to_normal(deps.api)?,
+                deps.api.addr_humanize(fee_treasury)?,
+            ))),
+            _ => Ok(None),
+        },
+    }
+}
+
+/// The native funds must exactly cover the initial liquidity and the creation fee
+fn assert_sent_funds(
+    info: &MessageInfo,
+    assets: &[Asset; 2],
+    creation_fee: Option<&Asset>,
+) -> Result<(), ContractError> {
+    if let Some(fee) = creation_fee {
+        if let AssetInfo::NativeToken { denom } = &fee.info {
+            let sent = info
+                .funds
+                .iter()
+                .find(|coin| coin.denom == *denom)
+                .map(|coin| coin.amount)
+                .unwrap_or_default();
+            if sent < fee.amount {
+                return Err(ContractError::CreationFeeNotPaid {
+                    fee: fee.to_string(),
+                });
+            }
+        }
+    }
+
+    let mut expected: Vec<Coin> = vec![];
+    for asset in assets.iter().chain(creation_fee) {
+        if let AssetInfo::NativeToken { denom } = &asset.info {
+            if asset.amount.is_zero() {
+                continue;
+            }
+
+            match expected.iter_mut().find(|coin| coin.denom == *denom) {
+                Some(coin) => coin.amount += asset.amount,
+                None => expected.push(coin(asset.amount.u128(), denom)),
+            }
+        }
+    }
+
+    let mut sent: Vec<Coin> = info
+        .funds
+        .iter()
+        .filter(|coin| !coin.amount.is_zero())
+        .cloned()
+        .collect();
+
+    expected.sort_by(|a, b| a.denom.cmp(&b.denom));
+    sent.sort_by(|a, b| a.denom.cmp(&b.denom));
+    if sent != expected {
+        return Err(StdError::generic_err(
+            "Native token balance mismatch between the argument and the transferred",
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+fn creation_fee_msg(
+    deps: Deps<TerraQuery>,
+    info: &MessageInfo,
+    fee: Asset,
+    fee_treasury: Addr,
+) -> StdResult<CosmosMsg<TerraMsg>> {
+    match &fee.info {
+        AssetInfo::NativeToken { .. } => fee.into_msg(&deps.querier, fee_treasury),
+        // pulled via allowance, TransferFrom fails when it is insufficient
+        AssetInfo::Token { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: info.sender.to_string(),
+                recipient: fee_treasury.to_string(),
+                amount: fee.amount,
+            })?,
+            funds: vec![],
+        })),
+    }
+}
+
 pub fn execute_add_native_token_decimals(
     deps: DepsMut<TerraQuery>,
     env: Env,
     info: MessageInfo,
     denom: String,
     decimals: u8,
-) -> StdResult<Response<TerraMsg>> {
+) -> Result<Response<TerraMsg>, ContractError> {
     let config: Config = CONFIG.load(deps.storage)?;
 
     // permission check
     if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
-        return Err(StdError::generic_err("unauthorized"));
-    }
-
-    let balance = query_balance(&deps.querier, env.contract.address, denom.to_string())?;
-    if balance.is_zero() {
-        return Err(StdError::generic_err(
-            "a balance greater than zero is required by the factory for verification",
-        ));
+        return Err(ContractError::Unauthorized {});
     }
 
+    assert_native_token_decimals(deps.as_ref(), &env, &denom, decimals)?;
     add_allow_native_token(deps.storage, denom.to_string(), decimals)?;
 
     Ok(Response::new().add_attributes(vec![
@@ -224,18 +632,109 @@ pub fn execute_add_native_token_decimals(
     ]))
 }
 
+pub fn execute_add_native_token_decimals_batch(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+    tokens: Vec<NativeTokenDecimals>,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut attributes = vec![attr("action", "add_allow_native_tokens")];
+    for token in tokens {
+        assert_native_token_decimals(deps.as_ref(), &env, &token.denom, token.decimals)?;
+        add_allow_native_token(deps.storage, token.denom.to_string(), token.decimals)?;
+
+        attributes.push(attr("denom", token.denom));
+        attributes.push(attr("decimals", token.decimals.to_string()));
+    }
+
+    Ok(Response::new().add_attributes(attributes))
+}
+
+pub fn execute_remove_native_token_decimals(
+    deps: DepsMut<TerraQuery>,
+    _env: Env,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if !ALLOW_NATIVE_TOKENS.has(deps.storage, denom.as_bytes()) {
+        return Err(StdError::generic_err("Native token decimals are not registered").into());
+    }
+    ALLOW_NATIVE_TOKENS.remove(deps.storage, denom.as_bytes());
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "remove_allow_native_token"),
+        ("denom", &denom),
+    ]))
+}
+
+/// Verifies the decimals of a native token against its denom metadata,
+/// or requires the factory to hold a balance of it when the chain has no metadata
+fn assert_native_token_decimals(
+    deps: Deps<TerraQuery>,
+    env: &Env,
+    denom: &str,
+    decimals: u8,
+) -> Result<(), ContractError> {
+    if let Some(hash) = denom.strip_prefix("ibc/") {
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ContractError::InvalidIbcDenom {
+                denom: denom.to_string(),
+            });
+        }
+    }
+
+    match query_denom_metadata_decimals(&deps.querier, denom.to_string())? {
+        Some(metadata_decimals) if metadata_decimals != decimals => {
+            Err(ContractError::NativeTokenDecimalsMismatch {
+                denom: denom.to_string(),
+                decimals: metadata_decimals,
+            })
+        }
+        Some(_) => Ok(()),
+        None => {
+            let balance = query_balance(
+                &deps.querier,
+                env.contract.address.clone(),
+                denom.to_string(),
+            )?;
+            if balance.is_zero() {
+                return Err(StdError::generic_err(
+                    "a balance greater than zero is required by the factory for verification",
+                )
+                .into());
+            }
+
+            Ok(())
+        }
+    }
+}
+
 pub fn execute_migrate_pair(
     deps: DepsMut<TerraQuery>,
     _env: Env,
     info: MessageInfo,
     contract: String,
     code_id: Option<u64>,
-) -> StdResult<Response<TerraMsg>> {
+) -> Result<Response<TerraMsg>, ContractError> {
     let config: Config = CONFIG.load(deps.storage)?;
 
     // permission check
     if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
-        return Err(StdError::generic_err("unauthorized"));
+        return Err(ContractError::Unauthorized {});
     }
 
     let code_id = code_id.unwrap_or(config.pair_code_id);
@@ -249,6 +748,214 @@ pub fn execute_migrate_pair(
     )
 }
 
+// Only owner can execute it
+pub fn execute_index_pairs(
+    deps: DepsMut<TerraQuery>,
+    _env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let (indexed, done) = index_pairs(deps.storage, limit)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "index_pairs"),
+        ("indexed_pairs", &indexed.to_string()),
+        ("done", &done.to_string()),
+    ]))
+}
+
+pub fn execute_migrate_pairs(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+    start_after: Option<[AssetInfo; 2]>,
+    limit: Option<u32>,
+    code_id: Option<u64>,
+    reset: Option<bool>,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let code_id = code_id.unwrap_or(config.pair_code_id);
+
+    // resume the migration to the same code id unless reset, start over for another one
+    let mut migration = match PAIR_MIGRATION.may_load(deps.storage)? {
+        Some(migration) if migration.code_id == code_id && !reset.unwrap_or(false) => migration,
+        _ => PairMigration {
+            code_id,
+            last_migrated: None,
+            migrated_count: 0,
+        },
+    };
+
+    let start_after = if let Some(start_after) = start_after {
+        Some([
+            start_after[0].to_raw(deps.api)?,
+            start_after[1].to_raw(deps.api)?,
+        ])
+    } else {
+        migration.last_migrated.clone()
+    };
+
+    let pairs: Vec<PairInfo> = read_pairs(deps.storage, deps.api, start_after, limit)?;
+
+    let mut messages: Vec<CosmosMsg<TerraMsg>> = vec![];
+    let mut skipped_pairs: Vec<String> = vec![];
+    for pair in pairs.iter() {
+        // registered pairs may be administered by another address
+        let admin = deps
+            .querier
+            .query_wasm_contract_info(&pair.contract_addr)
+            .ok()
+            .and_then(|contract_info| contract_info.admin);
+        if admin.as_deref() != Some(env.contract.address.as_str()) {
+            skipped_pairs.push(pair.contract_addr.to_string());
+            continue;
+        }
+
+        messages.push(CosmosMsg::Wasm(WasmMsg::Migrate {
+            contract_addr: pair.contract_addr.to_string(),
+            new_code_id: code_id,
+            msg: to_binary(&PairMigrateMsg {})?,
+        }));
+    }
+
+    if let Some(last) = pairs.last() {
+        migration.last_migrated = Some([
+            last.asset_infos[0].to_raw(deps.api)?,
+            last.asset_infos[1].to_raw(deps.api)?,
+        ]);
+        migration.migrated_count += messages.len() as u64;
+    }
+
+    PAIR_MIGRATION.save(deps.storage, &migration)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            ("action", "migrate_pairs"),
+            ("code_id", &code_id.to_string()),
+            ("migrated_pairs", &messages.len().to_string()),
+            ("skipped_pairs", &skipped_pairs.join(",")),
+        ])
+        .add_messages(messages))
+}
+
+pub fn execute_deregister_pair(
+    deps: DepsMut<TerraQuery>,
+    _env: Env,
+    info: MessageInfo,
+    asset_infos: [AssetInfo; 2],
+) -> Result<Response<TerraMsg>, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pair_key = pair_key(&[
+        asset_infos[0].to_raw(deps.api)?,
+        asset_infos[1].to_raw(deps.api)?,
+    ]);
+    let pair_info: PairInfoRaw = PAIRS.load(deps.storage, &pair_key)?;
+
+    remove_pair(deps.storage, &pair_key, &pair_info)?;
+    DEPRECATED_PAIRS.save(deps.storage, pair_info.contract_addr.as_slice(), &pair_info)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "deregister_pair"),
+        ("pair", &format!("{}-{}", asset_infos[0], asset_infos[1])),
+        (
+            "pair_contract_addr",
+            deps.api.addr_humanize(&pair_info.contract_addr)?.as_str(),
+        ),
+    ]))
+}
+
+pub fn execute_register_pair(
+    deps: DepsMut<TerraQuery>,
+    _env: Env,
+    info: MessageInfo,
+    pair_addr: String,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pair_addr = deps.api.addr_validate(&pair_addr)?;
+    let pair_info = query_pair_info_from_pair(&deps.querier, pair_addr.clone())?;
+    if pair_info.contract_addr != pair_addr {
+        return Err(ContractError::InvalidPairContract {});
+    }
+
+    // the liquidity token must be minted by the pair only
+    let minter = query_token_minter(
+        &deps.querier,
+        deps.api.addr_validate(&pair_info.liquidity_token)?,
+    )?;
+    if minter.map(|minter| minter.minter) != Some(pair_addr.to_string()) {
+        return Err(ContractError::InvalidPairContract {});
+    }
+
+    if pair_info.asset_infos[0] == pair_info.asset_infos[1] {
+        return Err(StdError::generic_err("same asset").into());
+    }
+
+    for asset_info in pair_info.asset_infos.iter() {
+        if BLOCKED_ASSETS.has(deps.storage, asset_info.to_raw(deps.api)?.as_bytes()) {
+            return Err(ContractError::AssetBlocked {
+                asset: asset_info.to_string(),
+            });
+        }
+    }
+
+    let raw_infos = [
+        pair_info.asset_infos[0].to_raw(deps.api)?,
+        pair_info.asset_infos[1].to_raw(deps.api)?,
+    ];
+    let pair_key = pair_key(&raw_infos);
+    if let Ok(Some(_)) = PAIRS.may_load(deps.storage, &pair_key) {
+        return Err(StdError::generic_err("Pair already exists").into());
+    }
+
+    let contract_addr = deps.api.addr_canonicalize(pair_addr.as_str())?;
+    store_pair(
+        deps.storage,
+        &pair_key,
+        &PairInfoRaw {
+            liquidity_token: deps.api.addr_canonicalize(&pair_info.liquidity_token)?,
+            contract_addr: contract_addr.clone(),
+            asset_infos: raw_infos,
+            asset_decimals: pair_info.asset_decimals,
+        },
+    )?;
+    DEPRECATED_PAIRS.remove(deps.storage, contract_addr.as_slice());
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "register_pair"),
+        (
+            "pair",
+            &format!("{}-{}", pair_info.asset_infos[0], pair_info.asset_infos[1]),
+        ),
+        ("pair_contract_addr", pair_addr.as_str()),
+        ("liquidity_token_addr", pair_info.liquidity_token.as_str()),
+    ]))
+}
+
 /// This just stores the result for future query
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut<TerraQuery>, env: Env, msg: Reply) -> StdResult<Response<TerraMsg>> {
@@ -256,8 +963,6 @@ pub fn reply(deps: DepsMut<TerraQuery>, env: Env, msg: Reply) -> StdResult<Respo
         return Err(StdError::generic_err("invalid reply msg"));
     }
 
-    let tmp_pair_info = TMP_PAIR_INFO.load(deps.storage)?;
-
     let res: MsgInstantiateContractResponse =
         Message::parse_from_bytes(msg.result.unwrap().data.unwrap().as_slice()).map_err(|_| {
             StdError::parse_err("MsgInstantiateContractResponse", "failed to parse data")
@@ -266,14 +971,22 @@ pub fn reply(deps: DepsMut<TerraQuery>, env: Env, msg: Reply) -> StdResult<Respo
     let pair_contract = res.get_contract_address();
     let pair_info = query_pair_info_from_pair(&deps.querier, Addr::unchecked(pair_contract))?;
 
+    // the pair tells which pending creation it belongs to
+    let pair_key = pair_key(&[
+        pair_info.asset_infos[0].to_raw(deps.api)?,
+        pair_info.asset_infos[1].to_raw(deps.api)?,
+    ]);
+    let tmp_pair_info = PENDING_PAIRS.load(deps.storage, &pair_key)?;
+    PENDING_PAIRS.remove(deps.storage, &pair_key);
+
     let raw_infos = [
         tmp_pair_info.assets[0].info.clone(),
         tmp_pair_info.assets[1].info.clone(),
     ];
 
-    PAIRS.save(
+    store_pair(
         deps.storage,
-        &tmp_pair_info.pair_key,
+        &pair_key,
         &PairInfoRaw {
             liquidity_token: deps.api.addr_canonicalize(&pair_info.liquidity_token)?,
             contract_addr: deps.api.addr_canonicalize(pair_contract)?,
@@ -337,7 +1050,7 @@ pub fn reply(deps: DepsMut<TerraQuery>, env: Env, msg: Reply) -> StdResult<Respo
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps<TerraQuery>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps<TerraQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::Pair { asset_infos } => to_binary(&query_pair(deps, asset_infos)?),
@@ -347,6 +1060,45 @@ pub fn query(deps: Deps<TerraQuery>, _env: Env, msg: QueryMsg) -> StdResult<Bina
         QueryMsg::NativeTokenDecimals { denom } => {
             to_binary(&query_native_token_decimal(deps, denom)?)
         }
+        QueryMsg::NativeTokenDecimalsList { start_after, limit } => {
+            to_binary(&query_native_token_decimals_list(deps, start_after, limit)?)
+        }
+        QueryMsg::PairsByAsset {
+            asset_info,
+            start_after,
+            limit,
+        } => to_binary(&query_pairs_by_asset(deps, asset_info, start_after, limit)?),
+        QueryMsg::PairCount {} => to_binary(&query_pair_count(deps)?),
+        QueryMsg::PairByLiquidityToken { liquidity_token } => {
+            to_binary(&query_pair_by_liquidity_token(deps, liquidity_token)?)
+        }
+        QueryMsg::PairByAddress { pair_addr } => {
+            to_binary(&query_pair_by_address(deps, pair_addr)?)
+        }
+        QueryMsg::AllowedCreators { start_after, limit } => {
+            to_binary(&query_allowed_creators(deps, start_after, limit)?)
+        }
+        QueryMsg::DeprecatedPairs { start_after, limit } => {
+            to_binary(&query_deprecated_pairs(deps, start_after, limit)?)
+        }
+        QueryMsg::PairMigration {} => to_binary(&query_pair_migration(deps)?),
+        QueryMsg::OutdatedPairs {
+            code_id,
+            start_after,
+            limit,
+        } => to_binary(&query_outdated_pairs(deps, code_id, start_after, limit)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
+        QueryMsg::IsBlocked { asset_info } => to_binary(&query_is_blocked(deps, asset_info)?),
+        QueryMsg::BlockedAssets { start_after, limit } => {
+            to_binary(&query_blocked_assets(deps, start_after, limit)?)
+        }
+        QueryMsg::FeeConfig { pair_addr } => to_binary(&query_fee_config(deps, pair_addr)?),
+        QueryMsg::PairFeeConfigs { start_after, limit } => {
+            to_binary(&query_pair_fee_configs(deps, start_after, limit)?)
+        }
+        QueryMsg::PredictPairAddress { asset_infos } => {
+            to_binary(&query_predict_pair_address(deps, env, asset_infos)?)
+        }
     }
 }
 
@@ -356,11 +1108,56 @@ pub fn query_config(deps: Deps<TerraQuery>) -> StdResult<ConfigResponse> {
         owner: deps.api.addr_humanize(&state.owner)?.to_string(),
         token_code_id: state.token_code_id,
         pair_code_id: state.pair_code_id,
+        pair_creation_mode: state.pair_creation_mode,
+        creation_fee: state
+            .creation_fee
+            .map(|fee| fee.to_normal(deps.api))
+            .transpose()?,
+        fee_treasury: state
+            .fee_treasury
+            .map(|addr| deps.api.addr_humanize(&addr).map(|addr| addr.to_string()))
+            .transpose()?,
+        default_fee_config: read_default_fee_config(deps.storage)?,
     };
 
     Ok(resp)
 }
 
+pub fn query_fee_config(deps: Deps<TerraQuery>, pair_addr: String) -> StdResult<FeeConfigResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pair_addr = deps.api.addr_canonicalize(&pair_addr)?;
+
+    let fee_config = match PAIR_FEE_CONFIGS.may_load(deps.storage, pair_addr.as_slice())? {
+        Some(fee_config) => fee_config,
+        None => read_default_fee_config(deps.storage)?,
+    };
+
+    Ok(FeeConfigResponse {
+        total_fee_bps: fee_config.total_fee_bps,
+        protocol_fee_bps: fee_config.protocol_fee_bps,
+        fee_treasury: config
+            .fee_treasury
+            .map(|addr| deps.api.addr_humanize(&addr).map(|addr| addr.to_string()))
+            .transpose()?,
+    })
+}
+
+pub fn query_pair_fee_configs(
+    deps: Deps<TerraQuery>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<PairFeeConfigsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.addr_canonicalize(&start_after)?)
+    } else {
+        None
+    };
+
+    let fee_configs = read_pair_fee_configs(deps.storage, deps.api, start_after, limit)?;
+
+    Ok(PairFeeConfigsResponse { fee_configs })
+}
+
 pub fn query_pair(deps: Deps<TerraQuery>, asset_infos: [AssetInfo; 2]) -> StdResult<PairInfo> {
     let pair_key = pair_key(&[
         asset_infos[0].to_raw(deps.api)?,
@@ -385,9 +1182,229 @@ pub fn query_pairs(
     };
 
     let pairs: Vec<PairInfo> = read_pairs(deps.storage, deps.api, start_after, limit)?;
-    let resp = PairsResponse { pairs };
 
-    Ok(resp)
+    pairs_response(deps, pairs)
+}
+
+pub fn query_pairs_by_asset(
+    deps: Deps<TerraQuery>,
+    asset_info: AssetInfo,
+    start_after: Option<[AssetInfo; 2]>,
+    limit: Option<u32>,
+) -> StdResult<PairsResponse> {
+    let asset_info = asset_info.to_raw(deps.api)?;
+    let start_after = if let Some(start_after) = start_after {
+        Some([
+            start_after[0].to_raw(deps.api)?,
+            start_after[1].to_raw(deps.api)?,
+        ])
+    } else {
+        None
+    };
+
+    let pairs: Vec<PairInfo> =
+        read_pairs_by_asset(deps.storage, deps.api, &asset_info, start_after, limit)?;
+
+    pairs_response(deps, pairs)
+}
+
+pub fn query_pair_count(deps: Deps<TerraQuery>) -> StdResult<PairCountResponse> {
+    Ok(PairCountResponse {
+        count: count_pairs(deps.storage)?,
+    })
+}
+
+pub fn query_pair_by_liquidity_token(
+    deps: Deps<TerraQuery>,
+    liquidity_token: String,
+) -> StdResult<PairInfo> {
+    let liquidity_token = deps.api.addr_canonicalize(&liquidity_token)?;
+    let pair_key = PAIRS_BY_LIQUIDITY_TOKEN.load(deps.storage, liquidity_token.as_slice())?;
+    let pair_info: PairInfoRaw = PAIRS.load(deps.storage, &pair_key)?;
+    pair_info.to_normal(deps.api)
+}
+
+pub fn query_pair_by_address(deps: Deps<TerraQuery>, pair_addr: String) -> StdResult<PairInfo> {
+    let pair_addr = deps.api.addr_canonicalize(&pair_addr)?;
+    let pair_key = PAIRS_BY_ADDRESS.load(deps.storage, pair_addr.as_slice())?;
+    let pair_info: PairInfoRaw = PAIRS.load(deps.storage, &pair_key)?;
+    pair_info.to_normal(deps.api)
+}
+
+pub fn query_allowed_creators(
+    deps: Deps<TerraQuery>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllowedCreatorsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.addr_canonicalize(&start_after)?)
+    } else {
+        None
+    };
+
+    let creators = read_allowed_creators(deps.storage, deps.api, start_after, limit)?;
+
+    Ok(AllowedCreatorsResponse { creators })
+}
+
+pub fn query_deprecated_pairs(
+    deps: Deps<TerraQuery>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<PairsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.addr_canonicalize(&start_after)?)
+    } else {
+        None
+    };
+
+    let pairs: Vec<PairInfo> = read_deprecated_pairs(deps.storage, deps.api, start_after, limit)?;
+
+    pairs_response(deps, pairs)
+}
+
+/// Flags the pairs that contain blocked assets
+fn pairs_response(deps: Deps<TerraQuery>, pairs: Vec<PairInfo>) -> StdResult<PairsResponse> {
+    let mut blocked_pairs: Vec<String> = vec![];
+    for pair in pairs.iter() {
+        for asset_info in pair.asset_infos.iter() {
+            if BLOCKED_ASSETS.has(deps.storage, asset_info.to_raw(deps.api)?.as_bytes()) {
+                blocked_pairs.push(pair.contract_addr.to_string());
+                break;
+            }
+        }
+    }
+
+    Ok(PairsResponse {
+        pairs,
+        blocked_pairs,
+    })
+}
+
+pub fn query_is_blocked(
+    deps: Deps<TerraQuery>,
+    asset_info: AssetInfo,
+) -> StdResult<IsBlockedResponse> {
+    let asset_info = asset_info.to_raw(deps.api)?;
+
+    Ok(IsBlockedResponse {
+        blocked: BLOCKED_ASSETS.has(deps.storage, asset_info.as_bytes()),
+    })
+}
+
+pub fn query_blocked_assets(
+    deps: Deps<TerraQuery>,
+    start_after: Option<AssetInfo>,
+    limit: Option<u32>,
+) -> StdResult<BlockedAssetsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(start_after.to_raw(deps.api)?)
+    } else {
+        None
+    };
+
+    let assets = read_blocked_assets(deps.storage, deps.api, start_after, limit)?;
+
+    Ok(BlockedAssetsResponse { assets })
+}
+
+pub fn query_pair_migration(deps: Deps<TerraQuery>) -> StdResult<PairMigrationResponse> {
+    let migration = PAIR_MIGRATION.load(deps.storage)?;
+    let last_migrated = if let Some(last_migrated) = migration.last_migrated {
+        Some([
+            last_migrated[0].to_normal(deps.api)?,
+            last_migrated[1].to_normal(deps.api)?,
+        ])
+    } else {
+        None
+    };
+
+    Ok(PairMigrationResponse {
+        code_id: migration.code_id,
+        last_migrated,
+        migrated_count: migration.migrated_count,
+    })
+}
+
+pub fn query_predict_pair_address(
+    deps: Deps<TerraQuery>,
+    env: Env,
+    asset_infos: [AssetInfo; 2],
+) -> StdResult<PredictPairAddressResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let checksum = deps
+        .querier
+        .query_wasm_code_info(config.pair_code_id)?
+        .checksum;
+
+    let pair_key = pair_key(&[
+        asset_infos[0].to_raw(deps.api)?,
+        asset_infos[1].to_raw(deps.api)?,
+    ]);
+    let contract_addr = instantiate2_address(
+        checksum.as_slice(),
+        &deps.api.addr_canonicalize(env.contract.address.as_str())?,
+        pair_salt(&pair_key).as_slice(),
+    )
+    .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    Ok(PredictPairAddressResponse {
+        contract_addr: deps.api.addr_humanize(&contract_addr)?.to_string(),
+    })
+}
+
+pub fn query_ownership_proposal(deps: Deps<TerraQuery>) -> StdResult<OwnershipProposalResponse> {
+    let proposal = OWNERSHIP_PROPOSAL.load(deps.storage)?;
+
+    Ok(OwnershipProposalResponse {
+        owner: deps.api.addr_humanize(&proposal.owner)?.to_string(),
+        expires_at: proposal.expires_at,
+    })
+}
+
+pub fn query_outdated_pairs(
+    deps: Deps<TerraQuery>,
+    code_id: Option<u64>,
+    start_after: Option<[AssetInfo; 2]>,
+    limit: Option<u32>,
+) -> StdResult<OutdatedPairsResponse> {
+    let code_id = match code_id {
+        Some(code_id) => code_id,
+        None => CONFIG.load(deps.storage)?.pair_code_id,
+    };
+
+    let start_after = if let Some(start_after) = start_after {
+        Some([
+            start_after[0].to_raw(deps.api)?,
+            start_after[1].to_raw(deps.api)?,
+        ])
+    } else {
+        None
+    };
+
+    let pairs: Vec<PairInfo> = read_pairs(deps.storage, deps.api, start_after, limit)?;
+    let last_checked = pairs.last().map(|pair| pair.asset_infos.clone());
+
+    let mut outdated: Vec<OutdatedPair> = vec![];
+    for pair in pairs {
+        let contract_info = deps.querier.query_wasm_contract_info(&pair.contract_addr)?;
+        if contract_info.code_id == code_id {
+            continue;
+        }
+
+        let version = query_contract_version(&deps.querier, Addr::unchecked(&pair.contract_addr))?;
+        outdated.push(OutdatedPair {
+            asset_infos: pair.asset_infos,
+            contract_addr: pair.contract_addr,
+            code_id: contract_info.code_id,
+            version: version.map(|version| version.version),
+        });
+    }
+
+    Ok(OutdatedPairsResponse {
+        pairs: outdated,
+        last_checked,
+    })
 }
 
 pub fn query_native_token_decimal(
@@ -399,19 +1416,39 @@ pub fn query_native_token_decimal(
     Ok(NativeTokenDecimalsResponse { decimals })
 }
 
+pub fn query_native_token_decimals_list(
+    deps: Deps<TerraQuery>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<NativeTokenDecimalsListResponse> {
+    let tokens = read_allow_native_tokens(deps.storage, start_after, limit)?;
+
+    Ok(NativeTokenDecimalsListResponse { tokens })
+}
+
 const TARGET_CONTRACT_VERSION: &str = "0.0.0";
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(
-    deps: DepsMut<TerraQuery>,
+    mut deps: DepsMut<TerraQuery>,
     _env: Env,
     _msg: MigrateMsg,
 ) -> StdResult<Response<TerraMsg>> {
     migrate_version(
-        deps,
+        deps.branch(),
         TARGET_CONTRACT_VERSION,
         CONTRACT_NAME,
         CONTRACT_VERSION,
     )?;
 
+    // pending pairs are tracked per pair key now
+    deps.storage.remove(b"tmp_pair_info");
+
+    // the pairs created before the asset and reverse lookup indexes existed
+    // are indexed and counted by the owner through IndexPairs
+    if !PAIR_COUNT.exists(deps.storage) {
+        PAIR_COUNT.save(deps.storage, &0)?;
+        PAIR_INDEXING.save(deps.storage, &vec![])?;
+    }
+
     Ok(Response::default())
 }
@@ -0,0 +1,50 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Pair creation is restricted to the owner")]
+    PairCreationOwnerOnly {},
+
+    #[error("Creator is not on the allowlist")]
+    CreatorNotAllowlisted {},
+
+    #[error("Pair creation fee not paid ({fee} required)")]
+    CreationFeeNotPaid { fee: String },
+
+    #[error("Initial liquidity must be provided for both assets or none")]
+    InvalidInitialLiquidity {},
+
+    #[error("Pair contract and its liquidity token do not match")]
+    InvalidPairContract {},
+
+    #[error("Fee treasury must be set to charge a creation or protocol fee")]
+    FeeTreasuryNotSet {},
+
+    #[error("Invalid fee config: the total fee cannot exceed {max} bps and the protocol fee cannot exceed the total fee")]
+    InvalidFeeConfig { max: u16 },
+
+    #[error("Decimals of {denom} do not match its denom metadata ({decimals})")]
+    NativeTokenDecimalsMismatch { denom: String, decimals: u8 },
+
+    #[error("Invalid IBC denom: {denom}")]
+    InvalidIbcDenom { denom: String },
+
+    #[error("Asset {asset} is blocked")]
+    AssetBlocked { asset: String },
+
+    #[error("Ownership proposal not found")]
+    OwnershipProposalNotFound {},
+
+    #[error("Ownership proposal expired")]
+    OwnershipProposalExpired {},
+
+    #[error("Ownership proposal cannot expire later than {max} seconds")]
+    InvalidOwnershipProposalTtl { max: u64 },
+}
@@ -1,8 +1,11 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use classic_cyberswap::asset::{AssetInfoRaw, AssetRaw, PairInfo, PairInfoRaw};
-use cosmwasm_std::{Addr, Api, CanonicalAddr, Order, StdResult, Storage};
+use classic_cyberswap::asset::{AssetInfo, AssetInfoRaw, AssetRaw, PairInfo, PairInfoRaw};
+use classic_cyberswap::factory::{
+    FeeConfig, NativeTokenDecimals, PairCreationMode, PairFeeConfig, DEFAULT_TOTAL_FEE_BPS,
+};
+use cosmwasm_std::{Addr, Api, CanonicalAddr, Empty, Order, StdResult, Storage};
 use cw_storage_plus::{Bound, Item, Map};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -10,20 +13,67 @@ pub struct Config {
     pub owner: CanonicalAddr,
     pub pair_code_id: u64,
     pub token_code_id: u64,
+    #[serde(default)]
+    pub pair_creation_mode: PairCreationMode,
+    #[serde(default)]
+    pub creation_fee: Option<AssetRaw>,
+    #[serde(default)]
+    pub fee_treasury: Option<CanonicalAddr>,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct OwnershipProposal {
+    pub owner: CanonicalAddr,
+    pub expires_at: u64,
+}
+
+pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PairMigration {
+    pub code_id: u64,
+    pub last_migrated: Option<[AssetInfoRaw; 2]>,
+    pub migrated_count: u64,
+}
+
+pub const PAIR_MIGRATION: Item<PairMigration> = Item::new("pair_migration");
+
+// key : asset info / value: asset info
+pub const BLOCKED_ASSETS: Map<&[u8], AssetInfoRaw> = Map::new("blocked_assets");
+
+// key : creator / value: empty
+pub const ALLOWED_CREATORS: Map<&[u8], Empty> = Map::new("allowed_creators");
+
+// fee of the pairs without an override
+pub const DEFAULT_FEE_CONFIG: Item<FeeConfig> = Item::new("default_fee_config");
+
+// key : pair contract / value: fee config
+pub const PAIR_FEE_CONFIGS: Map<&[u8], FeeConfig> = Map::new("pair_fee_configs");
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct TmpPairInfo {
-    pub pair_key: Vec<u8>,
     pub assets: [AssetRaw; 2],
     pub asset_decimals: [u8; 2],
     pub sender: Addr,
 }
 
-pub const TMP_PAIR_INFO: Item<TmpPairInfo> = Item::new("tmp_pair_info");
+// key : pair key / value: pair info until the creation reply
+pub const PENDING_PAIRS: Map<&[u8], TmpPairInfo> = Map::new("pending_pairs");
 pub const PAIRS: Map<&[u8], PairInfoRaw> = Map::new("pair_info");
+// number of indexed pairs in PAIRS
+pub const PAIR_COUNT: Item<u64> = Item::new("pair_count");
+// last pair key indexed by IndexPairs, kept until the pairs stored
+// before the indexes existed are all indexed
+pub const PAIR_INDEXING: Item<Vec<u8>> = Item::new("pair_indexing");
+// key : (asset info, pair key) / value: empty
+pub const PAIRS_BY_ASSET: Map<(&[u8], &[u8]), Empty> = Map::new("pairs_by_asset");
+// key : liquidity token / value: pair key
+pub const PAIRS_BY_LIQUIDITY_TOKEN: Map<&[u8], Vec<u8>> = Map::new("pairs_by_liquidity_token");
+// key : pair contract / value: pair key
+pub const PAIRS_BY_ADDRESS: Map<&[u8], Vec<u8>> = Map::new("pairs_by_address");
+// key : pair contract / value: pair info
+pub const DEPRECATED_PAIRS: Map<&[u8], PairInfoRaw> = Map::new("deprecated_pairs");
 
 pub fn pair_key(asset_infos: &[AssetInfoRaw; 2]) -> Vec<u8> {
     let mut asset_infos = asset_infos.to_vec();
@@ -54,6 +104,217 @@ pub fn read_pairs(
         .collect::<StdResult<Vec<PairInfo>>>()
 }
 
+pub fn read_pairs_by_asset(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    asset_info: &AssetInfoRaw,
+    start_after: Option<[AssetInfoRaw; 2]>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PairInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after).map(Bound::ExclusiveRaw);
+
+    PAIRS_BY_ASSET
+        .prefix(asset_info.as_bytes())
+        .keys_raw(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|pair_key| PAIRS.load(storage, &pair_key)?.to_normal(api))
+        .collect::<StdResult<Vec<PairInfo>>>()
+}
+
+pub fn count_pairs(storage: &dyn Storage) -> StdResult<u64> {
+    let count = PAIR_COUNT.may_load(storage)?.unwrap_or_default();
+    let last_indexed = match PAIR_INDEXING.may_load(storage)? {
+        Some(last_indexed) => last_indexed,
+        None => return Ok(count),
+    };
+
+    // add the pairs the backfill has not reached yet
+    let start = (!last_indexed.is_empty()).then_some(Bound::ExclusiveRaw(last_indexed));
+    let mut unindexed = 0u64;
+    for item in PAIRS.range(storage, start, None, Order::Ascending) {
+        let (_, pair_info) = item?;
+        if !PAIRS_BY_ADDRESS.has(storage, pair_info.contract_addr.as_slice()) {
+            unindexed += 1;
+        }
+    }
+
+    Ok(count + unindexed)
+}
+
+/// Indexes a page of the pairs stored before the indexes existed,
+/// returns the number of pairs indexed and whether the backfill is done
+pub fn index_pairs(storage: &mut dyn Storage, limit: Option<u32>) -> StdResult<(u64, bool)> {
+    let last_indexed = match PAIR_INDEXING.may_load(storage)? {
+        Some(last_indexed) => last_indexed,
+        None => return Ok((0, true)),
+    };
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = (!last_indexed.is_empty()).then_some(Bound::ExclusiveRaw(last_indexed));
+    let pairs = PAIRS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut indexed = 0u64;
+    for (pair_key, pair_info) in pairs.iter() {
+        if !PAIRS_BY_ADDRESS.has(storage, pair_info.contract_addr.as_slice()) {
+            index_pair(storage, pair_key, pair_info)?;
+            indexed += 1;
+        }
+    }
+    let count = PAIR_COUNT.may_load(storage)?.unwrap_or_default();
+    PAIR_COUNT.save(storage, &(count + indexed))?;
+
+    let done = pairs.len() < limit;
+    match pairs.last() {
+        Some((pair_key, _)) if !done => PAIR_INDEXING.save(storage, pair_key)?,
+        _ => PAIR_INDEXING.remove(storage),
+    }
+
+    Ok((indexed, done))
+}
+
+/// Stores the pair and indexes it under both of its assets,
+/// its liquidity token and its contract address
+pub fn store_pair(
+    storage: &mut dyn Storage,
+    pair_key: &[u8],
+    pair_info: &PairInfoRaw,
+) -> StdResult<()> {
+    if !PAIRS_BY_ADDRESS.has(storage, pair_info.contract_addr.as_slice()) {
+        let count = PAIR_COUNT.may_load(storage)?.unwrap_or_default();
+        PAIR_COUNT.save(storage, &(count + 1))?;
+    }
+    PAIRS.save(storage, pair_key, pair_info)?;
+    index_pair(storage, pair_key, pair_info)
+}
+
+fn index_pair(
+    storage: &mut dyn Storage,
+    pair_key: &[u8],
+    pair_info: &PairInfoRaw,
+) -> StdResult<()> {
+    for asset_info in pair_info.asset_infos.iter() {
+        PAIRS_BY_ASSET.save(storage, (asset_info.as_bytes(), pair_key), &Empty {})?;
+    }
+
+    PAIRS_BY_LIQUIDITY_TOKEN.save(
+        storage,
+        pair_info.liquidity_token.as_slice(),
+        &pair_key.to_vec(),
+    )?;
+    PAIRS_BY_ADDRESS.save(
+        storage,
+        pair_info.contract_addr.as_slice(),
+        &pair_key.to_vec(),
+    )
+}
+
+pub fn read_allowed_creators(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|addr| Bound::ExclusiveRaw(addr.to_vec()));
+
+    ALLOWED_CREATORS
+        .keys_raw(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|creator| Ok(api.addr_humanize(&creator.into())?.to_string()))
+        .collect::<StdResult<Vec<String>>>()
+}
+
+pub fn read_blocked_assets(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    start_after: Option<AssetInfoRaw>,
+    limit: Option<u32>,
+) -> StdResult<Vec<AssetInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|asset_info| Bound::ExclusiveRaw(asset_info.as_bytes().to_vec()));
+
+    BLOCKED_ASSETS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, asset_info) = item?;
+            asset_info.to_normal(api)
+        })
+        .collect::<StdResult<Vec<AssetInfo>>>()
+}
+
+pub fn read_default_fee_config(storage: &dyn Storage) -> StdResult<FeeConfig> {
+    Ok(DEFAULT_FEE_CONFIG.may_load(storage)?.unwrap_or(FeeConfig {
+        total_fee_bps: DEFAULT_TOTAL_FEE_BPS,
+        protocol_fee_bps: 0,
+    }))
+}
+
+pub fn read_pair_fee_configs(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PairFeeConfig>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|addr| Bound::ExclusiveRaw(addr.to_vec()));
+
+    PAIR_FEE_CONFIGS
+        .range_raw(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (pair_addr, fee_config) = item?;
+            Ok(PairFeeConfig {
+                pair_addr: api.addr_humanize(&pair_addr.into())?.to_string(),
+                fee_config,
+            })
+        })
+        .collect::<StdResult<Vec<PairFeeConfig>>>()
+}
+
+/// Removes the pair and all of its indexes
+pub fn remove_pair(
+    storage: &mut dyn Storage,
+    pair_key: &[u8],
+    pair_info: &PairInfoRaw,
+) -> StdResult<()> {
+    if PAIRS_BY_ADDRESS.has(storage, pair_info.contract_addr.as_slice()) {
+        let count = PAIR_COUNT.may_load(storage)?.unwrap_or_default();
+        PAIR_COUNT.save(storage, &count.saturating_sub(1))?;
+    }
+    PAIRS.remove(storage, pair_key);
+    for asset_info in pair_info.asset_infos.iter() {
+        PAIRS_BY_ASSET.remove(storage, (asset_info.as_bytes(), pair_key));
+    }
+
+    PAIRS_BY_LIQUIDITY_TOKEN.remove(storage, pair_info.liquidity_token.as_slice());
+    PAIRS_BY_ADDRESS.remove(storage, pair_info.contract_addr.as_slice());
+    Ok(())
+}
+
+pub fn read_deprecated_pairs(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PairInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|addr| Bound::ExclusiveRaw(addr.to_vec()));
+
+    DEPRECATED_PAIRS
+        .range_raw(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, v) = item?;
+            v.to_normal(api)
+        })
+        .collect::<StdResult<Vec<PairInfo>>>()
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(start_after: Option<[AssetInfoRaw; 2]>) -> Option<Vec<u8>> {
     start_after.map(|asset_infos| {
@@ -79,6 +340,27 @@ pub fn add_allow_native_token(
     ALLOW_NATIVE_TOKENS.save(storage, denom.as_bytes(), &decimals)
 }
 
+pub fn read_allow_native_tokens(
+    storage: &dyn Storage,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<NativeTokenDecimals>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|denom| Bound::ExclusiveRaw(denom.into_bytes()));
+
+    ALLOW_NATIVE_TOKENS
+        .range_raw(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (denom, decimals) = item?;
+            Ok(NativeTokenDecimals {
+                denom: String::from_utf8(denom)?,
+                decimals,
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod allow_native_token {
 
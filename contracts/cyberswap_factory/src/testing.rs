@@ -1,13 +1,19 @@
-use classic_bindings::TerraQuery;
+use classic_bindings::{TerraMsg, TerraQuery};
 
-use crate::contract::{execute, instantiate, query, reply};
+use crate::contract::{execute, instantiate, migrate, query, reply};
+use crate::error::ContractError;
 use classic_cyberswap::mock_querier::{mock_dependencies, WasmMockQuerier};
 
-use crate::state::{pair_key, TmpPairInfo, TMP_PAIR_INFO};
+use crate::contract::pair_salt;
+use crate::state::{pair_key, store_pair, TmpPairInfo, PAIRS, PENDING_PAIRS};
 
-use classic_cyberswap::asset::{Asset, AssetInfo, PairInfo};
+use classic_cyberswap::asset::{Asset, AssetInfo, PairInfo, PairInfoRaw};
 use classic_cyberswap::factory::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, NativeTokenDecimalsResponse, QueryMsg,
+    AllowedCreatorsResponse, BlockedAssetsResponse, ConfigResponse, ExecuteMsg, FeeConfig,
+    FeeConfigResponse, InstantiateMsg, IsBlockedResponse, MigrateMsg, NativeTokenDecimals,
+    NativeTokenDecimalsListResponse, NativeTokenDecimalsResponse, OutdatedPair,
+    OutdatedPairsResponse, OwnershipProposalResponse, PairCountResponse, PairCreationMode,
+    PairFeeConfig, PairFeeConfigsResponse, PairMigrationResponse, PairsResponse, QueryMsg,
 };
 use classic_cyberswap::pair::{
     ExecuteMsg as PairExecuteMsg, InstantiateMsg as PairInstantiateMsg,
@@ -15,9 +21,10 @@ use classic_cyberswap::pair::{
 };
 use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, coin, coins, from_binary, to_binary, Addr, CosmosMsg, OwnedDeps, Reply, ReplyOn,
-    Response, StdError, SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
+    attr, coin, coins, from_binary, to_binary, Addr, BankMsg, CosmosMsg, HexBinary, OwnedDeps,
+    Reply, ReplyOn, Response, StdError, SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
 };
+use cw2::set_contract_version;
 use cw20::Cw20ExecuteMsg;
 
 #[test]
@@ -55,31 +62,20 @@ fn update_config() {
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    // update owner
-    let info = mock_info("addr0000", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner: Some("addr0001".to_string()),
-        pair_code_id: None,
-        token_code_id: None,
-    };
-
-    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-    assert_eq!(0, res.messages.len());
-
-    // it worked, let's query the state
-    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
-    let config_res: ConfigResponse = from_binary(&query_res).unwrap();
-    assert_eq!(123u64, config_res.token_code_id);
-    assert_eq!(321u64, config_res.pair_code_id);
-    assert_eq!("addr0001".to_string(), config_res.owner);
-
     // update left items
     let env = mock_env();
-    let info = mock_info("addr0001", &[]);
+    let info = mock_info("addr0000", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner: None,
         pair_code_id: Some(100u64),
         token_code_id: Some(200u64),
+        pair_creation_mode: None,
+        creation_fee: None,
+        fee_treasury: None,
+        add_allowed_creators: None,
+        remove_allowed_creators: None,
+        default_fee_config: None,
+        pair_fee_configs: None,
+        remove_pair_fee_configs: None,
     };
 
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
@@ -90,24 +86,193 @@ fn update_config() {
     let config_res: ConfigResponse = from_binary(&query_res).unwrap();
     assert_eq!(200u64, config_res.token_code_id);
     assert_eq!(100u64, config_res.pair_code_id);
-    assert_eq!("addr0001".to_string(), config_res.owner);
+    assert_eq!("addr0000".to_string(), config_res.owner);
 
     // Unauthorized err
     let env = mock_env();
-    let info = mock_info("addr0000", &[]);
+    let info = mock_info("addr0001", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner: None,
         pair_code_id: None,
         token_code_id: None,
+        pair_creation_mode: None,
+        creation_fee: None,
+        fee_treasury: None,
+        add_allowed_creators: None,
+        remove_allowed_creators: None,
+        default_fee_config: None,
+        pair_fee_configs: None,
+        remove_pair_fee_configs: None,
     };
 
     let res = execute(deps.as_mut(), env, info, msg);
     match res {
-        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "unauthorized"),
+        Err(ContractError::Unauthorized {}) => {}
         _ => panic!("Must return unauthorized error"),
     }
 }
 
+#[test]
+fn transfer_ownership() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let propose_msg = ExecuteMsg::ProposeNewOwner {
+        owner: "addr0001".to_string(),
+        expires_in: 100u64,
+    };
+
+    // only the owner can propose
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        propose_msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    // the proposal cannot live forever
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ProposeNewOwner {
+            owner: "addr0001".to_string(),
+            expires_in: 14 * 24 * 60 * 60 + 1,
+        },
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::InvalidOwnershipProposalTtl {
+            max: 14 * 24 * 60 * 60
+        })
+    );
+
+    let env = mock_env();
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        propose_msg,
+    )
+    .unwrap();
+    let expires_at = env.block.time.seconds() + 100;
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "propose_new_owner"),
+            attr("new_owner", "addr0001"),
+            attr("expires_at", expires_at.to_string()),
+        ]
+    );
+
+    let res: OwnershipProposalResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::OwnershipProposal {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        res,
+        OwnershipProposalResponse {
+            owner: "addr0001".to_string(),
+            expires_at,
+        }
+    );
+
+    // the proposal does not change the owner yet
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.owner, "addr0000".to_string());
+
+    // only the proposed owner can claim
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0002", &[]),
+        ExecuteMsg::ClaimOwnership {},
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    // expired proposal
+    let mut expired_env = mock_env();
+    expired_env.block.time = expired_env.block.time.plus_seconds(101);
+    let res = execute(
+        deps.as_mut(),
+        expired_env,
+        mock_info("addr0001", &[]),
+        ExecuteMsg::ClaimOwnership {},
+    );
+    assert_eq!(res, Err(ContractError::OwnershipProposalExpired {}));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::ClaimOwnership {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_ownership"),
+            attr("previous_owner", "addr0000"),
+            attr("new_owner", "addr0001"),
+        ]
+    );
+
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.owner, "addr0001".to_string());
+
+    // the proposal is consumed
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::ClaimOwnership {},
+    );
+    assert_eq!(res, Err(ContractError::OwnershipProposalNotFound {}));
+}
+
+#[test]
+fn drop_ownership_proposal() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ProposeNewOwner {
+            owner: "addr0001".to_string(),
+            expires_in: 100u64,
+        },
+    )
+    .unwrap();
+
+    // only the owner can drop the proposal
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::DropOwnershipProposal {},
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::DropOwnershipProposal {},
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::ClaimOwnership {},
+    );
+    assert_eq!(res, Err(ContractError::OwnershipProposalNotFound {}));
+}
+
 fn init(
     mut deps: OwnedDeps<MockStorage, MockApi, WasmMockQuerier, TerraQuery>,
 ) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier, TerraQuery> {
@@ -154,6 +319,11 @@ fn create_pair() {
         assets: assets.clone(),
     };
 
+    let raw_infos = [
+        assets[0].info.to_raw(deps.as_ref().api).unwrap(),
+        assets[1].info.to_raw(deps.as_ref().api).unwrap(),
+    ];
+
     let env = mock_env();
     let info = mock_info("addr0000", &[]);
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
@@ -170,7 +340,7 @@ fn create_pair() {
             id: 1,
             gas_limit: None,
             reply_on: ReplyOn::Success,
-            msg: WasmMsg::Instantiate {
+            msg: WasmMsg::Instantiate2 {
                 msg: to_binary(&PairInstantiateMsg {
                     asset_infos: [
                         AssetInfo::NativeToken {
@@ -188,6 +358,7 @@ fn create_pair() {
                 funds: vec![],
                 label: "pair".to_string(),
                 admin: Some(MOCK_CONTRACT_ADDR.to_string()),
+                salt: pair_salt(&pair_key(&raw_infos)),
             }
             .into()
         },]
@@ -198,16 +369,12 @@ fn create_pair() {
         assets[1].to_raw(deps.as_ref().api).unwrap(),
     ];
 
-    let raw_infos = [
-        assets[0].info.to_raw(deps.as_ref().api).unwrap(),
-        assets[1].info.to_raw(deps.as_ref().api).unwrap(),
-    ];
-
     assert_eq!(
-        TMP_PAIR_INFO.load(&deps.storage).unwrap(),
+        PENDING_PAIRS
+            .load(&deps.storage, &pair_key(&raw_infos))
+            .unwrap(),
         TmpPairInfo {
             assets: raw_assets,
-            pair_key: pair_key(&raw_infos),
             sender: Addr::unchecked("addr0000"),
             asset_decimals: [6u8, 8u8]
         }
@@ -245,6 +412,11 @@ fn create_pair_native_token_and_ibc_token() {
         assets: assets.clone(),
     };
 
+    let raw_infos = [
+        assets[0].info.to_raw(deps.as_ref().api).unwrap(),
+        assets[1].info.to_raw(deps.as_ref().api).unwrap(),
+    ];
+
     let env = mock_env();
     let info = mock_info("addr0000", &[]);
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
@@ -258,7 +430,7 @@ fn create_pair_native_token_and_ibc_token() {
             id: 1,
             gas_limit: None,
             reply_on: ReplyOn::Success,
-            msg: WasmMsg::Instantiate {
+            msg: WasmMsg::Instantiate2 {
                 msg: to_binary(&PairInstantiateMsg {
                     asset_infos: [
                         AssetInfo::NativeToken {
@@ -276,6 +448,7 @@ fn create_pair_native_token_and_ibc_token() {
                 funds: vec![],
                 label: "pair".to_string(),
                 admin: Some(MOCK_CONTRACT_ADDR.to_string()),
+                salt: pair_salt(&pair_key(&raw_infos)),
             }
             .into()
         },]
@@ -286,16 +459,12 @@ fn create_pair_native_token_and_ibc_token() {
         assets[1].to_raw(deps.as_ref().api).unwrap(),
     ];
 
-    let raw_infos = [
-        assets[0].info.to_raw(deps.as_ref().api).unwrap(),
-        assets[1].info.to_raw(deps.as_ref().api).unwrap(),
-    ];
-
     assert_eq!(
-        TMP_PAIR_INFO.load(&deps.storage).unwrap(),
+        PENDING_PAIRS
+            .load(&deps.storage, &pair_key(&raw_infos))
+            .unwrap(),
         TmpPairInfo {
             assets: raw_assets,
-            pair_key: pair_key(&raw_infos),
             sender: Addr::unchecked("addr0000"),
             asset_decimals: [6u8, 6u8]
         }
@@ -328,7 +497,9 @@ fn fail_to_create_same_pair() {
     let info = mock_info("addr0000", &[]);
 
     match execute(deps.as_mut(), env, info, msg).unwrap_err() {
-        StdError::GenericErr { msg, .. } => assert_eq!(msg, "same asset".to_string()),
+        ContractError::Std(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "same asset".to_string())
+        }
         _ => panic!("Must return generic error"),
     }
 }
@@ -362,7 +533,9 @@ fn fail_to_create_pair_with_unknown_denom() {
     let info = mock_info("addr0000", &[]);
 
     match execute(deps.as_mut(), env, info, msg).unwrap_err() {
-        StdError::GenericErr { msg, .. } => assert_eq!(msg, "asset1 is invalid".to_string()),
+        ContractError::Std(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "asset1 is invalid".to_string())
+        }
         _ => panic!("Must return generic error"),
     }
 }
@@ -396,7 +569,9 @@ fn fail_to_create_pair_with_unknown_token() {
     let info = mock_info("addr0000", &[]);
 
     match execute(deps.as_mut(), env, info, msg).unwrap_err() {
-        StdError::GenericErr { msg, .. } => assert_eq!(msg, "asset2 is invalid".to_string()),
+        ContractError::Std(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "asset2 is invalid".to_string())
+        }
         _ => panic!("Must return generic error"),
     }
 }
@@ -439,12 +614,12 @@ fn reply_only_create_pair() {
     ];
 
     let pair_key = pair_key(&raw_infos);
-    TMP_PAIR_INFO
+    PENDING_PAIRS
         .save(
             &mut deps.storage,
+            &pair_key,
             &TmpPairInfo {
                 assets: raw_assets,
-                pair_key,
                 sender: Addr::unchecked("addr0000"),
                 asset_decimals: [8u8, 8u8],
             },
@@ -490,6 +665,24 @@ fn reply_only_create_pair() {
         res.attributes[1],
         attr("liquidity_token_addr", "liquidity0000")
     );
+
+    let res: PairsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairsByAsset {
+                asset_info: AssetInfo::Token {
+                    contract_addr: "asset0001".to_string(),
+                },
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.pairs.len(), 1);
+    assert_eq!(res.pairs[0].contract_addr, "0000".to_string());
 }
 
 #[test]
@@ -530,12 +723,12 @@ fn reply_create_pair_with_provide() {
     ];
 
     let pair_key = pair_key(&raw_infos);
-    TMP_PAIR_INFO
+    PENDING_PAIRS
         .save(
             &mut deps.storage,
+            &pair_key,
             &TmpPairInfo {
                 assets: raw_assets,
-                pair_key,
                 sender: Addr::unchecked("addr0000"),
                 asset_decimals: [18u8, 8u8],
             },
@@ -704,7 +897,7 @@ fn failed_add_allow_native_token_with_non_admin() {
 
     assert_eq!(
         execute(deps.as_mut(), mock_env(), info, msg),
-        Err(StdError::generic_err("unauthorized"))
+        Err(ContractError::Unauthorized {})
     );
 }
 
@@ -722,9 +915,234 @@ fn failed_add_allow_native_token_with_zero_factory_balance() {
 
     assert_eq!(
         execute(deps.as_mut(), mock_env(), info, msg),
-        Err(StdError::generic_err(
+        Err(ContractError::Std(StdError::generic_err(
+            "a balance greater than zero is required by the factory for verification",
+        )))
+    );
+}
+
+const IBC_DENOM: &str = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+#[test]
+fn add_allow_native_token_with_denom_metadata() {
+    // the factory holds no balance of the denom
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+    deps.querier
+        .with_denom_metadata(&[(&IBC_DENOM.to_string(), 6u32)]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::AddNativeTokenDecimals {
+            denom: IBC_DENOM.to_string(),
+            decimals: 8u8,
+        },
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::NativeTokenDecimalsMismatch {
+            denom: IBC_DENOM.to_string(),
+            decimals: 6u8,
+        })
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::AddNativeTokenDecimals {
+            denom: IBC_DENOM.to_string(),
+            decimals: 6u8,
+        },
+    )
+    .unwrap();
+
+    let res: NativeTokenDecimalsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::NativeTokenDecimals {
+                denom: IBC_DENOM.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(6u8, res.decimals);
+
+    // an IBC denom must carry a 64 hex characters hash
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::AddNativeTokenDecimals {
+            denom: "ibc/HASH".to_string(),
+            decimals: 6u8,
+        },
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::InvalidIbcDenom {
+            denom: "ibc/HASH".to_string(),
+        })
+    );
+}
+
+#[test]
+fn add_and_remove_allow_native_tokens_in_batch() {
+    let mut deps = mock_dependencies(&[coin(1u128, "uluna"), coin(1u128, "uusd")]);
+    deps = init(deps);
+    deps.querier
+        .with_denom_metadata(&[(&IBC_DENOM.to_string(), 6u32)]);
+
+    let tokens = vec![
+        NativeTokenDecimals {
+            denom: "uusd".to_string(),
+            decimals: 6u8,
+        },
+        NativeTokenDecimals {
+            denom: "uluna".to_string(),
+            decimals: 6u8,
+        },
+        NativeTokenDecimals {
+            denom: IBC_DENOM.to_string(),
+            decimals: 6u8,
+        },
+    ];
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("noadmin", &[]),
+        ExecuteMsg::AddNativeTokenDecimalsBatch {
+            tokens: tokens.clone(),
+        },
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    // one token failing the verification rejects the batch
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::AddNativeTokenDecimalsBatch {
+            tokens: vec![
+                tokens[0].clone(),
+                NativeTokenDecimals {
+                    denom: "ukrw".to_string(),
+                    decimals: 6u8,
+                },
+            ],
+        },
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::Std(StdError::generic_err(
             "a balance greater than zero is required by the factory for verification",
-        ))
+        )))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::AddNativeTokenDecimalsBatch {
+            tokens: tokens.clone(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "add_allow_native_tokens"),
+            attr("denom", "uusd"),
+            attr("decimals", "6"),
+            attr("denom", "uluna"),
+            attr("decimals", "6"),
+            attr("denom", IBC_DENOM),
+            attr("decimals", "6"),
+        ]
+    );
+
+    let res: NativeTokenDecimalsListResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::NativeTokenDecimalsList {
+                start_after: None,
+                limit: Some(2),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.tokens, vec![tokens[2].clone(), tokens[1].clone()]);
+
+    let res: NativeTokenDecimalsListResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::NativeTokenDecimalsList {
+                start_after: Some("uluna".to_string()),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.tokens, vec![tokens[0].clone()]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("noadmin", &[]),
+        ExecuteMsg::RemoveNativeTokenDecimals {
+            denom: "uluna".to_string(),
+        },
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::RemoveNativeTokenDecimals {
+            denom: "uluna".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "remove_allow_native_token"),
+            attr("denom", "uluna"),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::NativeTokenDecimals {
+            denom: "uluna".to_string(),
+        },
+    );
+    assert!(res.is_err());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::RemoveNativeTokenDecimals {
+            denom: "uluna".to_string(),
+        },
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::Std(StdError::generic_err(
+            "Native token decimals are not registered"
+        )))
     );
 }
 
@@ -831,6 +1249,1754 @@ fn failed_migrate_pair_with_no_admin() {
 
     assert_eq!(
         execute(deps.as_mut(), mock_env(), info, msg),
-        Err(StdError::generic_err("unauthorized")),
+        Err(ContractError::Unauthorized {}),
+    );
+}
+
+#[test]
+fn query_pairs_by_asset_and_pair_count() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let uluna = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+    let token = AssetInfo::Token {
+        contract_addr: "asset0000".to_string(),
+    };
+
+    for (i, asset_infos) in [
+        [uusd.clone(), uluna.clone()],
+        [uusd.clone(), token.clone()],
+        [uluna.clone(), token.clone()],
+    ]
+    .iter()
+    .enumerate()
+    {
+        let raw_infos = [
+            asset_infos[0].to_raw(deps.as_ref().api).unwrap(),
+            asset_infos[1].to_raw(deps.as_ref().api).unwrap(),
+        ];
+        let pair_info = PairInfoRaw {
+            asset_infos: raw_infos.clone(),
+            contract_addr: deps
+                .as_ref()
+                .api
+                .addr_canonicalize(&format!("pair000{}", i))
+                .unwrap(),
+            liquidity_token: deps
+                .as_ref()
+                .api
+                .addr_canonicalize(&format!("liquidity000{}", i))
+                .unwrap(),
+            asset_decimals: [6u8, 6u8],
+        };
+        store_pair(deps.as_mut().storage, &pair_key(&raw_infos), &pair_info).unwrap();
+    }
+
+    let res: PairCountResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PairCount {}).unwrap()).unwrap();
+    assert_eq!(res.count, 3u64);
+
+    let res: PairsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairsByAsset {
+                asset_info: uusd.clone(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let mut pair_addrs: Vec<String> = res.pairs.into_iter().map(|p| p.contract_addr).collect();
+    pair_addrs.sort();
+    assert_eq!(pair_addrs, vec!["pair0000", "pair0001"]);
+
+    let res: PairsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairsByAsset {
+                asset_info: uusd.clone(),
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.pairs.len(), 1);
+
+    // paging continues after the returned pair
+    let res: PairsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairsByAsset {
+                asset_info: uusd,
+                start_after: Some(res.pairs[0].asset_infos.clone()),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.pairs.len(), 1);
+
+    let res: PairsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairsByAsset {
+                asset_info: AssetInfo::NativeToken {
+                    denom: "ukrw".to_string(),
+                },
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(res.pairs.is_empty());
+}
+
+#[test]
+fn query_pair_by_liquidity_token_and_address() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let asset_infos = [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: "asset0000".to_string(),
+        },
+    ];
+    let raw_infos = [
+        asset_infos[0].to_raw(deps.as_ref().api).unwrap(),
+        asset_infos[1].to_raw(deps.as_ref().api).unwrap(),
+    ];
+    let pair_info = PairInfoRaw {
+        asset_infos: raw_infos.clone(),
+        contract_addr: deps.as_ref().api.addr_canonicalize("pair0000").unwrap(),
+        liquidity_token: deps
+            .as_ref()
+            .api
+            .addr_canonicalize("liquidity0000")
+            .unwrap(),
+        asset_decimals: [6u8, 8u8],
+    };
+    store_pair(deps.as_mut().storage, &pair_key(&raw_infos), &pair_info).unwrap();
+
+    let expected = PairInfo {
+        asset_infos,
+        contract_addr: "pair0000".to_string(),
+        liquidity_token: "liquidity0000".to_string(),
+        asset_decimals: [6u8, 8u8],
+    };
+
+    let res: PairInfo = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairByLiquidityToken {
+                liquidity_token: "liquidity0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, expected);
+
+    let res: PairInfo = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairByAddress {
+                pair_addr: "pair0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, expected);
+
+    // a pair contract is not a liquidity token
+    query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::PairByLiquidityToken {
+            liquidity_token: "pair0000".to_string(),
+        },
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn migrate_backfills_pair_indexes() {
+    let mut deps = init(mock_dependencies(&[]));
+    set_contract_version(
+        deps.as_mut().storage,
+        "crates.io:cyberswap-factory",
+        "0.0.0",
+    )
+    .unwrap();
+
+    let asset_infos = [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::NativeToken {
+            denom: "uluna".to_string(),
+        },
+    ];
+    let raw_infos = [
+        asset_infos[0].to_raw(deps.as_ref().api).unwrap(),
+        asset_infos[1].to_raw(deps.as_ref().api).unwrap(),
+    ];
+    let pair_info = PairInfoRaw {
+        asset_infos: raw_infos.clone(),
+        contract_addr: deps.as_ref().api.addr_canonicalize("pair0000").unwrap(),
+        liquidity_token: deps
+            .as_ref()
+            .api
+            .addr_canonicalize("liquidity0000")
+            .unwrap(),
+        asset_decimals: [6u8, 6u8],
+    };
+    // pairs stored before the indexes existed
+    PAIRS
+        .save(deps.as_mut().storage, &pair_key(&raw_infos), &pair_info)
+        .unwrap();
+
+    query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::PairByAddress {
+            pair_addr: "pair0000".to_string(),
+        },
+    )
+    .unwrap_err();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    // counted before it is indexed
+    let res: PairCountResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PairCount {}).unwrap()).unwrap();
+    assert_eq!(res.count, 1u64);
+
+    // only the owner can index the pairs
+    let msg = ExecuteMsg::IndexPairs { limit: None };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "index_pairs"),
+            attr("indexed_pairs", "1"),
+            attr("done", "true"),
+        ]
+    );
+
+    let res: PairInfo = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairByAddress {
+                pair_addr: "pair0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.liquidity_token, "liquidity0000".to_string());
+
+    let res: PairInfo = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairByLiquidityToken {
+                liquidity_token: "liquidity0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.contract_addr, "pair0000".to_string());
+
+    let res: PairsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairsByAsset {
+                asset_info: asset_infos[1].clone(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.pairs.len(), 1);
+
+    let res: PairCountResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PairCount {}).unwrap()).unwrap();
+    assert_eq!(res.count, 1u64);
+}
+
+fn update_pair_creation(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier, TerraQuery>,
+    pair_creation_mode: Option<PairCreationMode>,
+    creation_fee: Option<Asset>,
+    fee_treasury: Option<String>,
+    add_allowed_creators: Option<Vec<String>>,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let msg = ExecuteMsg::UpdateConfig {
+        pair_code_id: None,
+        token_code_id: None,
+        pair_creation_mode,
+        creation_fee,
+        fee_treasury,
+        add_allowed_creators,
+        remove_allowed_creators: None,
+        default_fee_config: None,
+        pair_fee_configs: None,
+        remove_pair_fee_configs: None,
+    };
+
+    execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg)
+}
+
+fn create_pair_msg() -> ExecuteMsg {
+    ExecuteMsg::CreatePair {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: "asset0001".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+        ],
+    }
+}
+
+fn pending_pair(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier, TerraQuery>,
+    msg: &ExecuteMsg,
+) -> TmpPairInfo {
+    let assets = match msg {
+        ExecuteMsg::CreatePair { assets } => assets,
+        _ => panic!("Must be a create pair msg"),
+    };
+    let raw_infos = [
+        assets[0].info.to_raw(deps.as_ref().api).unwrap(),
+        assets[1].info.to_raw(deps.as_ref().api).unwrap(),
+    ];
+
+    PENDING_PAIRS
+        .load(&deps.storage, &pair_key(&raw_infos))
+        .unwrap()
+}
+
+#[test]
+fn create_pairs_in_one_transaction() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let asset_infos = [
+        [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: "asset0000".to_string(),
+            },
+        ],
+        [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: "asset0001".to_string(),
+            },
+        ],
+    ];
+    deps.querier.with_token_balances(&[
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+        ),
+        (
+            &"asset0001".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+        ),
+    ]);
+    deps.querier.with_cyberswap_factory(
+        &[
+            (
+                &"pair0000".to_string(),
+                &PairInfo {
+                    asset_infos: asset_infos[0].clone(),
+                    contract_addr: "pair0000".to_string(),
+                    liquidity_token: "liquidity0000".to_string(),
+                    asset_decimals: [6u8, 6u8],
+                },
+            ),
+            (
+                &"pair0001".to_string(),
+                &PairInfo {
+                    asset_infos: asset_infos[1].clone(),
+                    contract_addr: "pair0001".to_string(),
+                    liquidity_token: "liquidity0001".to_string(),
+                    asset_decimals: [6u8, 6u8],
+                },
+            ),
+        ],
+        &[("uusd".to_string(), 6u8)],
+    );
+
+    // both creations are pending before any reply arrives
+    for asset_infos in asset_infos.iter() {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0000", &[]),
+            ExecuteMsg::CreatePair {
+                assets: [
+                    Asset {
+                        info: asset_infos[0].clone(),
+                        amount: Uint128::zero(),
+                    },
+                    Asset {
+                        info: asset_infos[1].clone(),
+                        amount: Uint128::zero(),
+                    },
+                ],
+            },
+        )
+        .unwrap();
+    }
+
+    // the replies arrive in reverse order
+    for pair_contract in ["pair0001", "pair0000"] {
+        let mut data = vec![10, pair_contract.len() as u8];
+        data.extend_from_slice(pair_contract.as_bytes());
+        let res = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: 1,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: Some(data.into()),
+                }),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0], attr("pair_contract_addr", pair_contract));
+    }
+
+    for (i, asset_infos) in asset_infos.iter().enumerate() {
+        let res: PairInfo = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Pair {
+                    asset_infos: asset_infos.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.contract_addr, format!("pair000{}", i));
+        assert_eq!(res.liquidity_token, format!("liquidity000{}", i));
+    }
+}
+
+#[test]
+fn predict_pair_address() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let msg = QueryMsg::PredictPairAddress {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: "asset0000".to_string(),
+            },
+        ],
+    };
+
+    // the checksum comes from the code info of the pair code
+    let res = query(deps.as_ref(), mock_env(), msg.clone()).unwrap_err();
+    assert!(res.to_string().contains("No such code: 321"));
+
+    // the mock api cannot humanize the 32 bytes instantiate2 addresses,
+    // so the prediction fails after the checksum is found
+    deps.querier
+        .with_code_checksums(&[(321u64, &HexBinary::from(vec![1u8; 32]))]);
+    let res = query(deps.as_ref(), mock_env(), msg.clone()).unwrap_err();
+    assert!(res
+        .to_string()
+        .contains("canonical address length not correct"));
+
+    // the salt does not depend on the asset order
+    let raw_infos = [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        }
+        .to_raw(deps.as_ref().api)
+        .unwrap(),
+        AssetInfo::Token {
+            contract_addr: "asset0000".to_string(),
+        }
+        .to_raw(deps.as_ref().api)
+        .unwrap(),
+    ];
+    assert_eq!(
+        pair_salt(&pair_key(&[raw_infos[1].clone(), raw_infos[0].clone()])),
+        pair_salt(&pair_key(&raw_infos))
+    );
+
+    // a new pair code id predicts with the checksum of the new code
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::UpdateConfig {
+            pair_code_id: Some(100u64),
+            token_code_id: None,
+            pair_creation_mode: None,
+            creation_fee: None,
+            fee_treasury: None,
+            add_allowed_creators: None,
+            remove_allowed_creators: None,
+            default_fee_config: None,
+            pair_fee_configs: None,
+            remove_pair_fee_configs: None,
+        },
+    )
+    .unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert!(res.to_string().contains("No such code: 100"));
+}
+
+#[test]
+fn create_pair_owner_only() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_cyberswap_factory(&[], &[("uusd".to_string(), 6u8)]);
+
+    update_pair_creation(
+        &mut deps,
+        Some(PairCreationMode::OwnerOnly),
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        create_pair_msg(),
+    );
+    assert_eq!(res, Err(ContractError::PairCreationOwnerOnly {}));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        create_pair_msg(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn create_pair_allowlist() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_cyberswap_factory(&[], &[("uusd".to_string(), 6u8)]);
+
+    update_pair_creation(
+        &mut deps,
+        Some(PairCreationMode::Allowlist),
+        None,
+        None,
+        Some(vec!["addr0001".to_string()]),
+    )
+    .unwrap();
+
+    let res: AllowedCreatorsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AllowedCreators {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.creators, vec!["addr0001".to_string()]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0002", &[]),
+        create_pair_msg(),
+    );
+    assert_eq!(res, Err(ContractError::CreatorNotAllowlisted {}));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        create_pair_msg(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn create_pair_permissionless_with_creation_fee() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_cyberswap_factory(&[], &[("uusd".to_string(), 6u8)]);
+
+    let creation_fee = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uluna".to_string(),
+        },
+        amount: Uint128::from(100u128),
+    };
+
+    // a fee without a treasury is rejected
+    assert_eq!(
+        update_pair_creation(&mut deps, None, Some(creation_fee.clone()), None, None),
+        Err(ContractError::FeeTreasuryNotSet {})
+    );
+
+    update_pair_creation(
+        &mut deps,
+        None,
+        Some(creation_fee.clone()),
+        Some("treasury0000".to_string()),
+        None,
+    )
+    .unwrap();
+
+    let config_res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config_res.pair_creation_mode,
+        PairCreationMode::Permissionless
+    );
+    assert_eq!(config_res.creation_fee, Some(creation_fee));
+    assert_eq!(config_res.fee_treasury, Some("treasury0000".to_string()));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[coin(99u128, "uluna")]),
+        create_pair_msg(),
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::CreationFeeNotPaid {
+            fee: "100uluna".to_string()
+        })
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[coin(100u128, "uluna")]),
+        create_pair_msg(),
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(BankMsg::Send {
+            to_address: "treasury0000".to_string(),
+            amount: coins(100u128, "uluna"),
+        })
+    );
+    assert_eq!(
+        pending_pair(&deps, &create_pair_msg()).sender,
+        Addr::unchecked("addr0001")
+    );
+
+    // the owner creates pairs for free
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        create_pair_msg(),
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+}
+
+#[test]
+fn create_pair_with_token_creation_fee() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_cyberswap_factory(&[], &[("uusd".to_string(), 6u8)]);
+
+    update_pair_creation(
+        &mut deps,
+        None,
+        Some(Asset {
+            info: AssetInfo::Token {
+                contract_addr: "cyber0000".to_string(),
+            },
+            amount: Uint128::from(100u128),
+        }),
+        Some("treasury0000".to_string()),
+        None,
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        create_pair_msg(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(WasmMsg::Execute {
+            contract_addr: "cyber0000".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: "addr0001".to_string(),
+                recipient: "treasury0000".to_string(),
+                amount: Uint128::from(100u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    );
+}
+
+#[test]
+fn create_pair_with_initial_liquidity() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_cyberswap_factory(&[], &[("uusd".to_string(), 6u8)]);
+
+    let assets = [
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(100u128),
+        },
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: "asset0001".to_string(),
+            },
+            amount: Uint128::from(200u128),
+        },
+    ];
+
+    // native liquidity must be sent along
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::CreatePair {
+            assets: assets.clone(),
+        },
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::Std(StdError::generic_err(
+            "Native token balance mismatch between the argument and the transferred"
+        )))
+    );
+
+    // unrelated coins would be stuck in the factory
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin(100u128, "uusd"), coin(1u128, "uluna")]),
+        ExecuteMsg::CreatePair {
+            assets: assets.clone(),
+        },
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::Std(StdError::generic_err(
+            "Native token balance mismatch between the argument and the transferred"
+        )))
+    );
+
+    // one sided liquidity can not be provided
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin(100u128, "uusd")]),
+        ExecuteMsg::CreatePair {
+            assets: [
+                assets[0].clone(),
+                Asset {
+                    info: assets[1].info.clone(),
+                    amount: Uint128::zero(),
+                },
+            ],
+        },
+    );
+    assert_eq!(res, Err(ContractError::InvalidInitialLiquidity {}));
+
+    let msg = ExecuteMsg::CreatePair {
+        assets: assets.clone(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin(100u128, "uusd")]),
+        msg.clone(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        pending_pair(&deps, &msg).assets,
+        [
+            assets[0].to_raw(deps.as_ref().api).unwrap(),
+            assets[1].to_raw(deps.as_ref().api).unwrap(),
+        ]
+    );
+}
+
+#[test]
+fn create_pair_with_initial_liquidity_and_creation_fee_in_same_denom() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_cyberswap_factory(&[], &[("uusd".to_string(), 6u8)]);
+
+    update_pair_creation(
+        &mut deps,
+        None,
+        Some(Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::from(10u128),
+        }),
+        Some("treasury0000".to_string()),
+        None,
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::CreatePair {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::from(100u128),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: "asset0001".to_string(),
+                },
+                amount: Uint128::from(200u128),
+            },
+        ],
+    };
+
+    // the fee comes on top of the liquidity
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[coin(100u128, "uusd")]),
+        msg.clone(),
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::Std(StdError::generic_err(
+            "Native token balance mismatch between the argument and the transferred"
+        )))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[coin(110u128, "uusd")]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(BankMsg::Send {
+            to_address: "treasury0000".to_string(),
+            amount: coins(10u128, "uusd"),
+        })
+    );
+}
+
+#[test]
+fn deregister_and_register_pair() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let asset_infos = [
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        AssetInfo::Token {
+            contract_addr: "asset0000".to_string(),
+        },
+    ];
+    let raw_infos = [
+        asset_infos[0].to_raw(deps.as_ref().api).unwrap(),
+        asset_infos[1].to_raw(deps.as_ref().api).unwrap(),
+    ];
+    let pair_info = PairInfoRaw {
+        asset_infos: raw_infos.clone(),
+        contract_addr: deps.as_ref().api.addr_canonicalize("pair0000").unwrap(),
+        liquidity_token: deps
+            .as_ref()
+            .api
+            .addr_canonicalize("liquidity0000")
+            .unwrap(),
+        asset_decimals: [6u8, 8u8],
+    };
+    store_pair(deps.as_mut().storage, &pair_key(&raw_infos), &pair_info).unwrap();
+
+    let msg = ExecuteMsg::DeregisterPair {
+        asset_infos: asset_infos.clone(),
+    };
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0001", &[]),
+            msg.clone()
+        ),
+        Err(ContractError::Unauthorized {})
+    );
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "deregister_pair"),
+            attr("pair", "uusd-asset0000"),
+            attr("pair_contract_addr", "pair0000"),
+        ]
+    );
+
+    query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Pair {
+            asset_infos: asset_infos.clone(),
+        },
+    )
+    .unwrap_err();
+    query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::PairByAddress {
+            pair_addr: "pair0000".to_string(),
+        },
+    )
+    .unwrap_err();
+    let res: PairCountResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PairCount {}).unwrap()).unwrap();
+    assert_eq!(res.count, 0u64);
+
+    let res: PairsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::DeprecatedPairs {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.pairs.len(), 1);
+    assert_eq!(res.pairs[0].contract_addr, "pair0000".to_string());
+
+    // register it back
+    deps.querier.with_cyberswap_factory(
+        &[(
+            &"pair0000".to_string(),
+            &PairInfo {
+                asset_infos: asset_infos.clone(),
+                contract_addr: "pair0000".to_string(),
+                liquidity_token: "liquidity0000".to_string(),
+                asset_decimals: [6u8, 8u8],
+            },
+        )],
+        &[],
+    );
+    deps.querier
+        .with_token_minters(&[(&"liquidity0000".to_string(), &"pair0000".to_string())]);
+
+    let msg = ExecuteMsg::RegisterPair {
+        pair_addr: "pair0000".to_string(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "register_pair"),
+            attr("pair", "uusd-asset0000"),
+            attr("pair_contract_addr", "pair0000"),
+            attr("liquidity_token_addr", "liquidity0000"),
+        ]
+    );
+
+    let res: PairInfo = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Pair {
+                asset_infos: asset_infos.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.liquidity_token, "liquidity0000".to_string());
+
+    let res: PairsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::DeprecatedPairs {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(res.pairs.is_empty());
+
+    // registering twice fails
+    let msg = ExecuteMsg::RegisterPair {
+        pair_addr: "pair0000".to_string(),
+    };
+    assert_eq!(
+        execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg),
+        Err(ContractError::Std(StdError::generic_err(
+            "Pair already exists"
+        )))
+    );
+}
+
+#[test]
+fn failed_register_pair_with_foreign_liquidity_token() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    deps.querier.with_cyberswap_factory(
+        &[(
+            &"pair0000".to_string(),
+            &PairInfo {
+                asset_infos: [
+                    AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    AssetInfo::Token {
+                        contract_addr: "asset0000".to_string(),
+                    },
+                ],
+                contract_addr: "pair0000".to_string(),
+                liquidity_token: "liquidity0000".to_string(),
+                asset_decimals: [6u8, 8u8],
+            },
+        )],
+        &[],
+    );
+    deps.querier
+        .with_token_minters(&[(&"liquidity0000".to_string(), &"addr0001".to_string())]);
+
+    let msg = ExecuteMsg::RegisterPair {
+        pair_addr: "pair0000".to_string(),
+    };
+    assert_eq!(
+        execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg),
+        Err(ContractError::InvalidPairContract {})
+    );
+}
+
+#[test]
+fn failed_register_pair_with_blocked_asset() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    deps.querier.with_cyberswap_factory(
+        &[(
+            &"pair0000".to_string(),
+            &PairInfo {
+                asset_infos: [
+                    AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    AssetInfo::Token {
+                        contract_addr: "asset0000".to_string(),
+                    },
+                ],
+                contract_addr: "pair0000".to_string(),
+                liquidity_token: "liquidity0000".to_string(),
+                asset_decimals: [6u8, 8u8],
+            },
+        )],
+        &[],
+    );
+    deps.querier
+        .with_token_minters(&[(&"liquidity0000".to_string(), &"pair0000".to_string())]);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::UpdateBlocklist {
+            add: Some(vec![AssetInfo::Token {
+                contract_addr: "asset0000".to_string(),
+            }]),
+            remove: None,
+        },
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::RegisterPair {
+        pair_addr: "pair0000".to_string(),
+    };
+    assert_eq!(
+        execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg),
+        Err(ContractError::AssetBlocked {
+            asset: "asset0000".to_string(),
+        })
+    );
+}
+
+fn store_native_pairs(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier, TerraQuery>,
+    denoms: &[(&str, &str)],
+) {
+    for (i, (denom_0, denom_1)) in denoms.iter().enumerate() {
+        let raw_infos = [
+            AssetInfo::NativeToken {
+                denom: denom_0.to_string(),
+            }
+            .to_raw(deps.as_ref().api)
+            .unwrap(),
+            AssetInfo::NativeToken {
+                denom: denom_1.to_string(),
+            }
+            .to_raw(deps.as_ref().api)
+            .unwrap(),
+        ];
+        let pair_info = PairInfoRaw {
+            asset_infos: raw_infos.clone(),
+            contract_addr: deps
+                .as_ref()
+                .api
+                .addr_canonicalize(&format!("pair000{}", i))
+                .unwrap(),
+            liquidity_token: deps
+                .as_ref()
+                .api
+                .addr_canonicalize(&format!("liquidity000{}", i))
+                .unwrap(),
+            asset_decimals: [6u8, 6u8],
+        };
+        store_pair(deps.as_mut().storage, &pair_key(&raw_infos), &pair_info).unwrap();
+    }
+}
+
+#[test]
+fn migrate_pairs_in_pages() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+    store_native_pairs(
+        &mut deps,
+        &[
+            ("uaud", "uluna"),
+            ("ucad", "uluna"),
+            ("ueur", "uluna"),
+            ("ukrw", "uluna"),
+        ],
+    );
+    // the last pair was registered with another admin
+    deps.querier.with_contract_admins(&[
+        (&"pair0000".to_string(), &MOCK_CONTRACT_ADDR.to_string()),
+        (&"pair0001".to_string(), &MOCK_CONTRACT_ADDR.to_string()),
+        (&"pair0002".to_string(), &MOCK_CONTRACT_ADDR.to_string()),
+        (&"pair0003".to_string(), &"addr0001".to_string()),
+    ]);
+
+    let msg = ExecuteMsg::MigratePairs {
+        start_after: None,
+        limit: Some(2),
+        code_id: Some(400u64),
+        reset: None,
+    };
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("addr0001", &[]),
+            msg.clone()
+        ),
+        Err(ContractError::Unauthorized {})
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Migrate {
+                contract_addr: "pair0000".to_string(),
+                new_code_id: 400u64,
+                msg: to_binary(&PairMigrateMsg {}).unwrap(),
+            }),
+            SubMsg::new(WasmMsg::Migrate {
+                contract_addr: "pair0001".to_string(),
+                new_code_id: 400u64,
+                msg: to_binary(&PairMigrateMsg {}).unwrap(),
+            }),
+        ]
+    );
+
+    let res: PairMigrationResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PairMigration {}).unwrap())
+            .unwrap();
+    assert_eq!(res.code_id, 400u64);
+    assert_eq!(res.migrated_count, 2u64);
+    assert_eq!(
+        res.last_migrated,
+        Some([
+            AssetInfo::NativeToken {
+                denom: "ucad".to_string(),
+            },
+            AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+        ])
+    );
+
+    // resumes after the last migrated pair
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Migrate {
+            contract_addr: "pair0002".to_string(),
+            new_code_id: 400u64,
+            msg: to_binary(&PairMigrateMsg {}).unwrap(),
+        })]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "migrate_pairs"),
+            attr("code_id", "400"),
+            attr("migrated_pairs", "1"),
+            attr("skipped_pairs", "pair0003"),
+        ]
+    );
+
+    // nothing left
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    assert!(res.messages.is_empty());
+    let res: PairMigrationResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PairMigration {}).unwrap())
+            .unwrap();
+    assert_eq!(res.migrated_count, 3u64);
+
+    // another code id starts over, defaulting to the config one
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::MigratePairs {
+            start_after: None,
+            limit: None,
+            code_id: None,
+            reset: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 3);
+    let res: PairMigrationResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PairMigration {}).unwrap())
+            .unwrap();
+    assert_eq!(res.code_id, 321u64);
+    assert_eq!(res.migrated_count, 3u64);
+
+    // reset starts the same code id over
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::MigratePairs {
+            start_after: None,
+            limit: Some(1),
+            code_id: None,
+            reset: Some(true),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Migrate {
+            contract_addr: "pair0000".to_string(),
+            new_code_id: 321u64,
+            msg: to_binary(&PairMigrateMsg {}).unwrap(),
+        })]
+    );
+    let res: PairMigrationResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PairMigration {}).unwrap())
+            .unwrap();
+    assert_eq!(res.code_id, 321u64);
+    assert_eq!(res.migrated_count, 1u64);
+}
+
+#[test]
+fn query_outdated_pairs() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+    store_native_pairs(
+        &mut deps,
+        &[("uaud", "uluna"), ("ucad", "uluna"), ("ueur", "uluna")],
+    );
+    deps.querier.with_contract_infos(&[
+        (&"pair0000".to_string(), 321u64, &"0.2.0".to_string()),
+        (&"pair0001".to_string(), 300u64, &"0.1.0".to_string()),
+        (&"pair0002".to_string(), 300u64, &"0.1.0".to_string()),
+    ]);
+
+    let res: OutdatedPairsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::OutdatedPairs {
+                code_id: None,
+                start_after: None,
+                limit: Some(2),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.pairs,
+        vec![OutdatedPair {
+            asset_infos: [
+                AssetInfo::NativeToken {
+                    denom: "ucad".to_string(),
+                },
+                AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+            ],
+            contract_addr: "pair0001".to_string(),
+            code_id: 300u64,
+            version: Some("0.1.0".to_string()),
+        }]
+    );
+
+    let res: OutdatedPairsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::OutdatedPairs {
+                code_id: None,
+                start_after: res.last_checked,
+                limit: Some(2),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.pairs.len(), 1);
+    assert_eq!(res.pairs[0].contract_addr, "pair0002".to_string());
+}
+
+#[test]
+fn update_blocklist() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_cyberswap_factory(&[], &[("uusd".to_string(), 6u8)]);
+    store_native_pairs(&mut deps, &[("uluna", "uusd"), ("ukrw", "uusd")]);
+
+    let blocked_asset = AssetInfo::Token {
+        contract_addr: "asset0001".to_string(),
+    };
+    let msg = ExecuteMsg::UpdateBlocklist {
+        add: Some(vec![
+            blocked_asset.clone(),
+            AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+        ]),
+        remove: None,
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_blocklist"),
+            attr("blocked", "asset0001"),
+            attr("blocked", "uluna"),
+        ]
+    );
+
+    let res: IsBlockedResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::IsBlocked {
+                asset_info: blocked_asset.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(res.blocked);
+
+    let res: BlockedAssetsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::BlockedAssets {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.assets.len(), 2);
+    assert!(res.assets.contains(&blocked_asset));
+
+    // pairs with a blocked asset are flagged
+    let res: PairsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Pairs {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.pairs.len(), 2);
+    assert_eq!(res.blocked_pairs, vec!["pair0000".to_string()]);
+
+    // blocked assets can not be paired
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        create_pair_msg(),
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::AssetBlocked {
+            asset: "asset0001".to_string(),
+        })
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::UpdateBlocklist {
+            add: None,
+            remove: Some(vec![blocked_asset.clone()]),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_blocklist"),
+            attr("unblocked", "asset0001"),
+        ]
+    );
+
+    let res: IsBlockedResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::IsBlocked {
+                asset_info: blocked_asset,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(!res.blocked);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        create_pair_msg(),
+    )
+    .unwrap();
+}
+
+fn update_fee_configs(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier, TerraQuery>,
+    fee_treasury: Option<String>,
+    default_fee_config: Option<FeeConfig>,
+    pair_fee_configs: Option<Vec<PairFeeConfig>>,
+    remove_pair_fee_configs: Option<Vec<String>>,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let msg = ExecuteMsg::UpdateConfig {
+        pair_code_id: None,
+        token_code_id: None,
+        pair_creation_mode: None,
+        creation_fee: None,
+        fee_treasury,
+        add_allowed_creators: None,
+        remove_allowed_creators: None,
+        default_fee_config,
+        pair_fee_configs,
+        remove_pair_fee_configs,
+    };
+
+    execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg)
+}
+
+fn query_fee_config(
+    deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier, TerraQuery>,
+    pair_addr: &str,
+) -> FeeConfigResponse {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::FeeConfig {
+                pair_addr: pair_addr.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn update_default_and_pair_fee_configs() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+    store_native_pairs(&mut deps, &[("uluna", "uusd"), ("ukrw", "uusd")]);
+
+    // pairs charge the default fee until one is configured
+    assert_eq!(
+        query_fee_config(&deps, "pair0000"),
+        FeeConfigResponse {
+            total_fee_bps: 30u16,
+            protocol_fee_bps: 0u16,
+            fee_treasury: None,
+        }
+    );
+
+    let default_fee_config = FeeConfig {
+        total_fee_bps: 25u16,
+        protocol_fee_bps: 5u16,
+    };
+
+    // the protocol fee needs a treasury
+    let res = update_fee_configs(
+        &mut deps,
+        None,
+        Some(default_fee_config.clone()),
+        None,
+        None,
+    );
+    assert_eq!(res, Err(ContractError::FeeTreasuryNotSet {}));
+
+    let res = update_fee_configs(
+        &mut deps,
+        None,
+        Some(FeeConfig {
+            total_fee_bps: 1_001u16,
+            protocol_fee_bps: 0u16,
+        }),
+        None,
+        None,
+    );
+    assert_eq!(res, Err(ContractError::InvalidFeeConfig { max: 1_000u16 }));
+
+    let res = update_fee_configs(
+        &mut deps,
+        None,
+        None,
+        Some(vec![PairFeeConfig {
+            pair_addr: "pair0000".to_string(),
+            fee_config: FeeConfig {
+                total_fee_bps: 10u16,
+                protocol_fee_bps: 20u16,
+            },
+        }]),
+        None,
+    );
+    assert_eq!(res, Err(ContractError::InvalidFeeConfig { max: 1_000u16 }));
+
+    // only registered pairs can be overridden
+    let res = update_fee_configs(
+        &mut deps,
+        None,
+        None,
+        Some(vec![PairFeeConfig {
+            pair_addr: "pair0009".to_string(),
+            fee_config: FeeConfig {
+                total_fee_bps: 10u16,
+                protocol_fee_bps: 0u16,
+            },
+        }]),
+        None,
+    );
+    assert!(res.is_err());
+
+    let pair_fee_config = PairFeeConfig {
+        pair_addr: "pair0000".to_string(),
+        fee_config: FeeConfig {
+            total_fee_bps: 40u16,
+            protocol_fee_bps: 0u16,
+        },
+    };
+    let res = update_fee_configs(
+        &mut deps,
+        Some("treasury0000".to_string()),
+        Some(default_fee_config.clone()),
+        Some(vec![pair_fee_config.clone()]),
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_config"),
+            attr("default_total_fee_bps", "25"),
+            attr("default_protocol_fee_bps", "5"),
+            attr("pair_addr", "pair0000"),
+            attr("total_fee_bps", "40"),
+            attr("protocol_fee_bps", "0"),
+        ]
+    );
+
+    assert_eq!(
+        query_fee_config(&deps, "pair0000"),
+        FeeConfigResponse {
+            total_fee_bps: 40u16,
+            protocol_fee_bps: 0u16,
+            fee_treasury: Some("treasury0000".to_string()),
+        }
+    );
+    assert_eq!(
+        query_fee_config(&deps, "pair0001"),
+        FeeConfigResponse {
+            total_fee_bps: 25u16,
+            protocol_fee_bps: 5u16,
+            fee_treasury: Some("treasury0000".to_string()),
+        }
+    );
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.default_fee_config, default_fee_config);
+
+    let res: PairFeeConfigsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PairFeeConfigs {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.fee_configs, vec![pair_fee_config]);
+
+    let res = update_fee_configs(
+        &mut deps,
+        None,
+        None,
+        None,
+        Some(vec!["pair0000".to_string()]),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_config"),
+            attr("removed_pair_fee_config", "pair0000"),
+        ]
     );
+    assert_eq!(query_fee_config(&deps, "pair0000").total_fee_bps, 25u16);
 }
@@ -10,12 +10,16 @@ use classic_cyberswap::farming::{
     StakerInfoResponse, StateResponse,
 };
 
-use crate::state::{Config, StakerInfo, StateInfo, CONFIG, STAKER_INFO, STATE_INFO};
+use crate::state::{
+    Config, OwnershipProposal, StakerInfo, StateInfo, CONFIG, OWNERSHIP_PROPOSAL, STAKER_INFO,
+    STATE_INFO,
+};
 
 const CONTRACT_NAME: &str = "crates.io:cyberswap-farming";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub const ONE_DAY: u64 = 24 * 60 * 60; // 86400s
+pub const MAX_OWNERSHIP_PROPOSAL_TTL: u64 = 14 * ONE_DAY; // 14 days to sec
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -37,6 +41,10 @@ pub fn instantiate(
         .api
         .addr_validate(&msg.staking_token)
         .unwrap_or(Addr::unchecked(msg.staking_token));
+    let router_address = msg
+        .router_address
+        .map(|router_address| deps.api.addr_validate(&router_address))
+        .transpose()?;
 
     let config = Config {
         owner: owner.clone(),
@@ -46,6 +54,7 @@ pub fn instantiate(
         distribution_schedule: (0, 0, Uint128::zero()),
         referral_rate: msg.referral_rate,
         referral_lock_days: msg.referral_lock_days,
+        router_address,
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -68,10 +77,17 @@ pub fn instantiate(
 pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
     match msg {
         ExecuteMsg::UpdateConfig {
-            owner,
             referral_rate,
             referral_lock_days,
-        } => update_config(deps, env, info, owner, referral_rate, referral_lock_days),
+            router_address,
+        } => update_config(
+            deps,
+            env,
+            info,
+            referral_rate,
+            referral_lock_days,
+            router_address,
+        ),
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::Unstake {
             amount,
@@ -79,6 +95,11 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
         } => unstake(deps, env, info, amount, withdraw_pending_reward),
         ExecuteMsg::Claim {} => try_claim(deps, env, info),
         ExecuteMsg::ClaimReferralReward {} => try_claim_referral_reward(deps, env, info),
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            propose_new_owner(deps, env, info, owner, expires_in)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => drop_ownership_proposal(deps, env, info),
+        ExecuteMsg::ClaimOwnership {} => claim_ownership(deps, env, info),
     }
 }
 
@@ -108,7 +129,10 @@ pub fn receive_cw20(
     let config: Config = CONFIG.load(deps.storage)?;
 
     match from_binary(&cw20_msg.msg) {
-        Ok(Cw20HookMsg::Stake { referral_addr }) => {
+        Ok(Cw20HookMsg::Stake {
+            referral_addr,
+            staker,
+        }) => {
             if config.staking_token != info.sender.as_str() {
                 return Err(StdError::generic_err("unauthorized"));
             }
@@ -117,7 +141,17 @@ pub fn receive_cw20(
                 .addr_validate(&cw20_msg.sender)
                 .unwrap_or(Addr::unchecked(cw20_msg.sender));
             //TODO: let cw20_sender = deps.api.addr_validate(&cw20_msg.sender)?;
-            stake(deps, env, cw20_sender, cw20_msg.amount, referral_addr)
+            // the router stakes the return of a swap for the swapper
+            let staker = match staker {
+                Some(staker) => {
+                    if config.router_address != Some(cw20_sender) {
+                        return Err(StdError::generic_err("unauthorized"));
+                    }
+                    deps.api.addr_validate(&staker)?
+                }
+                None => cw20_sender,
+            };
+            stake(deps, env, staker, cw20_msg.amount, referral_addr)
         }
         Ok(Cw20HookMsg::UpdateRewardSchedule {
             period_start,
@@ -202,9 +236,9 @@ pub fn update_config(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    owner: Option<String>,
     referral_rate: Option<u64>,
     referral_lock_days: Option<u64>,
+    router_address: Option<String>,
 ) -> StdResult<Response> {
     let mut config = CONFIG.load(deps.storage)?;
 
@@ -213,14 +247,6 @@ pub fn update_config(
         return Err(StdError::generic_err("Only owner can update configuration"));
     }
 
-    if let Some(owner) = owner {
-        config.owner = deps
-            .api
-            .addr_validate(&owner)
-            .unwrap_or(Addr::unchecked(owner.clone()));
-        //TODO: config.owner = deps.api.addr_validate(&owner)?;
-    }
-
     if let Some(referral_rate) = referral_rate {
         config.referral_rate = referral_rate;
     }
@@ -229,11 +255,101 @@ pub fn update_config(
         config.referral_lock_days = referral_lock_days;
     }
 
+    if let Some(router_address) = router_address {
+        config.router_address = Some(deps.api.addr_validate(&router_address)?);
+    }
+
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new().add_attribute("action", "UpdateConfig"))
 }
 
+pub fn propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    expires_in: u64,
+) -> StdResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // ONLY OWNER CAN PROPOSE A NEW OWNER
+    if info.sender != config.owner {
+        return Err(StdError::generic_err("Only owner can propose a new owner"));
+    }
+
+    if expires_in > MAX_OWNERSHIP_PROPOSAL_TTL {
+        return Err(StdError::generic_err(format!(
+            "Ownership proposal cannot expire later than {} seconds",
+            MAX_OWNERSHIP_PROPOSAL_TTL
+        )));
+    }
+
+    let new_owner = deps.api.addr_validate(&owner)?;
+    if new_owner == config.owner {
+        return Err(StdError::generic_err(
+            "New owner cannot be the current owner",
+        ));
+    }
+
+    let expires_at = env.block.time.seconds() + expires_in;
+    OWNERSHIP_PROPOSAL.save(
+        deps.storage,
+        &OwnershipProposal {
+            owner: new_owner.clone(),
+            expires_at,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "ProposeNewOwner"),
+        ("new_owner", new_owner.as_str()),
+        ("expires_at", expires_at.to_string().as_str()),
+    ]))
+}
+
+pub fn drop_ownership_proposal(deps: DepsMut, _env: Env, info: MessageInfo) -> StdResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // ONLY OWNER CAN DROP THE PROPOSAL
+    if info.sender != config.owner {
+        return Err(StdError::generic_err(
+            "Only owner can drop the ownership proposal",
+        ));
+    }
+
+    OWNERSHIP_PROPOSAL.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "DropOwnershipProposal"))
+}
+
+pub fn claim_ownership(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+    let proposal = OWNERSHIP_PROPOSAL
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("Ownership proposal not found"))?;
+
+    // ONLY PROPOSED OWNER CAN CLAIM
+    if info.sender != proposal.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    if env.block.time.seconds() > proposal.expires_at {
+        return Err(StdError::generic_err("Ownership proposal expired"));
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let previous_owner = config.owner;
+    config.owner = proposal.owner;
+    CONFIG.save(deps.storage, &config)?;
+    OWNERSHIP_PROPOSAL.remove(deps.storage);
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "ClaimOwnership"),
+        ("previous_owner", previous_owner.as_str()),
+        ("new_owner", info.sender.as_str()),
+    ]))
+}
+
 pub fn update_reward_schedule(
     deps: DepsMut,
     env: Env,
@@ -418,6 +534,7 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         distribution_schedule: config.distribution_schedule,
         referral_rate: config.referral_rate,
         referral_lock_days: config.referral_lock_days,
+        router_address: config.router_address.map(|addr| addr.to_string()),
     })
 }
 
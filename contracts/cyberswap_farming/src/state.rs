@@ -11,6 +11,7 @@ use serde::{Deserialize, Serialize};
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const STATE_INFO: Item<StateInfo> = Item::new("state");
 pub const STAKER_INFO: Map<&Addr, StakerInfo> = Map::new("staker");
+pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -21,6 +22,17 @@ pub struct Config {
     pub distribution_schedule: (u64, u64, Uint128),
     pub referral_rate: u64,
     pub referral_lock_days: u64,
+    /// Router allowed to stake the return of a swap for the swapper
+    #[serde(default)]
+    pub router_address: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipProposal {
+    /// Proposed owner, who has to claim the ownership
+    pub owner: Addr,
+    /// Timestamp after which the proposal can no longer be claimed
+    pub expires_at: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
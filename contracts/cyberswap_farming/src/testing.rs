@@ -0,0 +1,133 @@
+use cosmwasm_std::{to_binary, Addr, Uint128};
+use cw20::{Cw20Coin, Cw20ExecuteMsg};
+use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
+use cw_multi_test::{App, ContractWrapper, Executor};
+
+use crate::contract::{execute, instantiate, query};
+
+use classic_cyberswap::farming::{Cw20HookMsg, InstantiateMsg, QueryMsg, StakerInfoResponse};
+
+const OWNER: &str = "owner";
+const USER: &str = "user";
+const ROUTER: &str = "router";
+
+fn setup() -> (App, Addr, Addr) {
+    let mut app = App::default();
+
+    let token_code = app.store_code(Box::new(ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    )));
+    let lp_token = app
+        .instantiate_contract(
+            token_code,
+            Addr::unchecked(OWNER),
+            &Cw20InstantiateMsg {
+                name: "Cyberswap LP".to_string(),
+                symbol: "uLP".to_string(),
+                decimals: 6,
+                initial_balances: vec![
+                    Cw20Coin {
+                        address: USER.to_string(),
+                        amount: Uint128::from(1000u128),
+                    },
+                    Cw20Coin {
+                        address: ROUTER.to_string(),
+                        amount: Uint128::from(1000u128),
+                    },
+                ],
+                mint: None,
+                marketing: None,
+            },
+            &[],
+            "lp",
+            None,
+        )
+        .unwrap();
+
+    let farming_code = app.store_code(Box::new(ContractWrapper::new(execute, instantiate, query)));
+    let farming = app
+        .instantiate_contract(
+            farming_code,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                owner: OWNER.to_string(),
+                reward_token: "reward".to_string(),
+                staking_token: lp_token.to_string(),
+                staking_token_decimals: 6,
+                referral_rate: 0,
+                referral_lock_days: 0,
+                router_address: Some(ROUTER.to_string()),
+            },
+            &[],
+            "farming",
+            None,
+        )
+        .unwrap();
+
+    (app, lp_token, farming)
+}
+
+fn stake_msg(farming: &Addr, staker: Option<&str>) -> Cw20ExecuteMsg {
+    Cw20ExecuteMsg::Send {
+        contract: farming.to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_binary(&Cw20HookMsg::Stake {
+            referral_addr: Addr::unchecked("referrer"),
+            staker: staker.map(|staker| staker.to_string()),
+        })
+        .unwrap(),
+    }
+}
+
+fn stake_amount(app: &App, farming: &Addr, staker: &str) -> Uint128 {
+    let res: StakerInfoResponse = app
+        .wrap()
+        .query_wasm_smart(
+            farming,
+            &QueryMsg::StakerInfo {
+                staker: staker.to_string(),
+                timestamp: None,
+            },
+        )
+        .unwrap();
+    res.stake_amount
+}
+
+#[test]
+fn stake_for_another_staker() {
+    let (mut app, lp_token, farming) = setup();
+
+    // only the router may stake for another address
+    let err = app
+        .execute_contract(
+            Addr::unchecked(USER),
+            lp_token.clone(),
+            &stake_msg(&farming, Some("other")),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Generic error: unauthorized");
+
+    app.execute_contract(
+        Addr::unchecked(USER),
+        lp_token.clone(),
+        &stake_msg(&farming, None),
+        &[],
+    )
+    .unwrap();
+    assert_eq!(stake_amount(&app, &farming, USER), Uint128::from(100u128));
+
+    // the router stakes the return of a swap for the swapper
+    app.execute_contract(
+        Addr::unchecked(ROUTER),
+        lp_token,
+        &stake_msg(&farming, Some(USER)),
+        &[],
+    )
+    .unwrap();
+    assert_eq!(stake_amount(&app, &farming, USER), Uint128::from(200u128));
+    assert_eq!(stake_amount(&app, &farming, ROUTER), Uint128::zero());
+    assert_eq!(stake_amount(&app, &farming, "other"), Uint128::zero());
+}
@@ -1,6 +1,6 @@
 use crate::error::ContractError;
 use crate::response::MsgInstantiateContractResponse;
-use crate::state::PAIR_INFO;
+use crate::state::{FACTORY, PAIR_INFO};
 
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
@@ -14,11 +14,12 @@ use cosmwasm_std::{
 use classic_bindings::{TerraMsg, TerraQuery};
 
 use classic_cyberswap::asset::{Asset, AssetInfo, PairInfo, PairInfoRaw};
+use classic_cyberswap::factory::{FeeConfigResponse, DEFAULT_TOTAL_FEE_BPS};
 use classic_cyberswap::pair::{
     Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolResponse, QueryMsg,
     ReverseSimulationResponse, SimulationResponse,
 };
-use classic_cyberswap::querier::query_token_info;
+use classic_cyberswap::querier::{query_fee_config, query_token_info};
 use classic_cyberswap::token::InstantiateMsg as TokenInstantiateMsg;
 use classic_cyberswap::util::{assert_deadline, migrate_version};
 use cw2::set_contract_version;
@@ -34,20 +35,31 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const INSTANTIATE_REPLY_ID: u64 = 1;
 
-/// Commission rate == 0.3%
-const COMMISSION_RATE: u64 = 3;
-
 const MINIMUM_LIQUIDITY_AMOUNT: u128 = 1_000;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut<TerraQuery>,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response<TerraMsg>> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    // the instantiator is the factory if it manages the fee of the pair
+    if query_fee_config(
+        &deps.querier,
+        info.sender.clone(),
+        env.contract.address.to_string(),
+    )
+    .is_ok()
+    {
+        FACTORY.save(
+            deps.storage,
+            &deps.api.addr_canonicalize(info.sender.as_str())?,
+        )?;
+    }
+
     let pair_info: &PairInfoRaw = &PairInfoRaw {
         contract_addr: deps.api.addr_canonicalize(env.contract.address.as_str())?,
         liquidity_token: CanonicalAddr::from(vec![]),
@@ -142,6 +154,7 @@ pub fn execute(
                 deadline,
             )
         }
+        ExecuteMsg::UpdateFactory {} => update_factory(deps, env),
     }
 }
 
@@ -501,6 +514,7 @@ pub fn swap(
     offer_asset.assert_sent_native_token_balance(&info)?;
 
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let fee_config = load_fee_config(deps.as_ref(), &env.contract.address)?;
 
     let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, env.contract.address)?;
 
@@ -534,8 +548,12 @@ pub fn swap(
     }
 
     let offer_amount = offer_asset.amount;
-    let (return_amount, spread_amount, commission_amount) =
-        compute_swap(offer_pool.amount, ask_pool.amount, offer_amount)?;
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        offer_pool.amount,
+        ask_pool.amount,
+        offer_amount,
+        commission_rate(&fee_config),
+    )?;
 
     let return_asset = Asset {
         info: ask_pool.info.clone(),
@@ -562,8 +580,26 @@ pub fn swap(
         messages.push(return_asset.into_msg(&deps.querier, receiver.clone())?);
     }
 
+    let mut protocol_fee_amount = Uint128::zero();
+    if let Some(fee_treasury) = fee_config.fee_treasury {
+        if fee_config.total_fee_bps > 0 {
+            protocol_fee_amount = commission_amount
+                .multiply_ratio(fee_config.protocol_fee_bps, fee_config.total_fee_bps);
+        }
+
+        if !protocol_fee_amount.is_zero() {
+            let protocol_fee = Asset {
+                info: ask_pool.info.clone(),
+                amount: protocol_fee_amount,
+            };
+            messages.push(
+                protocol_fee.into_msg(&deps.querier, deps.api.addr_validate(&fee_treasury)?)?,
+            );
+        }
+    }
+
     // 1. send collateral token from the contract to a user
-    // 2. send inactive commission to collector
+    // 2. send the protocol part of the commission to the fee treasury
     Ok(Response::new().add_messages(messages).add_attributes(vec![
         ("action", "swap"),
         ("sender", sender.as_str()),
@@ -575,9 +611,66 @@ pub fn swap(
         ("tax_amount", &tax_amount.to_string()),
         ("spread_amount", &spread_amount.to_string()),
         ("commission_amount", &commission_amount.to_string()),
+        ("protocol_fee_amount", &protocol_fee_amount.to_string()),
     ]))
 }
 
+/// Loads the fee from the factory, pairs without a factory charge the default fee
+fn load_fee_config(deps: Deps<TerraQuery>, contract_addr: &Addr) -> StdResult<FeeConfigResponse> {
+    match FACTORY.may_load(deps.storage)? {
+        Some(factory) => query_fee_config(
+            &deps.querier,
+            deps.api.addr_humanize(&factory)?,
+            contract_addr.to_string(),
+        ),
+        None => Ok(FeeConfigResponse {
+            total_fee_bps: DEFAULT_TOTAL_FEE_BPS,
+            protocol_fee_bps: 0,
+            fee_treasury: None,
+        }),
+    }
+}
+
+/// Returns the admin of the pair if it is a factory managing the fee of the pair
+fn query_factory_admin(deps: Deps<TerraQuery>, env: &Env) -> StdResult<Option<Addr>> {
+    let admin = match deps
+        .querier
+        .query_wasm_contract_info(env.contract.address.clone())?
+        .admin
+    {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => return Ok(None),
+    };
+
+    Ok(query_fee_config(
+        &deps.querier,
+        admin.clone(),
+        env.contract.address.to_string(),
+    )
+    .ok()
+    .map(|_| admin))
+}
+
+/// The fees of a pair instantiated by another address, e.g. registered to a factory later,
+/// are managed by the factory administering it once anyone updates the factory
+pub fn update_factory(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let factory =
+        query_factory_admin(deps.as_ref(), &env)?.ok_or(ContractError::Unauthorized {})?;
+    FACTORY.save(deps.storage, &deps.api.addr_canonicalize(factory.as_str())?)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_factory"),
+        ("factory", factory.as_str()),
+    ]))
+}
+
+fn commission_rate(fee_config: &FeeConfigResponse) -> Decimal256 {
+    Decimal256::from_ratio(fee_config.total_fee_bps, 10_000u16)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps<TerraQuery>, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     match msg {
@@ -624,6 +717,7 @@ pub fn query_simulation(
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
 
     let contract_addr = deps.api.addr_humanize(&pair_info.contract_addr)?;
+    let fee_config = load_fee_config(deps, &contract_addr)?;
     let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, contract_addr)?;
 
     let offer_pool: Asset;
@@ -638,8 +732,12 @@ pub fn query_simulation(
         return Err(ContractError::AssetMismatch {});
     }
 
-    let (return_amount, spread_amount, commission_amount) =
-        compute_swap(offer_pool.amount, ask_pool.amount, offer_asset.amount)?;
+    let (return_amount, spread_amount, commission_amount) = compute_swap(
+        offer_pool.amount,
+        ask_pool.amount,
+        offer_asset.amount,
+        commission_rate(&fee_config),
+    )?;
 
     Ok(SimulationResponse {
         return_amount,
@@ -655,6 +753,7 @@ pub fn query_reverse_simulation(
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
 
     let contract_addr = deps.api.addr_humanize(&pair_info.contract_addr)?;
+    let fee_config = load_fee_config(deps, &contract_addr)?;
     let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, contract_addr)?;
 
     let offer_pool: Asset;
@@ -669,8 +768,12 @@ pub fn query_reverse_simulation(
         return Err(ContractError::AssetMismatch {});
     }
 
-    let (offer_amount, spread_amount, commission_amount) =
-        compute_offer_amount(offer_pool.amount, ask_pool.amount, ask_asset.amount)?;
+    let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
+        offer_pool.amount,
+        ask_pool.amount,
+        ask_asset.amount,
+        commission_rate(&fee_config),
+    )?;
 
     Ok(ReverseSimulationResponse {
         offer_amount,
@@ -683,13 +786,12 @@ fn compute_swap(
     offer_pool: Uint128,
     ask_pool: Uint128,
     offer_amount: Uint128,
+    commission_rate: Decimal256,
 ) -> StdResult<(Uint128, Uint128, Uint128)> {
     let offer_pool: Uint256 = offer_pool.into();
     let ask_pool: Uint256 = ask_pool.into();
     let offer_amount: Uint256 = offer_amount.into();
 
-    let commission_rate = Decimal256::permille(COMMISSION_RATE);
-
     // offer => ask
     // ask_amount = (ask_pool - cp / (offer_pool + offer_amount)) * (1 - commission_rate)
     let return_amount: Uint256 = (ask_pool * offer_amount) / (offer_pool + offer_amount);
@@ -698,7 +800,9 @@ fn compute_swap(
     let spread_amount: Uint256 =
         (offer_amount * Decimal256::from_ratio(ask_pool, offer_pool)) - return_amount;
     let mut commission_amount: Uint256 = return_amount * commission_rate;
-    if return_amount != (commission_amount * (Decimal256::one() / commission_rate)) {
+    if !commission_rate.is_zero()
+        && return_amount != (commission_amount * (Decimal256::one() / commission_rate))
+    {
         commission_amount += Uint256::from(1u128);
     }
 
@@ -718,9 +822,14 @@ fn test_compute_swap_with_huge_pool_variance() {
     let ask_pool = Uint128::from(317u128);
 
     assert_eq!(
-        compute_swap(offer_pool, ask_pool, Uint128::from(1u128))
-            .unwrap()
-            .0,
+        compute_swap(
+            offer_pool,
+            ask_pool,
+            Uint128::from(1u128),
+            Decimal256::permille(3)
+        )
+        .unwrap()
+        .0,
         Uint128::zero()
     );
 }
@@ -729,13 +838,12 @@ fn compute_offer_amount(
     offer_pool: Uint128,
     ask_pool: Uint128,
     ask_amount: Uint128,
+    commission_rate: Decimal256,
 ) -> StdResult<(Uint128, Uint128, Uint128)> {
     let offer_pool: Uint256 = offer_pool.into();
     let ask_pool: Uint256 = ask_pool.into();
     let ask_amount: Uint256 = ask_amount.into();
 
-    let commission_rate = Decimal256::permille(COMMISSION_RATE);
-
     // ask => offer
     // offer_amount = cp / (ask_pool - ask_amount / (1 - commission_rate)) - offer_pool
     let cp: Uint256 = offer_pool * ask_pool;
@@ -885,9 +993,17 @@ const TARGET_CONTRACT_VERSION: &str = "0.1.1";
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(
     deps: DepsMut<TerraQuery>,
-    _env: Env,
+    env: Env,
     _msg: MigrateMsg,
 ) -> Result<Response<TerraMsg>, ContractError> {
+    // pairs created before the fees were managed by the factory,
+    // which is their admin
+    if FACTORY.may_load(deps.storage)?.is_none() {
+        if let Some(factory) = query_factory_admin(deps.as_ref(), &env)? {
+            FACTORY.save(deps.storage, &deps.api.addr_canonicalize(factory.as_str())?)?;
+        }
+    }
+
     migrate_version(
         deps,
         TARGET_CONTRACT_VERSION,
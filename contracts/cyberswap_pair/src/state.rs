@@ -1,4 +1,8 @@
 use classic_cyberswap::asset::PairInfoRaw;
+use cosmwasm_std::CanonicalAddr;
 use cw_storage_plus::Item;
 
 pub const PAIR_INFO: Item<PairInfoRaw> = Item::new("pair_info");
+
+/// Factory managing the fees of the pair
+pub const FACTORY: Item<CanonicalAddr> = Item::new("factory");
@@ -877,6 +877,7 @@ fn try_native_to_token() {
             attr("tax_amount", expected_tax_amount.to_string()),
             attr("spread_amount", expected_spread_amount.to_string()),
             attr("commission_amount", expected_commission_amount.to_string()),
+            attr("protocol_fee_amount", "0"),
         ]
     );
 
@@ -894,6 +895,308 @@ fn try_native_to_token() {
     );
 }
 
+#[test]
+fn try_native_to_token_with_factory_fee_config() {
+    let total_share = Uint128::from(30000000000u128);
+    let asset_pool_amount = Uint128::from(20000000000u128);
+    let collateral_pool_amount = Uint128::from(30000000000u128);
+    let offer_amount = Uint128::from(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    // 0.5% fee, 0.1% of it goes to the treasury
+    deps.querier
+        .with_fee_config(50u16, 10u16, Some("treasury0000".to_string()));
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: "asset0000".to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 8u8],
+    };
+
+    let info = mock_info("factory0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // 952380952 before the commission of 4761905
+    let expected_return_amount = Uint128::from(947619047u128);
+    let expected_commission_amount = Uint128::from(4761905u128);
+    let expected_protocol_fee_amount = Uint128::from(952381u128);
+
+    assert_eq!(
+        res.attributes[9..],
+        vec![
+            attr("commission_amount", expected_commission_amount.to_string()),
+            attr(
+                "protocol_fee_amount",
+                expected_protocol_fee_amount.to_string()
+            ),
+        ]
+    );
+
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "asset0000".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "addr0000".to_string(),
+                    amount: expected_return_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "asset0000".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "treasury0000".to_string(),
+                    amount: expected_protocol_fee_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+}
+
+#[test]
+fn try_native_to_token_without_factory_fee_config() {
+    let total_share = Uint128::from(30000000000u128);
+    let asset_pool_amount = Uint128::from(20000000000u128);
+    let collateral_pool_amount = Uint128::from(30000000000u128);
+    let offer_amount = Uint128::from(1500000000u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: collateral_pool_amount + offer_amount, /* user deposit must be pre-applied */
+    }]);
+
+    deps.querier.with_tax(
+        Decimal::zero(),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &total_share)],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+        ),
+    ]);
+
+    // the instantiator is not a factory
+    deps.querier.without_fee_config();
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: "asset0000".to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 8u8],
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // the default fee of 0.3% is charged
+    let res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: "asset0000".to_string(),
+            },
+            amount: offer_amount,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.commission_amount, Uint128::from(6593024u128));
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // 952380952 before the commission of 2857143
+    let expected_return_amount = Uint128::from(949523809u128);
+    let expected_commission_amount = Uint128::from(2857143u128);
+
+    assert_eq!(
+        res.attributes[9..],
+        vec![
+            attr("commission_amount", expected_commission_amount.to_string()),
+            attr("protocol_fee_amount", "0"),
+        ]
+    );
+
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "asset0000".to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: expected_return_amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        }))]
+    );
+}
+
+#[test]
+fn update_factory_of_registered_pair() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(30000000000u128),
+    }]);
+    deps.querier.with_token_balances(&[
+        (
+            &"liquidity0000".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(30000000000u128),
+            )],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(20000000000u128),
+            )],
+        ),
+    ]);
+
+    // instantiated by an address that is not a factory
+    deps.querier.without_fee_config();
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: "asset0000".to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 8u8],
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let offer_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: "asset0000".to_string(),
+        },
+        amount: Uint128::from(1500000000u128),
+    };
+    let res = query_simulation(deps.as_ref(), offer_asset.clone()).unwrap();
+    assert_eq!(res.commission_amount, Uint128::from(6279070u128));
+
+    // the admin is not a factory
+    deps.querier
+        .with_contract_admins(&[(&MOCK_CONTRACT_ADDR.to_string(), &"factory0000".to_string())]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::UpdateFactory {},
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    // the pair is registered to the factory administering it
+    deps.querier
+        .with_fee_config(50u16, 10u16, Some("treasury0000".to_string()));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        ExecuteMsg::UpdateFactory {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_factory"),
+            attr("factory", "factory0000"),
+        ]
+    );
+
+    let res = query_simulation(deps.as_ref(), offer_asset.clone()).unwrap();
+    assert_eq!(res.commission_amount, Uint128::from(10465117u128));
+
+    // a failing factory fails the swap instead of charging the default fee
+    deps.querier.without_fee_config();
+    query_simulation(deps.as_ref(), offer_asset).unwrap_err();
+}
+
 #[test]
 fn try_token_to_native() {
     let total_share = Uint128::from(20000000000u128);
@@ -1085,6 +1388,7 @@ fn try_token_to_native() {
             attr("tax_amount", expected_tax_amount.to_string()),
             attr("spread_amount", expected_spread_amount.to_string()),
             attr("commission_amount", expected_commission_amount.to_string()),
+            attr("protocol_fee_amount", "0"),
         ]
     );
 
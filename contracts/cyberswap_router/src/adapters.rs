@@ -0,0 +1,244 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    to_binary, Addr, Coin, CosmosMsg, Decimal, Deps, QueryRequest, StdError, StdResult, WasmMsg,
+    WasmQuery,
+};
+
+use crate::operations::asset_into_swap_msg;
+use crate::querier::compute_tax;
+use crate::state::{Config, CONFIG};
+
+use classic_bindings::{TerraMsg, TerraQuery};
+
+use classic_cyberswap::asset::{Asset, AssetInfo, PairInfo};
+use classic_cyberswap::pair::{
+    QueryMsg as PairQueryMsg, ReverseSimulationResponse, SimulationResponse,
+};
+use classic_cyberswap::querier::query_pair_info;
+use classic_cyberswap::router::SwapOperation;
+use cw20::Cw20ExecuteMsg;
+
+/// DEX whose pair contracts execute the swap operations
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dex {
+    CyberSwap,
+    TerraSwap,
+    Astroport,
+}
+
+impl fmt::Display for Dex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dex::CyberSwap => write!(f, "cyberswap"),
+            Dex::TerraSwap => write!(f, "terraswap"),
+            Dex::Astroport => write!(f, "astroport"),
+        }
+    }
+}
+
+/// Returns the DEX executing the operation
+pub fn operation_dex(operation: &SwapOperation) -> StdResult<Dex> {
+    match operation {
+        SwapOperation::NativeSwap { .. } => Err(StdError::generic_err(
+            "native_swap is not executed by a pair",
+        )),
+        SwapOperation::CyberSwap { .. } => Ok(Dex::CyberSwap),
+        SwapOperation::TerraSwap { .. } => Ok(Dex::TerraSwap),
+        SwapOperation::Astroport { .. } => Ok(Dex::Astroport),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum DexFactoryQueryMsg {
+    Pair { asset_infos: [AssetInfo; 2] },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+struct DexPairResponse {
+    contract_addr: String,
+}
+
+/// Astroport pairs take the ask asset along with the offer asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum AstroportPairQueryMsg {
+    Simulation {
+        offer_asset: Asset,
+        ask_asset_info: Option<AssetInfo>,
+    },
+    ReverseSimulation {
+        offer_asset_info: Option<AssetInfo>,
+        ask_asset: Asset,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum DexPairExecuteMsg {
+    Swap {
+        offer_asset: Asset,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ask_asset_info: Option<AssetInfo>,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum DexPairCw20HookMsg {
+    Swap {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ask_asset_info: Option<AssetInfo>,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+    },
+}
+
+fn query_dex_factory(deps: Deps<TerraQuery>, dex: Dex) -> StdResult<Addr> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let factory = match dex {
+        Dex::CyberSwap => Some(config.cyberswap_factory),
+        Dex::TerraSwap => config.terraswap_factory,
+        Dex::Astroport => config.astroport_factory,
+    };
+
+    match factory {
+        Some(factory) => deps.api.addr_humanize(&factory),
+        None => Err(StdError::generic_err(format!(
+            "{} factory is not configured",
+            dex
+        ))),
+    }
+}
+
+/// Returns the pair contract of the assets on the DEX
+pub fn query_dex_pair(
+    deps: Deps<TerraQuery>,
+    dex: Dex,
+    asset_infos: [AssetInfo; 2],
+) -> StdResult<Addr> {
+    let factory = query_dex_factory(deps, dex)?;
+    let contract_addr = match dex {
+        Dex::CyberSwap => {
+            let pair_info: PairInfo = query_pair_info(&deps.querier, factory, &asset_infos)?;
+            pair_info.contract_addr
+        }
+        Dex::TerraSwap | Dex::Astroport => {
+            let res: DexPairResponse =
+                deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                    contract_addr: factory.to_string(),
+                    msg: to_binary(&DexFactoryQueryMsg::Pair { asset_infos })?,
+                }))?;
+            res.contract_addr
+        }
+    };
+
+    Ok(Addr::unchecked(contract_addr))
+}
+
+pub fn simulate_dex_swap(
+    deps: Deps<TerraQuery>,
+    dex: Dex,
+    pair_contract: Addr,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+) -> StdResult<SimulationResponse> {
+    let msg = match dex {
+        Dex::CyberSwap | Dex::TerraSwap => to_binary(&PairQueryMsg::Simulation { offer_asset })?,
+        Dex::Astroport => to_binary(&AstroportPairQueryMsg::Simulation {
+            offer_asset,
+            ask_asset_info: Some(ask_asset_info),
+        })?,
+    };
+
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: pair_contract.to_string(),
+        msg,
+    }))
+}
+
+pub fn reverse_simulate_dex_swap(
+    deps: Deps<TerraQuery>,
+    dex: Dex,
+    pair_contract: Addr,
+    offer_asset_info: AssetInfo,
+    ask_asset: Asset,
+) -> StdResult<ReverseSimulationResponse> {
+    let msg = match dex {
+        Dex::CyberSwap | Dex::TerraSwap => {
+            to_binary(&PairQueryMsg::ReverseSimulation { ask_asset })?
+        }
+        Dex::Astroport => to_binary(&AstroportPairQueryMsg::ReverseSimulation {
+            offer_asset_info: Some(offer_asset_info),
+            ask_asset,
+        })?,
+    };
+
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: pair_contract.to_string(),
+        msg,
+    }))
+}
+
+/// Returns the message swapping the offer asset on the DEX pair
+pub fn dex_swap_msg(
+    deps: Deps<TerraQuery>,
+    dex: Dex,
+    pair_contract: Addr,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+    to: Option<String>,
+) -> StdResult<CosmosMsg<TerraMsg>> {
+    let ask_asset_info = match dex {
+        Dex::CyberSwap => return asset_into_swap_msg(deps, pair_contract, offer_asset, None, to),
+        Dex::TerraSwap => None,
+        Dex::Astroport => Some(ask_asset_info),
+    };
+
+    match offer_asset.info.clone() {
+        AssetInfo::NativeToken { denom } => {
+            // deduct tax first
+            let amount = offer_asset.amount.checked_sub(compute_tax(
+                &deps.querier,
+                offer_asset.amount,
+                denom.clone(),
+            )?)?;
+
+            Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: pair_contract.to_string(),
+                funds: vec![Coin { denom, amount }],
+                msg: to_binary(&DexPairExecuteMsg::Swap {
+                    offer_asset: Asset {
+                        amount,
+                        ..offer_asset
+                    },
+                    ask_asset_info,
+                    belief_price: None,
+                    max_spread: None,
+                    to,
+                })?,
+            }))
+        }
+        AssetInfo::Token { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: pair_contract.to_string(),
+                amount: offer_asset.amount,
+                msg: to_binary(&DexPairCw20HookMsg::Swap {
+                    ask_asset_info,
+                    belief_price: None,
+                    max_spread: None,
+                    to,
+                })?,
+            })?,
+        })),
+    }
+}
@@ -0,0 +1,163 @@
+use cosmwasm_std::{
+    to_binary, Addr, CosmosMsg, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+    WasmMsg,
+};
+
+use crate::contract::assert_operations;
+use crate::operations::route_msgs;
+use crate::state::{Config, CONFIG};
+
+use classic_bindings::{TerraMsg, TerraQuery};
+
+use classic_cyberswap::asset::AssetInfo;
+use classic_cyberswap::router::{BatchSwapInput, ExecuteMsg};
+use classic_cyberswap::util::assert_deadline;
+use cw20::Cw20ExecuteMsg;
+
+/// Swaps every input into the ask asset and asserts the total received amount
+#[allow(clippy::too_many_arguments)]
+pub fn execute_batch_swap(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+    inputs: Vec<BatchSwapInput>,
+    ask_asset_info: AssetInfo,
+    minimum_receive: Option<Uint128>,
+    to: Option<Addr>,
+    deadline: Option<u64>,
+) -> StdResult<Response<TerraMsg>> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+
+    if inputs.is_empty() {
+        return Err(StdError::generic_err("must provide inputs"));
+    }
+
+    let offer_asset_infos: Vec<AssetInfo> = inputs
+        .iter()
+        .map(|input| input.offer_asset.info.clone())
+        .collect();
+
+    // the sent coins must all be swapped
+    for coin in info.funds.iter() {
+        let coin_asset_info = AssetInfo::NativeToken {
+            denom: coin.denom.clone(),
+        };
+        if !offer_asset_infos.contains(&coin_asset_info) {
+            return Err(StdError::generic_err(format!(
+                "no input for the sent {}",
+                coin.denom
+            )));
+        }
+    }
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    for (index, input) in inputs.iter().enumerate() {
+        let offer_asset = &input.offer_asset;
+        if offer_asset.amount.is_zero() {
+            return Err(StdError::generic_err("must provide offer amount"));
+        }
+
+        if offer_asset_infos[..index].contains(&offer_asset.info) {
+            return Err(StdError::generic_err(format!(
+                "duplicate offer asset {}",
+                offer_asset.info
+            )));
+        }
+
+        offer_asset.assert_sent_native_token_balance(&info)?;
+
+        let (first_operation, last_operation) =
+            match (input.operations.first(), input.operations.last()) {
+                (Some(first_operation), Some(last_operation)) => (first_operation, last_operation),
+                _ if offer_asset.info == ask_asset_info => continue,
+                _ => return Err(StdError::generic_err("must provide operations")),
+            };
+
+        if first_operation.get_offer_asset_info() != offer_asset.info
+            || last_operation.get_target_asset_info() != ask_asset_info
+        {
+            return Err(StdError::generic_err(format!(
+                "operations must swap {} into {}",
+                offer_asset.info, ask_asset_info
+            )));
+        }
+
+        // the following operations swap the whole pending amount of their offer asset
+        if input.operations[1..].iter().any(|operation| {
+            let operation_offer_asset_info = operation.get_offer_asset_info();
+            operation_offer_asset_info == ask_asset_info
+                || offer_asset_infos.contains(&operation_offer_asset_info)
+        }) {
+            return Err(StdError::generic_err(
+                "operations cannot go through the offer assets",
+            ));
+        }
+
+        assert_operations(&deps.querier, deps.api, &config, &input.operations)?;
+    }
+
+    let to = if let Some(to) = to {
+        to
+    } else {
+        info.sender.clone()
+    };
+
+    let mut messages: Vec<CosmosMsg<TerraMsg>> = vec![];
+    for input in inputs {
+        let offer_asset = input.offer_asset;
+
+        // the inputs of the ask asset go straight to the receiver
+        if input.operations.is_empty() {
+            messages.push(match &offer_asset.info {
+                AssetInfo::NativeToken { .. } => offer_asset.into_msg(&deps.querier, to.clone())?,
+                AssetInfo::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract_addr.to_string(),
+                    funds: vec![],
+                    msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                        owner: info.sender.to_string(),
+                        recipient: to.to_string(),
+                        amount: offer_asset.amount,
+                    })?,
+                }),
+            });
+            continue;
+        }
+
+        if let AssetInfo::Token { contract_addr } = &offer_asset.info {
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: info.sender.to_string(),
+                    recipient: env.contract.address.to_string(),
+                    amount: offer_asset.amount,
+                })?,
+            }));
+        }
+
+        messages.extend(route_msgs(
+            &env,
+            input.operations,
+            Some(offer_asset.amount),
+            Some(to.to_string()),
+        )?);
+    }
+
+    // Execute minimum amount assertion
+    if let Some(minimum_receive) = minimum_receive {
+        let receiver_balance = ask_asset_info.query_pool(&deps.querier, deps.api, to.clone())?;
+
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+                asset_info: ask_asset_info,
+                prev_balance: receiver_balance,
+                minimum_receive,
+                receiver: to.to_string(),
+            })?,
+        }))
+    }
+
+    Ok(Response::new().add_messages(messages))
+}
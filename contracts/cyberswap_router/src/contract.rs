@@ -2,27 +2,43 @@
 use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{
-    from_binary, to_binary, Addr, Api, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
-    QueryRequest, Response, StdError, StdResult, Uint128, WasmMsg, WasmQuery,
+    from_binary, to_binary, Addr, Api, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    MessageInfo, QuerierWrapper, Reply, Response, StdError, StdResult, Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
 
-use crate::operations::execute_swap_operation;
+use crate::adapters::{
+    operation_dex, query_dex_pair, reverse_simulate_dex_swap, simulate_dex_swap, Dex,
+};
+use crate::batch::execute_batch_swap;
+use crate::hook::{assert_post_swap_hook, execute_post_swap_hook};
+use crate::ibc::{
+    ibc_lifecycle_complete, ibc_transfer_pending, store_ibc_transfer, IBC_TRANSFER_REPLY_ID,
+};
+use crate::operations::{
+    credit_swap_return, credit_tracked_balances, execute_swap_operation, route_msgs, SWAP_REPLY_ID,
+    TRACK_BALANCES_REPLY_ID,
+};
 use crate::querier::{compute_reverse_tax, compute_tax};
+use crate::routes::find_best_route;
+use crate::split::{execute_split_swap, simulate_split_swap};
 use crate::state::{Config, CONFIG};
+use crate::zap::{execute_zap_in, execute_zap_out, transfer_pending, zap_provide_liquidity};
 
 use classic_bindings::{SwapResponse, TerraMsg, TerraQuerier, TerraQuery};
 
-use classic_cyberswap::asset::{Asset, AssetInfo, PairInfo};
-use classic_cyberswap::pair::{QueryMsg as PairQueryMsg, SimulationResponse};
-use classic_cyberswap::querier::{query_pair_info, reverse_simulate};
+use classic_cyberswap::asset::{Asset, AssetInfo};
+use classic_cyberswap::pair::SimulationResponse;
+use classic_cyberswap::querier::query_is_blocked;
 use classic_cyberswap::router::{
-    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
-    SimulateSwapOperationsResponse, SwapOperation,
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, IbcForward, InstantiateMsg, MigrateMsg, PostSwapHook,
+    QueryMsg, RouteError, RouteSimulation, SimulateManyResponse,
+    SimulateSwapOperationsDetailedResponse, SimulateSwapOperationsResponse, SudoMsg,
+    SwapHopSimulation, SwapOperation, ValidateRouteResponse,
 };
 use classic_cyberswap::util::assert_deadline;
 use cw20::Cw20ReceiveMsg;
-use std::collections::HashMap;
+use std::cmp::Reverse;
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cyberswap-router";
@@ -32,7 +48,7 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub fn instantiate(
     deps: DepsMut<TerraQuery>,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response<TerraMsg>> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
@@ -41,6 +57,13 @@ pub fn instantiate(
         deps.storage,
         &Config {
             cyberswap_factory: deps.api.addr_canonicalize(&msg.cyberswap_factory)?,
+            owner: deps.api.addr_canonicalize(info.sender.as_str())?,
+            route_assets: vec![],
+            terraswap_factory: None,
+            astroport_factory: None,
+            max_hops: None,
+            blocked_intermediate_assets: vec![],
+            forbid_pair_revisit: false,
         },
     )?;
 
@@ -61,20 +84,182 @@ pub fn execute(
             minimum_receive,
             to,
             deadline,
+            ibc_forward,
+            post_swap_hook,
         } => {
+            let offer_asset = native_offer_asset(&info, &operations)?;
+
             let api = deps.api;
             execute_swap_operations(
                 deps,
                 env,
                 info.sender,
+                offer_asset,
+                operations,
+                minimum_receive,
+                optional_addr_validate(api, to)?,
+                deadline,
+                ibc_forward,
+                post_swap_hook,
+            )
+        }
+        ExecuteMsg::ExecuteBestSwap {
+            offer_asset,
+            ask_asset_info,
+            max_hops,
+            minimum_receive,
+            to,
+            deadline,
+        } => {
+            if !offer_asset.is_native_token() {
+                return Err(StdError::generic_err(
+                    "token offers must be sent through the cw20 receive hook",
+                ));
+            }
+            offer_asset.assert_sent_native_token_balance(&info)?;
+
+            let api = deps.api;
+            execute_best_swap(
+                deps,
+                env,
+                info.sender,
+                offer_asset,
+                ask_asset_info,
+                max_hops,
+                minimum_receive,
+                optional_addr_validate(api, to)?,
+                deadline,
+            )
+        }
+        ExecuteMsg::ExecuteSplitSwap {
+            routes,
+            minimum_receive,
+            to,
+            deadline,
+        } => {
+            let offer_asset = native_offer_asset(
+                &info,
+                routes.first().map_or(&[], |route| &route.operations[..]),
+            )?;
+
+            let api = deps.api;
+            execute_split_swap(
+                deps,
+                env,
+                info.sender,
+                offer_asset,
+                routes,
+                minimum_receive,
+                optional_addr_validate(api, to)?,
+                deadline,
+            )
+        }
+        ExecuteMsg::ExecuteSwapOperationsExactOut {
+            operations,
+            ask_amount,
+            max_offer,
+            to,
+            deadline,
+        } => {
+            let offer_asset = native_offer_asset(&info, &operations)?;
+
+            let api = deps.api;
+            execute_swap_operations_exact_out(
+                deps,
+                env,
+                info.sender,
+                offer_asset,
                 operations,
+                ask_amount,
+                max_offer,
+                optional_addr_validate(api, to)?,
+                deadline,
+            )
+        }
+        ExecuteMsg::ZapIn {
+            offer_asset,
+            pair,
+            operations_a,
+            operations_b,
+            min_lp,
+            receiver,
+            deadline,
+        } => {
+            if !offer_asset.is_native_token() {
+                return Err(StdError::generic_err(
+                    "token offers must be sent through the cw20 receive hook",
+                ));
+            }
+            offer_asset.assert_sent_native_token_balance(&info)?;
+
+            let api = deps.api;
+            execute_zap_in(
+                deps,
+                env,
+                info.sender,
+                offer_asset,
+                pair,
+                operations_a,
+                operations_b,
+                min_lp,
+                optional_addr_validate(api, receiver)?,
+                deadline,
+            )
+        }
+        ExecuteMsg::ExecuteBatchSwap {
+            inputs,
+            ask_asset_info,
+            minimum_receive,
+            to,
+            deadline,
+        } => {
+            let api = deps.api;
+            execute_batch_swap(
+                deps,
+                env,
+                info,
+                inputs,
+                ask_asset_info,
                 minimum_receive,
                 optional_addr_validate(api, to)?,
                 deadline,
             )
         }
+        ExecuteMsg::UpdateConfig {
+            route_assets,
+            terraswap_factory,
+            astroport_factory,
+            max_hops,
+            blocked_intermediate_assets,
+            forbid_pair_revisit,
+        } => execute_update_config(
+            deps,
+            info,
+            route_assets,
+            terraswap_factory,
+            astroport_factory,
+            max_hops,
+            blocked_intermediate_assets,
+            forbid_pair_revisit,
+        ),
+        ExecuteMsg::Sweep {
+            asset_info,
+            amount,
+            receiver,
+        } => {
+            let api = deps.api;
+            execute_sweep(
+                deps,
+                env,
+                info,
+                asset_info,
+                amount,
+                optional_addr_validate(api, receiver)?,
+            )
+        }
         ExecuteMsg::ExecuteSwapOperation {
             operation,
+            amount,
             to,
             deadline,
         } => {
@@ -84,10 +269,41 @@ pub fn execute(
                 env,
                 info,
                 operation,
+                amount,
                 optional_addr_validate(api, to)?.map(|v| v.to_string()),
                 deadline,
             )
         }
+        ExecuteMsg::ZapProvideLiquidity {
+            pair_contract,
+            receiver,
+        } => zap_provide_liquidity(
+            deps,
+            env,
+            info,
+            Addr::unchecked(pair_contract),
+            Addr::unchecked(receiver),
+        ),
+        ExecuteMsg::TransferPending {
+            asset_info,
+            receiver,
+        } => transfer_pending(deps, env, info, asset_info, Addr::unchecked(receiver)),
+        ExecuteMsg::IbcTransferPending {
+            asset_info,
+            ibc_forward,
+            refund_to,
+        } => ibc_transfer_pending(
+            deps,
+            env,
+            info,
+            asset_info,
+            ibc_forward,
+            Addr::unchecked(refund_to),
+        ),
+        ExecuteMsg::ExecutePostSwapHook {
+            asset_info,
+            post_swap_hook,
+        } => execute_post_swap_hook(deps, env, info, asset_info, post_swap_hook),
         ExecuteMsg::AssertMinimumReceive {
             asset_info,
             prev_balance,
@@ -108,6 +324,32 @@ pub fn execute(
     }
 }
 
+/// Returns the native offer asset of the operations sent along with the message
+fn native_offer_asset(info: &MessageInfo, operations: &[SwapOperation]) -> StdResult<Asset> {
+    let offer_asset_info = operations
+        .first()
+        .map(|operation| operation.get_offer_asset_info())
+        .ok_or_else(|| StdError::generic_err("must provide operations"))?;
+
+    let AssetInfo::NativeToken { denom } = &offer_asset_info else {
+        return Err(StdError::generic_err(
+            "token offers must be sent through the cw20 receive hook",
+        ));
+    };
+
+    let amount = info
+        .funds
+        .iter()
+        .find(|coin| coin.denom == *denom)
+        .map(|coin| coin.amount)
+        .unwrap_or_default();
+
+    Ok(Asset {
+        info: offer_asset_info,
+        amount,
+    })
+}
+
 fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<Option<Addr>> {
     let addr = if let Some(addr) = addr {
         Some(api.addr_validate(&addr).unwrap_or(Addr::unchecked(addr)))
@@ -122,7 +364,7 @@ fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<Opti
 pub fn receive_cw20(
     deps: DepsMut<TerraQuery>,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     cw20_msg: Cw20ReceiveMsg,
 ) -> StdResult<Response<TerraMsg>> {
     let sender = Addr::unchecked(cw20_msg.sender);
@@ -132,29 +374,292 @@ pub fn receive_cw20(
             minimum_receive,
             to,
             deadline,
+            ibc_forward,
+            post_swap_hook,
         } => {
             let api = deps.api;
             execute_swap_operations(
                 deps,
                 env,
                 sender,
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: info.sender.to_string(),
+                    },
+                    amount: cw20_msg.amount,
+                },
                 operations,
                 minimum_receive,
                 optional_addr_validate(api, to)?,
                 deadline,
+                ibc_forward,
+                post_swap_hook,
+            )
+        }
+        Cw20HookMsg::ExecuteBestSwap {
+            ask_asset_info,
+            max_hops,
+            minimum_receive,
+            to,
+            deadline,
+        } => {
+            let api = deps.api;
+            execute_best_swap(
+                deps,
+                env,
+                sender,
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: info.sender.to_string(),
+                    },
+                    amount: cw20_msg.amount,
+                },
+                ask_asset_info,
+                max_hops,
+                minimum_receive,
+                optional_addr_validate(api, to)?,
+                deadline,
+            )
+        }
+        Cw20HookMsg::ExecuteSplitSwap {
+            routes,
+            minimum_receive,
+            to,
+            deadline,
+        } => {
+            let api = deps.api;
+            execute_split_swap(
+                deps,
+                env,
+                sender,
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: info.sender.to_string(),
+                    },
+                    amount: cw20_msg.amount,
+                },
+                routes,
+                minimum_receive,
+                optional_addr_validate(api, to)?,
+                deadline,
+            )
+        }
+        Cw20HookMsg::ExecuteSwapOperationsExactOut {
+            operations,
+            ask_amount,
+            max_offer,
+            to,
+            deadline,
+        } => {
+            let api = deps.api;
+            execute_swap_operations_exact_out(
+                deps,
+                env,
+                sender,
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: info.sender.to_string(),
+                    },
+                    amount: cw20_msg.amount,
+                },
+                operations,
+                ask_amount,
+                max_offer,
+                optional_addr_validate(api, to)?,
+                deadline,
+            )
+        }
+        Cw20HookMsg::ZapIn {
+            pair,
+            operations_a,
+            operations_b,
+            min_lp,
+            receiver,
+            deadline,
+        } => {
+            let api = deps.api;
+            execute_zap_in(
+                deps,
+                env,
+                sender,
+                Asset {
+                    info: AssetInfo::Token {
+                        contract_addr: info.sender.to_string(),
+                    },
+                    amount: cw20_msg.amount,
+                },
+                pair,
+                operations_a,
+                operations_b,
+                min_lp,
+                optional_addr_validate(api, receiver)?,
+                deadline,
+            )
+        }
+        Cw20HookMsg::ZapOut {
+            operations_to,
+            ask_asset_info,
+            min_receive,
+            receiver,
+            deadline,
+        } => {
+            let api = deps.api;
+            execute_zap_out(
+                deps,
+                env,
+                sender,
+                info.sender,
+                cw20_msg.amount,
+                operations_to,
+                ask_asset_info,
+                min_receive,
+                optional_addr_validate(api, receiver)?,
+                deadline,
             )
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn execute_update_config(
+    deps: DepsMut<TerraQuery>,
+    info: MessageInfo,
+    route_assets: Option<Vec<AssetInfo>>,
+    terraswap_factory: Option<String>,
+    astroport_factory: Option<String>,
+    max_hops: Option<u32>,
+    blocked_intermediate_assets: Option<Vec<AssetInfo>>,
+    forbid_pair_revisit: Option<bool>,
+) -> StdResult<Response<TerraMsg>> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    if let Some(route_assets) = route_assets {
+        config.route_assets = route_assets
+            .iter()
+            .map(|asset_info| asset_info.to_raw(deps.api))
+            .collect::<StdResult<Vec<_>>>()?;
+    }
+
+    if let Some(terraswap_factory) = terraswap_factory {
+        config.terraswap_factory = Some(deps.api.addr_canonicalize(&terraswap_factory)?);
+    }
+
+    if let Some(astroport_factory) = astroport_factory {
+        config.astroport_factory = Some(deps.api.addr_canonicalize(&astroport_factory)?);
+    }
+
+    if let Some(max_hops) = max_hops {
+        if max_hops == 0 {
+            return Err(StdError::generic_err("max_hops must be positive"));
+        }
+        config.max_hops = Some(max_hops);
+    }
+
+    if let Some(blocked_intermediate_assets) = blocked_intermediate_assets {
+        config.blocked_intermediate_assets = blocked_intermediate_assets
+            .iter()
+            .map(|asset_info| asset_info.to_raw(deps.api))
+            .collect::<StdResult<Vec<_>>>()?;
+    }
+
+    if let Some(forbid_pair_revisit) = forbid_pair_revisit {
+        config.forbid_pair_revisit = forbid_pair_revisit;
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
+
+/// Sends the router balance of the asset to the receiver, the swaps only spend
+/// the amounts of their own transaction so anything else is stuck funds
+pub fn execute_sweep(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+    asset_info: AssetInfo,
+    amount: Option<Uint128>,
+    receiver: Option<Addr>,
+) -> StdResult<Response<TerraMsg>> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let balance = asset_info.query_pool(&deps.querier, deps.api, env.contract.address)?;
+    let amount = amount.unwrap_or(balance);
+    if amount.is_zero() {
+        return Err(StdError::generic_err("nothing to sweep"));
+    }
+
+    if amount > balance {
+        return Err(StdError::generic_err(format!(
+            "insufficient balance; balance: {}, amount: {}",
+            balance, amount
+        )));
+    }
+
+    let receiver = receiver.unwrap_or(info.sender);
+    Ok(Response::new()
+        .add_message(
+            Asset {
+                info: asset_info.clone(),
+                amount,
+            }
+            .into_msg(&deps.querier, receiver.clone())?,
+        )
+        .add_attributes(vec![
+            ("action", "sweep".to_string()),
+            ("asset", asset_info.to_string()),
+            ("amount", amount.to_string()),
+            ("receiver", receiver.to_string()),
+        ]))
+}
+
+/// Swaps along the route with the highest return
+#[allow(clippy::too_many_arguments)]
+pub fn execute_best_swap(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+    max_hops: Option<u32>,
+    minimum_receive: Uint128,
+    to: Option<Addr>,
+    deadline: Option<u64>,
+) -> StdResult<Response<TerraMsg>> {
+    let route = find_best_route(deps.as_ref(), offer_asset.clone(), ask_asset_info, max_hops)?;
+
+    execute_swap_operations(
+        deps,
+        env,
+        sender,
+        offer_asset,
+        route.operations,
+        Some(minimum_receive),
+        to,
+        deadline,
+        None,
+        None,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn execute_swap_operations(
     deps: DepsMut<TerraQuery>,
     env: Env,
     sender: Addr,
+    offer_asset: Asset,
     operations: Vec<SwapOperation>,
     minimum_receive: Option<Uint128>,
     to: Option<Addr>,
     deadline: Option<u64>,
+    ibc_forward: Option<IbcForward>,
+    post_swap_hook: Option<PostSwapHook>,
 ) -> StdResult<Response<TerraMsg>> {
     assert_deadline(env.block.time.seconds(), deadline)?;
     let operations_len = operations.len();
@@ -162,12 +667,53 @@ pub fn execute_swap_operations(
         return Err(StdError::generic_err("must provide operations"));
     }
 
+    if operations[0].get_offer_asset_info() != offer_asset.info {
+        return Err(StdError::generic_err(
+            "operations must start with the offer asset",
+        ));
+    }
+
+    if offer_asset.amount.is_zero() {
+        return Err(StdError::generic_err("must provide offer amount"));
+    }
+
     // Assert the operations are properly set
-    assert_operations(&operations)?;
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_operations(&deps.querier, deps.api, &config, &operations)?;
 
-    let to = if let Some(to) = to { to } else { sender };
     let target_asset_info = operations.last().unwrap().get_target_asset_info();
 
+    // the return forwarded over IBC or delivered to the hook
+    // stays in the router until the transfer or the hook
+    let to = match (&ibc_forward, &post_swap_hook) {
+        (Some(_), Some(_)) => {
+            return Err(StdError::generic_err(
+                "cannot forward over ibc and call a post swap hook",
+            ));
+        }
+        (Some(_), None) if to.is_some() => {
+            return Err(StdError::generic_err(
+                "cannot forward over ibc to a local receiver",
+            ));
+        }
+        (Some(_), None) if !target_asset_info.is_native_token() => {
+            return Err(StdError::generic_err(
+                "ibc forwarding requires a native output",
+            ));
+        }
+        (None, Some(_)) if to.is_some() => {
+            return Err(StdError::generic_err(
+                "cannot call a post swap hook with a receiver",
+            ));
+        }
+        (Some(_), None) | (None, Some(_)) => env.contract.address.clone(),
+        (None, None) => to.unwrap_or_else(|| sender.clone()),
+    };
+    if let Some(post_swap_hook) = &post_swap_hook {
+        assert_post_swap_hook(post_swap_hook, &sender)?;
+    }
+    let keep_return = ibc_forward.is_some() || post_swap_hook.is_some();
+
     let mut operation_index = 0;
     let mut messages: Vec<CosmosMsg<TerraMsg>> = operations
         .into_iter()
@@ -178,7 +724,12 @@ pub fn execute_swap_operations(
                 funds: vec![],
                 msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
                     operation: op,
-                    to: if operation_index == operations_len {
+                    amount: if operation_index == 1 {
+                        Some(offer_asset.amount)
+                    } else {
+                        None
+                    },
+                    to: if operation_index == operations_len && !keep_return {
                         Some(to.to_string())
                     } else {
                         None
@@ -197,7 +748,7 @@ pub fn execute_swap_operations(
             contract_addr: env.contract.address.to_string(),
             funds: vec![],
             msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
-                asset_info: target_asset_info,
+                asset_info: target_asset_info.clone(),
                 prev_balance: receiver_balance,
                 minimum_receive,
                 receiver: to.to_string(),
@@ -205,9 +756,119 @@ pub fn execute_swap_operations(
         }))
     }
 
+    if let Some(ibc_forward) = ibc_forward {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::IbcTransferPending {
+                asset_info: target_asset_info.clone(),
+                ibc_forward,
+                refund_to: sender.to_string(),
+            })?,
+        }))
+    }
+
+    if let Some(post_swap_hook) = post_swap_hook {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::ExecutePostSwapHook {
+                asset_info: target_asset_info,
+                post_swap_hook,
+            })?,
+        }))
+    }
+
     Ok(Response::new().add_messages(messages))
 }
 
+/// Swaps the offer amount required to return ask_amount,
+/// found by reverse simulation, and refunds the rest of the offer
+#[allow(clippy::too_many_arguments)]
+pub fn execute_swap_operations_exact_out(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    operations: Vec<SwapOperation>,
+    ask_amount: Uint128,
+    max_offer: Uint128,
+    to: Option<Addr>,
+    deadline: Option<u64>,
+) -> StdResult<Response<TerraMsg>> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+    let operations_len = operations.len();
+    if operations_len == 0 {
+        return Err(StdError::generic_err("must provide operations"));
+    }
+
+    if operations[0].get_offer_asset_info() != offer_asset.info {
+        return Err(StdError::generic_err(
+            "operations must start with the offer asset",
+        ));
+    }
+
+    // Assert the operations are properly set
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_operations(&deps.querier, deps.api, &config, &operations)?;
+
+    let offer_amount =
+        reverse_simulate_swap_operations(deps.as_ref(), ask_amount, operations.clone())?.amount;
+    if offer_amount > max_offer {
+        return Err(StdError::generic_err(format!(
+            "assertion failed; maximum offer amount: {}, required offer amount: {}",
+            max_offer, offer_amount
+        )));
+    }
+
+    let refund_amount = offer_asset.amount.checked_sub(offer_amount).map_err(|_| {
+        StdError::generic_err(format!(
+            "insufficient offer amount; sent: {}, required: {}",
+            offer_asset.amount, offer_amount
+        ))
+    })?;
+
+    let mut messages: Vec<CosmosMsg<TerraMsg>> = vec![];
+    if !refund_amount.is_zero() {
+        messages.push(
+            Asset {
+                info: offer_asset.info,
+                amount: refund_amount,
+            }
+            .into_msg(&deps.querier, sender.clone())?,
+        );
+    }
+
+    let to = if let Some(to) = to { to } else { sender };
+    let target_asset_info = operations[operations_len - 1].get_target_asset_info();
+
+    // the first operation swaps the required offer amount
+    messages.extend(route_msgs(
+        &env,
+        operations,
+        Some(offer_amount),
+        Some(to.to_string()),
+    )?);
+
+    // Assert the receiver got the ask amount
+    let receiver_balance = target_asset_info.query_pool(&deps.querier, deps.api, to.clone())?;
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+            asset_info: target_asset_info,
+            prev_balance: receiver_balance,
+            minimum_receive: ask_amount,
+            receiver: to.to_string(),
+        })?,
+    }));
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("refund_amount", refund_amount.to_string()))
+}
+
 fn assert_minimum_receive(
     deps: Deps<TerraQuery>,
     asset_info: AssetInfo,
@@ -228,10 +889,39 @@ fn assert_minimum_receive(
     Ok(Response::default())
 }
 
+/// Credits the swap return, or the router balances the tracked submessage
+/// returned to the transaction, or stores the sent IBC transfer
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut<TerraQuery>, env: Env, msg: Reply) -> StdResult<Response<TerraMsg>> {
+    match msg.id {
+        TRACK_BALANCES_REPLY_ID => credit_tracked_balances(deps, env),
+        SWAP_REPLY_ID => credit_swap_return(deps, msg),
+        IBC_TRANSFER_REPLY_ID => store_ibc_transfer(deps, msg),
+        _ => Err(StdError::generic_err("invalid reply msg")),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut<TerraQuery>, _env: Env, msg: SudoMsg) -> StdResult<Response<TerraMsg>> {
+    match msg {
+        SudoMsg::IbcLifecycleComplete(msg) => ibc_lifecycle_complete(deps, msg),
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps<TerraQuery>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::FindBestRoute {
+            offer_asset,
+            ask_asset_info,
+            max_hops,
+        } => to_binary(&find_best_route(
+            deps,
+            offer_asset,
+            ask_asset_info,
+            max_hops,
+        )?),
         QueryMsg::SimulateSwapOperations {
             offer_amount,
             operations,
@@ -242,6 +932,26 @@ pub fn query(deps: Deps<TerraQuery>, _env: Env, msg: QueryMsg) -> StdResult<Bina
         } => to_binary(&reverse_simulate_swap_operations(
             deps, ask_amount, operations,
         )?),
+        QueryMsg::SimulateSwapOperationsDetailed {
+            offer_amount,
+            operations,
+        } => to_binary(&simulate_swap_operations_detailed(
+            deps,
+            offer_amount,
+            operations,
+        )?),
+        QueryMsg::SimulateSplitSwap {
+            offer_amount,
+            routes,
+            optimize,
+        } => to_binary(&simulate_split_swap(deps, offer_amount, routes, optimize)?),
+        QueryMsg::ValidateRoute { operations } => {
+            to_binary(&query_validate_route(deps, operations)?)
+        }
+        QueryMsg::SimulateMany {
+            offer_amount,
+            routes,
+        } => to_binary(&simulate_many(deps, offer_amount, routes)?),
     }
 }
 
@@ -252,17 +962,50 @@ pub fn query_config(deps: Deps<TerraQuery>) -> StdResult<ConfigResponse> {
             .api
             .addr_humanize(&state.cyberswap_factory)?
             .to_string(),
+        owner: deps.api.addr_humanize(&state.owner)?.to_string(),
+        route_assets: state
+            .route_assets
+            .iter()
+            .map(|asset_info| asset_info.to_normal(deps.api))
+            .collect::<StdResult<Vec<AssetInfo>>>()?,
+        terraswap_factory: state
+            .terraswap_factory
+            .map(|factory| deps.api.addr_humanize(&factory).map(|v| v.to_string()))
+            .transpose()?,
+        astroport_factory: state
+            .astroport_factory
+            .map(|factory| deps.api.addr_humanize(&factory).map(|v| v.to_string()))
+            .transpose()?,
+        max_hops: state.max_hops,
+        blocked_intermediate_assets: state
+            .blocked_intermediate_assets
+            .iter()
+            .map(|asset_info| asset_info.to_normal(deps.api))
+            .collect::<StdResult<Vec<AssetInfo>>>()?,
+        forbid_pair_revisit: state.forbid_pair_revisit,
     };
 
     Ok(resp)
 }
 
-fn simulate_swap_operations(
+fn query_validate_route(
+    deps: Deps<TerraQuery>,
+    operations: Vec<SwapOperation>,
+) -> StdResult<ValidateRouteResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let error = validate_route(&deps.querier, deps.api, &config, &operations)?.err();
+
+    Ok(ValidateRouteResponse {
+        valid: error.is_none(),
+        error,
+    })
+}
+
+pub(crate) fn simulate_swap_operations(
     deps: Deps<TerraQuery>,
     offer_amount: Uint128,
     operations: Vec<SwapOperation>,
 ) -> StdResult<SimulateSwapOperationsResponse> {
-    let config: Config = CONFIG.load(deps.storage)?;
     let terra_querier = TerraQuerier::new(&deps.querier);
 
     let operations_len = operations.len();
@@ -300,20 +1043,13 @@ fn simulate_swap_operations(
 
                 res.receive.amount
             }
-            SwapOperation::CyberSwap {
-                offer_asset_info,
-                ask_asset_info,
-            } => {
-                let cyberswap_factory = deps.api.addr_humanize(&config.cyberswap_factory)?;
-                simulate_return_amount(
-                    deps,
-                    cyberswap_factory,
-                    offer_amount,
-                    offer_asset_info,
-                    ask_asset_info,
-                )
-                .unwrap()
-            }
+            operation => simulate_return_amount(
+                deps,
+                operation_dex(&operation)?,
+                offer_amount,
+                operation.get_offer_asset_info(),
+                operation.get_target_asset_info(),
+            )?,
         }
     }
 
@@ -327,8 +1063,6 @@ fn reverse_simulate_swap_operations(
     ask_amount: Uint128,
     operations: Vec<SwapOperation>,
 ) -> StdResult<SimulateSwapOperationsResponse> {
-    let config: Config = CONFIG.load(deps.storage)?;
-
     let operations_len = operations.len();
     if operations_len == 0 {
         return Err(StdError::generic_err("must provide operations"));
@@ -345,84 +1079,208 @@ fn reverse_simulate_swap_operations(
                     "reverse simulation of native_swap is not supported yet",
                 ))
             }
-            SwapOperation::CyberSwap {
-                offer_asset_info,
-                ask_asset_info,
-            } => {
-                let cyberswap_factory = deps.api.addr_humanize(&config.cyberswap_factory)?;
-
-                reverse_simulate_return_amount(
-                    deps,
-                    cyberswap_factory,
-                    ask_amount,
-                    offer_asset_info,
-                    ask_asset_info,
-                )
-                .unwrap()
-            }
+            operation => reverse_simulate_return_amount(
+                deps,
+                operation_dex(&operation)?,
+                ask_amount,
+                operation.get_offer_asset_info(),
+                operation.get_target_asset_info(),
+            )?,
         }
     }
 
     Ok(SimulateSwapOperationsResponse { amount: ask_amount })
 }
 
-fn simulate_return_amount(
+fn simulate_swap_operations_detailed(
+    deps: Deps<TerraQuery>,
+    offer_amount: Uint128,
+    operations: Vec<SwapOperation>,
+) -> StdResult<SimulateSwapOperationsDetailedResponse> {
+    if operations.is_empty() {
+        return Err(StdError::generic_err("must provide operations"));
+    }
+
+    let mut amount = offer_amount;
+    let mut hops: Vec<SwapHopSimulation> = vec![];
+    // shares of the return kept after the spreads and after the fees
+    let mut spread_kept = Decimal::one();
+    let mut fee_kept = Decimal::one();
+    for operation in operations.into_iter() {
+        let (hop, fee_rate) = match operation {
+            SwapOperation::NativeSwap { .. } => {
+                return Err(StdError::generic_err(
+                    "detailed simulation of native_swap is not supported yet",
+                ))
+            }
+            operation => simulate_hop(
+                deps,
+                operation_dex(&operation)?,
+                amount,
+                operation.get_offer_asset_info(),
+                operation.get_target_asset_info(),
+            )?,
+        };
+
+        spread_kept *= Decimal::one() - hop.price_impact;
+        fee_kept *= Decimal::one() - fee_rate;
+        amount = hop.return_amount;
+        hops.push(hop);
+    }
+
+    Ok(SimulateSwapOperationsDetailedResponse {
+        amount,
+        hops,
+        effective_price: ratio(amount, offer_amount),
+        price_impact: Decimal::one() - spread_kept,
+        total_fee_rate: Decimal::one() - fee_kept,
+    })
+}
+
+/// Simulates every route on its own, a failing route only fails its result
+fn simulate_many(
+    deps: Deps<TerraQuery>,
+    offer_amount: Uint128,
+    routes: Vec<Vec<SwapOperation>>,
+) -> StdResult<SimulateManyResponse> {
+    if routes.is_empty() {
+        return Err(StdError::generic_err("must provide routes"));
+    }
+
+    let mut results: Vec<RouteSimulation> = routes
+        .into_iter()
+        .enumerate()
+        .map(
+            |(index, operations)| match simulate_swap_operations(deps, offer_amount, operations) {
+                Ok(res) => RouteSimulation {
+                    index: index as u32,
+                    amount: Some(res.amount),
+                    error: None,
+                },
+                Err(err) => RouteSimulation {
+                    index: index as u32,
+                    amount: None,
+                    error: Some(err.to_string()),
+                },
+            },
+        )
+        .collect();
+
+    // highest return first, the failed routes have no amount and go last
+    results.sort_by_key(|result| Reverse(result.amount));
+
+    Ok(SimulateManyResponse { routes: results })
+}
+
+/// Returns the ratio, or zero when the denominator is zero
+fn ratio(numerator: Uint128, denominator: Uint128) -> Decimal {
+    if denominator.is_zero() {
+        Decimal::zero()
+    } else {
+        Decimal::from_ratio(numerator, denominator)
+    }
+}
+
+pub(crate) fn simulate_return_amount(
     deps: Deps<TerraQuery>,
-    factory: Addr,
-    mut offer_amount: Uint128,
+    dex: Dex,
+    offer_amount: Uint128,
     offer_asset_info: AssetInfo,
     ask_asset_info: AssetInfo,
 ) -> StdResult<Uint128> {
-    let pair_info: PairInfo = query_pair_info(
-        &deps.querier,
-        factory,
-        &[offer_asset_info.clone(), ask_asset_info.clone()],
+    let (hop, _) = simulate_hop(deps, dex, offer_amount, offer_asset_info, ask_asset_info)?;
+
+    Ok(hop.return_amount)
+}
+
+/// Simulates a pair hop, along with the share of the return
+/// taken by the commission and the taxes
+fn simulate_hop(
+    deps: Deps<TerraQuery>,
+    dex: Dex,
+    offer_amount: Uint128,
+    offer_asset_info: AssetInfo,
+    ask_asset_info: AssetInfo,
+) -> StdResult<(SwapHopSimulation, Decimal)> {
+    let pair_contract = query_dex_pair(
+        deps,
+        dex,
+        [offer_asset_info.clone(), ask_asset_info.clone()],
     )?;
 
     // Deduct tax before querying simulation
+    let mut pair_offer_amount = offer_amount;
     if let AssetInfo::NativeToken { denom } = offer_asset_info.clone() {
-        offer_amount =
+        pair_offer_amount =
             offer_amount.checked_sub(compute_tax(&deps.querier, offer_amount, denom)?)?;
     }
 
-    let mut res: SimulationResponse =
-        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: pair_info.contract_addr,
-            msg: to_binary(&PairQueryMsg::Simulation {
-                offer_asset: Asset {
-                    info: offer_asset_info,
-                    amount: offer_amount,
-                },
-            })?,
-        }))?;
+    let res: SimulationResponse = simulate_dex_swap(
+        deps,
+        dex,
+        pair_contract.clone(),
+        Asset {
+            info: offer_asset_info.clone(),
+            amount: pair_offer_amount,
+        },
+        ask_asset_info.clone(),
+    )?;
 
     // Deduct tax after querying simulation
-    if let AssetInfo::NativeToken { denom } = ask_asset_info {
-        res.return_amount =
-            res.return_amount
-                .checked_sub(compute_tax(&deps.querier, res.return_amount, denom)?)?;
+    let mut return_amount = res.return_amount;
+    if let AssetInfo::NativeToken { denom } = ask_asset_info.clone() {
+        return_amount =
+            return_amount.checked_sub(compute_tax(&deps.querier, return_amount, denom)?)?;
     }
 
-    Ok(res.return_amount)
+    // the return before the commission, and at spot price
+    let gross_return_amount = res.return_amount + res.commission_amount;
+    let spot_return_amount = gross_return_amount + res.spread_amount;
+
+    let fee_rate = Decimal::one()
+        - ratio(return_amount, gross_return_amount) * ratio(pair_offer_amount, offer_amount);
+
+    Ok((
+        SwapHopSimulation {
+            pair_addr: pair_contract.to_string(),
+            offer_asset_info,
+            ask_asset_info,
+            offer_amount,
+            return_amount,
+            commission_amount: res.commission_amount,
+            spread_amount: res.spread_amount,
+            price_impact: ratio(res.spread_amount, spot_return_amount),
+        },
+        fee_rate,
+    ))
 }
 
 fn reverse_simulate_return_amount(
     deps: Deps<TerraQuery>,
-    factory: Addr,
+    dex: Dex,
     ask_amount: Uint128,
     offer_asset_info: AssetInfo,
     ask_asset_info: AssetInfo,
 ) -> StdResult<Uint128> {
-    let pair_info: PairInfo = query_pair_info(
-        &deps.querier,
-        factory,
-        &[offer_asset_info.clone(), ask_asset_info.clone()],
+    let pair_contract = query_dex_pair(
+        deps,
+        dex,
+        [offer_asset_info.clone(), ask_asset_info.clone()],
     )?;
 
-    let mut res = reverse_simulate(
-        &deps.querier,
-        Addr::unchecked(pair_info.contract_addr),
-        &Asset {
+    // Add the tax the pair pays on its return before querying simulation
+    let mut ask_amount = ask_amount;
+    if let AssetInfo::NativeToken { denom } = ask_asset_info.clone() {
+        ask_amount =
+            ask_amount.checked_add(compute_reverse_tax(&deps.querier, ask_amount, denom)?)?;
+    }
+
+    let mut res = reverse_simulate_dex_swap(
+        deps,
+        dex,
+        pair_contract,
+        offer_asset_info.clone(),
+        Asset {
             amount: ask_amount,
             info: ask_asset_info,
         },
@@ -440,139 +1298,342 @@ fn reverse_simulate_return_amount(
     Ok(res.offer_amount)
 }
 
-fn assert_operations(operations: &[SwapOperation]) -> StdResult<()> {
-    let mut ask_asset_map: HashMap<String, bool> = HashMap::new();
-    for operation in operations.iter() {
-        let (offer_asset, ask_asset) = match operation {
-            SwapOperation::NativeSwap {
-                offer_denom,
-                ask_denom,
-            } => (
-                AssetInfo::NativeToken {
-                    denom: offer_denom.clone(),
-                },
-                AssetInfo::NativeToken {
-                    denom: ask_denom.clone(),
-                },
-            ),
-            SwapOperation::CyberSwap {
-                offer_asset_info,
-                ask_asset_info,
-            } => (offer_asset_info.clone(), ask_asset_info.clone()),
-        };
+/// Checks the operations against the route rules of the config,
+/// the outer result only fails when a query fails
+pub(crate) fn validate_route(
+    querier: &QuerierWrapper<TerraQuery>,
+    api: &dyn Api,
+    config: &Config,
+    operations: &[SwapOperation],
+) -> StdResult<Result<(), RouteError>> {
+    if operations.is_empty() {
+        return Ok(Err(RouteError::NoOperations {}));
+    }
 
-        ask_asset_map.remove(&offer_asset.to_string());
-        ask_asset_map.insert(ask_asset.to_string(), true);
+    if let Some(max_hops) = config.max_hops {
+        if operations.len() as u32 > max_hops {
+            return Ok(Err(RouteError::TooManyHops {
+                hops: operations.len() as u32,
+                max_hops,
+            }));
+        }
     }
 
-    if ask_asset_map.keys().len() != 1 {
-        return Err(StdError::generic_err(
-            "invalid operations; multiple output token",
-        ));
+    let cyberswap_factory = api.addr_humanize(&config.cyberswap_factory)?;
+    let blocked_intermediate_assets = config
+        .blocked_intermediate_assets
+        .iter()
+        .map(|asset_info| asset_info.to_normal(api))
+        .collect::<StdResult<Vec<AssetInfo>>>()?;
+
+    let mut pair_keys: Vec<(String, [String; 2])> = vec![];
+    for (index, operation) in operations.iter().enumerate() {
+        let hop = index as u32;
+        let (offer_asset, ask_asset) = (
+            operation.get_offer_asset_info(),
+            operation.get_target_asset_info(),
+        );
+
+        if index > 0 {
+            let expected_asset = operations[index - 1].get_target_asset_info();
+            if offer_asset != expected_asset {
+                return Ok(Err(RouteError::Discontinuous {
+                    hop,
+                    expected_asset,
+                    offer_asset,
+                }));
+            }
+        }
+
+        for asset_info in [&offer_asset, &ask_asset] {
+            if query_is_blocked(querier, cyberswap_factory.clone(), asset_info)? {
+                return Ok(Err(RouteError::BlockedAsset {
+                    hop,
+                    asset_info: asset_info.clone(),
+                }));
+            }
+        }
+
+        if index < operations.len() - 1 && blocked_intermediate_assets.contains(&ask_asset) {
+            return Ok(Err(RouteError::BlockedIntermediateAsset {
+                hop,
+                asset_info: ask_asset,
+            }));
+        }
+
+        if config.forbid_pair_revisit {
+            let pair_key = operation_pair_key(operation)?;
+            if let Some(first_hop) = pair_keys.iter().position(|key| *key == pair_key) {
+                return Ok(Err(RouteError::PairRevisited {
+                    hop,
+                    first_hop: first_hop as u32,
+                }));
+            }
+            pair_keys.push(pair_key);
+        }
     }
 
-    Ok(())
+    Ok(Ok(()))
+}
+
+pub(crate) fn assert_operations(
+    querier: &QuerierWrapper<TerraQuery>,
+    api: &dyn Api,
+    config: &Config,
+    operations: &[SwapOperation],
+) -> StdResult<()> {
+    validate_route(querier, api, config, operations)?
+        .map_err(|err| StdError::generic_err(format!("invalid operations; {}", err)))
+}
+
+/// Identifies the pair of the operation by its DEX and its unordered assets
+fn operation_pair_key(operation: &SwapOperation) -> StdResult<(String, [String; 2])> {
+    let dex = match operation {
+        SwapOperation::NativeSwap { .. } => "native_swap".to_string(),
+        operation => operation_dex(operation)?.to_string(),
+    };
+
+    let mut assets = [
+        operation.get_offer_asset_info().to_string(),
+        operation.get_target_asset_info().to_string(),
+    ];
+    assets.sort();
+
+    Ok((dex, assets))
 }
 
 #[test]
 fn test_invalid_operations() {
+    use classic_cyberswap::asset::AssetInfoRaw;
+    use classic_cyberswap::mock_querier::mock_dependencies;
+
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_blocked_assets(&[AssetInfo::Token {
+        contract_addr: "asset0003".to_string(),
+    }]);
+    let querier = deps.as_ref().querier;
+    let api = deps.as_ref().api;
+    let mut config = Config {
+        cyberswap_factory: api.addr_canonicalize("cyberswapfactory").unwrap(),
+        owner: api.addr_canonicalize("owner").unwrap(),
+        route_assets: vec![],
+        terraswap_factory: None,
+        astroport_factory: None,
+        max_hops: None,
+        blocked_intermediate_assets: vec![],
+        forbid_pair_revisit: false,
+    };
+
     // empty error
-    assert!(assert_operations(&[]).is_err());
+    assert!(assert_operations(&querier, api, &config, &[]).is_err());
 
     // uluna output
-    assert!(assert_operations(&vec![
-        SwapOperation::NativeSwap {
-            offer_denom: "uusd".to_string(),
-            ask_denom: "uluna".to_string(),
-        },
-        SwapOperation::CyberSwap {
-            offer_asset_info: AssetInfo::NativeToken {
-                denom: "ukrw".to_string(),
-            },
-            ask_asset_info: AssetInfo::Token {
-                contract_addr: "asset0001".to_string(),
-            },
-        },
-        SwapOperation::CyberSwap {
-            offer_asset_info: AssetInfo::Token {
-                contract_addr: "asset0001".to_string(),
+    assert!(assert_operations(
+        &querier,
+        api,
+        &config,
+        &vec![
+            SwapOperation::NativeSwap {
+                offer_denom: "uusd".to_string(),
+                ask_denom: "ukrw".to_string(),
             },
-            ask_asset_info: AssetInfo::NativeToken {
-                denom: "uluna".to_string(),
+            SwapOperation::CyberSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "ukrw".to_string(),
+                },
+                ask_asset_info: AssetInfo::Token {
+                    contract_addr: "asset0001".to_string(),
+                },
             },
-        }
-    ])
+            SwapOperation::CyberSwap {
+                offer_asset_info: AssetInfo::Token {
+                    contract_addr: "asset0001".to_string(),
+                },
+                ask_asset_info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+            }
+        ]
+    )
     .is_ok());
 
     // asset0002 output
-    assert!(assert_operations(&vec![
-        SwapOperation::NativeSwap {
-            offer_denom: "uusd".to_string(),
-            ask_denom: "uluna".to_string(),
-        },
-        SwapOperation::CyberSwap {
-            offer_asset_info: AssetInfo::NativeToken {
-                denom: "ukrw".to_string(),
+    assert!(assert_operations(
+        &querier,
+        api,
+        &config,
+        &vec![
+            SwapOperation::NativeSwap {
+                offer_denom: "uusd".to_string(),
+                ask_denom: "ukrw".to_string(),
             },
-            ask_asset_info: AssetInfo::Token {
-                contract_addr: "asset0001".to_string(),
+            SwapOperation::CyberSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "ukrw".to_string(),
+                },
+                ask_asset_info: AssetInfo::Token {
+                    contract_addr: "asset0001".to_string(),
+                },
             },
-        },
-        SwapOperation::CyberSwap {
-            offer_asset_info: AssetInfo::Token {
-                contract_addr: "asset0001".to_string(),
+            SwapOperation::CyberSwap {
+                offer_asset_info: AssetInfo::Token {
+                    contract_addr: "asset0001".to_string(),
+                },
+                ask_asset_info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
             },
-            ask_asset_info: AssetInfo::NativeToken {
-                denom: "uluna".to_string(),
+            SwapOperation::CyberSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+                ask_asset_info: AssetInfo::Token {
+                    contract_addr: "asset0002".to_string(),
+                },
             },
-        },
-        SwapOperation::CyberSwap {
-            offer_asset_info: AssetInfo::NativeToken {
-                denom: "uluna".to_string(),
+        ]
+    )
+    .is_ok());
+
+    // discontinuous operations error
+    assert!(assert_operations(
+        &querier,
+        api,
+        &config,
+        &vec![
+            SwapOperation::NativeSwap {
+                offer_denom: "uusd".to_string(),
+                ask_denom: "ukrw".to_string(),
             },
-            ask_asset_info: AssetInfo::Token {
-                contract_addr: "asset0002".to_string(),
+            SwapOperation::CyberSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "ukrw".to_string(),
+                },
+                ask_asset_info: AssetInfo::Token {
+                    contract_addr: "asset0001".to_string(),
+                },
             },
-        },
-    ])
-    .is_ok());
+            SwapOperation::CyberSwap {
+                offer_asset_info: AssetInfo::Token {
+                    contract_addr: "asset0001".to_string(),
+                },
+                ask_asset_info: AssetInfo::NativeToken {
+                    denom: "uaud".to_string(),
+                },
+            },
+            SwapOperation::CyberSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+                ask_asset_info: AssetInfo::Token {
+                    contract_addr: "asset0002".to_string(),
+                },
+            },
+        ]
+    )
+    .is_err());
+
+    // blocked intermediate asset error
+    assert_eq!(
+        assert_operations(
+            &querier,
+            api,
+            &config,
+            &[
+                SwapOperation::CyberSwap {
+                    offer_asset_info: AssetInfo::NativeToken {
+                        denom: "uluna".to_string(),
+                    },
+                    ask_asset_info: AssetInfo::Token {
+                        contract_addr: "asset0003".to_string(),
+                    },
+                },
+                SwapOperation::CyberSwap {
+                    offer_asset_info: AssetInfo::Token {
+                        contract_addr: "asset0003".to_string(),
+                    },
+                    ask_asset_info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                },
+            ]
+        ),
+        Err(StdError::generic_err(
+            "invalid operations; hop 0 goes through the blocked asset asset0003"
+        ))
+    );
 
-    // multiple output token types error
-    assert!(assert_operations(&vec![
+    let operations = [
         SwapOperation::NativeSwap {
             offer_denom: "uusd".to_string(),
-            ask_denom: "ukrw".to_string(),
+            ask_denom: "uluna".to_string(),
         },
         SwapOperation::CyberSwap {
             offer_asset_info: AssetInfo::NativeToken {
-                denom: "ukrw".to_string(),
-            },
-            ask_asset_info: AssetInfo::Token {
-                contract_addr: "asset0001".to_string(),
-            },
-        },
-        SwapOperation::CyberSwap {
-            offer_asset_info: AssetInfo::Token {
-                contract_addr: "asset0001".to_string(),
+                denom: "uluna".to_string(),
             },
             ask_asset_info: AssetInfo::NativeToken {
-                denom: "uaud".to_string(),
+                denom: "uusd".to_string(),
             },
         },
-        SwapOperation::CyberSwap {
-            offer_asset_info: AssetInfo::NativeToken {
+        SwapOperation::NativeSwap {
+            offer_denom: "uusd".to_string(),
+            ask_denom: "uluna".to_string(),
+        },
+    ];
+    assert!(assert_operations(&querier, api, &config, &operations).is_ok());
+
+    // too many hops error
+    config.max_hops = Some(2);
+    assert_eq!(
+        validate_route(&querier, api, &config, &operations).unwrap(),
+        Err(RouteError::TooManyHops {
+            hops: 3,
+            max_hops: 2,
+        })
+    );
+    config.max_hops = Some(3);
+
+    // blocked intermediate asset error
+    config.blocked_intermediate_assets = vec![AssetInfoRaw::NativeToken {
+        denom: "uluna".to_string(),
+    }];
+    assert_eq!(
+        validate_route(&querier, api, &config, &operations).unwrap(),
+        Err(RouteError::BlockedIntermediateAsset {
+            hop: 0,
+            asset_info: AssetInfo::NativeToken {
                 denom: "uluna".to_string(),
             },
-            ask_asset_info: AssetInfo::Token {
-                contract_addr: "asset0002".to_string(),
-            },
-        },
-    ])
-    .is_err());
+        })
+    );
+    config.blocked_intermediate_assets = vec![];
+
+    // revisited pair error, the pairs of native_swap and cyberswap differ
+    config.forbid_pair_revisit = true;
+    assert_eq!(
+        assert_operations(&querier, api, &config, &operations),
+        Err(StdError::generic_err(
+            "invalid operations; hop 2 revisits the pair of hop 0"
+        ))
+    );
+    assert!(assert_operations(&querier, api, &config, &operations[..2]).is_ok());
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    // routers instantiated before the owner was introduced
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    if config.owner.is_empty() {
+        let contract_info = deps
+            .querier
+            .query_wasm_contract_info(env.contract.address)?;
+        if let Some(admin) = contract_info.admin {
+            config.owner = deps.api.addr_canonicalize(&admin)?;
+            CONFIG.save(deps.storage, &config)?;
+        }
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::default())
 }
@@ -0,0 +1,87 @@
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, Coin, CosmosMsg, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, WasmMsg,
+};
+use serde::Deserialize;
+
+use crate::querier::compute_tax;
+use crate::state::take_pending_amount;
+
+use classic_bindings::{TerraMsg, TerraQuery};
+
+use classic_cyberswap::asset::AssetInfo;
+use classic_cyberswap::router::PostSwapHook;
+use cw20::Cw20ExecuteMsg;
+
+/// The stake messages of the staking and farming contracts,
+/// which stake the received tokens for the staker set by the router
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum StakeHookMsg {
+    Stake { staker: Option<String> },
+}
+
+/// Asserts a stake hook stakes the return for the swapper
+/// rather than for the router or another address
+pub fn assert_post_swap_hook(post_swap_hook: &PostSwapHook, sender: &Addr) -> StdResult<()> {
+    if let Ok(StakeHookMsg::Stake { staker }) = from_binary(&post_swap_hook.msg_template) {
+        if staker.as_deref() != Some(sender.as_str()) {
+            return Err(StdError::generic_err(
+                "the post swap hook must stake for the swapper",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Delivers the pending amount of the asset to the hook contract,
+/// sending the tokens with the hook message or executing it with the coins
+pub fn execute_post_swap_hook(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+    asset_info: AssetInfo,
+    post_swap_hook: PostSwapHook,
+) -> StdResult<Response<TerraMsg>> {
+    if env.contract.address != info.sender {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let amount = take_pending_amount(deps.storage, &asset_info.to_raw(deps.api)?)?;
+    if amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "no amount of {} to deliver",
+            asset_info
+        )));
+    }
+
+    let contract = deps.api.addr_validate(&post_swap_hook.contract)?;
+    let message = match asset_info {
+        AssetInfo::NativeToken { denom } => {
+            // deduct tax first
+            let amount = amount.checked_sub(compute_tax(&deps.querier, amount, denom.clone())?)?;
+
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                funds: vec![Coin { denom, amount }],
+                msg: post_swap_hook.msg_template,
+            })
+        }
+        AssetInfo::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: contract.to_string(),
+                amount,
+                msg: post_swap_hook.msg_template,
+            })?,
+        }),
+    };
+
+    Ok(Response::new().add_message(message).add_attributes(vec![
+        ("action", "post_swap_hook".to_string()),
+        ("contract", contract.to_string()),
+        ("amount", amount.to_string()),
+    ]))
+}
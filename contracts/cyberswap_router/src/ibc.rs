@@ -0,0 +1,159 @@
+use cosmwasm_std::{
+    Addr, Coin, DepsMut, Env, IbcMsg, IbcTimeout, MessageInfo, Reply, Response, StdError,
+    StdResult, SubMsg,
+};
+use protobuf::CodedInputStream;
+
+use crate::querier::compute_tax;
+use crate::state::{take_pending_amount, IbcTransfer, IBC_TRANSFERS, PENDING_IBC_TRANSFER};
+
+use classic_bindings::{TerraMsg, TerraQuery};
+
+use classic_cyberswap::asset::{Asset, AssetInfo};
+use classic_cyberswap::router::{IbcForward, IbcLifecycleComplete};
+
+pub const IBC_TRANSFER_REPLY_ID: u64 = 2;
+
+/// Transfers the pending amount of the native asset over IBC,
+/// the refund of the transfer is kept until its acknowledgement
+pub fn ibc_transfer_pending(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+    asset_info: AssetInfo,
+    ibc_forward: IbcForward,
+    refund_to: Addr,
+) -> StdResult<Response<TerraMsg>> {
+    if env.contract.address != info.sender {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let AssetInfo::NativeToken { denom } = &asset_info else {
+        return Err(StdError::generic_err(
+            "ibc forwarding requires a native output",
+        ));
+    };
+
+    let amount = take_pending_amount(deps.storage, &asset_info.to_raw(deps.api)?)?;
+    if amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "no amount of {} to transfer",
+            asset_info
+        )));
+    }
+
+    // deduct tax first
+    let amount = amount.checked_sub(compute_tax(&deps.querier, amount, denom.clone())?)?;
+    let coin = Coin {
+        denom: denom.clone(),
+        amount,
+    };
+
+    PENDING_IBC_TRANSFER.save(
+        deps.storage,
+        &IbcTransfer {
+            channel: ibc_forward.channel.clone(),
+            refund_to: deps.api.addr_canonicalize(refund_to.as_str())?,
+            amount: coin.clone(),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(
+            IbcMsg::Transfer {
+                channel_id: ibc_forward.channel.clone(),
+                to_address: ibc_forward.receiver.clone(),
+                amount: coin.clone(),
+                timeout: IbcTimeout::with_timestamp(
+                    env.block.time.plus_seconds(ibc_forward.timeout),
+                ),
+            },
+            IBC_TRANSFER_REPLY_ID,
+        ))
+        .add_attributes(vec![
+            ("action", "ibc_transfer".to_string()),
+            ("channel", ibc_forward.channel),
+            ("receiver", ibc_forward.receiver),
+            ("amount", coin.to_string()),
+        ]))
+}
+
+/// Stores the sent transfer by the sequence of the MsgTransferResponse,
+/// the swap fails without it as the refund could not be matched
+pub fn store_ibc_transfer(deps: DepsMut<TerraQuery>, msg: Reply) -> StdResult<Response<TerraMsg>> {
+    let transfer = PENDING_IBC_TRANSFER.load(deps.storage)?;
+    PENDING_IBC_TRANSFER.remove(deps.storage);
+
+    let data = msg
+        .result
+        .into_result()
+        .map_err(StdError::generic_err)?
+        .data
+        .ok_or_else(|| StdError::generic_err("missing ibc transfer sequence"))?;
+    let sequence = parse_transfer_sequence(data.as_slice())?;
+
+    IBC_TRANSFERS.save(deps.storage, (&transfer.channel, sequence), &transfer)?;
+
+    Ok(Response::new().add_attribute("sequence", sequence.to_string()))
+}
+
+/// Reads the sequence, field 1, of the MsgTransferResponse
+fn parse_transfer_sequence(data: &[u8]) -> StdResult<u64> {
+    let parse_err = |_| StdError::parse_err("MsgTransferResponse", "failed to parse data");
+
+    let mut is = CodedInputStream::from_bytes(data);
+    while !is.eof().map_err(parse_err)? {
+        let (field_number, wire_type) = is.read_tag_unpack().map_err(parse_err)?;
+        if field_number == 1 {
+            return is.read_uint64().map_err(parse_err);
+        }
+
+        is.skip_field(wire_type).map_err(parse_err)?;
+    }
+
+    Err(StdError::generic_err("missing ibc transfer sequence"))
+}
+
+/// Forgets the acknowledged transfer, the failed and timed out ones
+/// came back to the router and are refunded
+pub fn ibc_lifecycle_complete(
+    deps: DepsMut<TerraQuery>,
+    msg: IbcLifecycleComplete,
+) -> StdResult<Response<TerraMsg>> {
+    let (channel, sequence, refund) = match msg {
+        IbcLifecycleComplete::IbcAck {
+            channel,
+            sequence,
+            success,
+            ..
+        } => (channel, sequence, !success),
+        IbcLifecycleComplete::IbcTimeout { channel, sequence } => (channel, sequence, true),
+    };
+
+    let transfer = IBC_TRANSFERS
+        .may_load(deps.storage, (&channel, sequence))?
+        .ok_or_else(|| StdError::generic_err("unknown ibc transfer"))?;
+    IBC_TRANSFERS.remove(deps.storage, (&channel, sequence));
+
+    let mut response = Response::new().add_attributes(vec![
+        ("action", "ibc_lifecycle_complete".to_string()),
+        ("channel", channel),
+        ("sequence", sequence.to_string()),
+    ]);
+    if refund {
+        let refund_to = deps.api.addr_humanize(&transfer.refund_to)?;
+        response = response
+            .add_message(
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: transfer.amount.denom,
+                    },
+                    amount: transfer.amount.amount,
+                }
+                .into_msg(&deps.querier, refund_to.clone())?,
+            )
+            .add_attribute("refund_to", refund_to);
+    }
+
+    Ok(response)
+}
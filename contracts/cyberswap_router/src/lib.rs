@@ -1,8 +1,15 @@
 pub mod contract;
 pub mod state;
 
+mod adapters;
+mod batch;
+mod hook;
+mod ibc;
 mod operations;
 mod querier;
+mod routes;
+mod split;
+mod zap;
 
 #[cfg(test)]
 mod testing;
@@ -1,29 +1,35 @@
 use std::str::FromStr;
 
 use cosmwasm_std::{
-    to_binary, Addr, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError,
-    StdResult, WasmMsg,
+    to_binary, Addr, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, Event, MessageInfo, Reply,
+    Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
 };
 
+use crate::adapters::{dex_swap_msg, operation_dex, query_dex_pair};
 use crate::querier::compute_tax;
-use crate::state::{Config, CONFIG};
+use crate::state::{
+    add_pending_amount, take_pending_amount, PendingSwap, PENDING_SWAP, TRACKED_BALANCES,
+};
 
 use classic_bindings::{TerraMsg, TerraQuery};
 
-use classic_cyberswap::asset::{Asset, AssetInfo, PairInfo};
+use classic_cyberswap::asset::{Asset, AssetInfo, AssetInfoRaw};
 use classic_cyberswap::pair::ExecuteMsg as PairExecuteMsg;
-use classic_cyberswap::querier::{query_balance, query_pair_info, query_token_balance};
-use classic_cyberswap::router::SwapOperation;
+use classic_cyberswap::router::{ExecuteMsg, SwapOperation};
 use classic_cyberswap::util::assert_deadline;
 use cw20::Cw20ExecuteMsg;
 
+pub const TRACK_BALANCES_REPLY_ID: u64 = 1;
+pub const SWAP_REPLY_ID: u64 = 3;
+
 /// Execute swap operation
-/// swap all offer asset to ask asset
+/// swap the amount, or the amount pending for the transaction, of offer asset to ask asset
 pub fn execute_swap_operation(
     deps: DepsMut<TerraQuery>,
     env: Env,
     info: MessageInfo,
     operation: SwapOperation,
+    amount: Option<Uint128>,
     to: Option<String>,
     deadline: Option<u64>,
 ) -> StdResult<Response<TerraMsg>> {
@@ -33,80 +39,249 @@ pub fn execute_swap_operation(
 
     assert_deadline(env.block.time.seconds(), deadline)?;
 
-    let messages: Vec<CosmosMsg<TerraMsg>> = match operation {
+    let offer_asset_info = operation.get_offer_asset_info();
+    let amount = match amount {
+        Some(amount) => amount,
+        None => take_pending_amount(deps.storage, &offer_asset_info.to_raw(deps.api)?)?,
+    };
+    if amount.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "no amount of {} to swap",
+            offer_asset_info
+        )));
+    }
+
+    let ask_asset_info = operation.get_target_asset_info();
+    let (swap_msg, pair_contract) =
+        swap_operation_msg(deps.as_ref(), operation, amount, to.clone())?;
+    if to.is_some() {
+        return Ok(Response::new().add_message(swap_msg));
+    }
+
+    // the return stays in the router, it is pending for the following operation
+    PENDING_SWAP.save(
+        deps.storage,
+        &PendingSwap {
+            ask_info: ask_asset_info.to_raw(deps.api)?,
+            pair_contract: pair_contract
+                .map(|pair_contract| deps.api.addr_canonicalize(pair_contract.as_str()))
+                .transpose()?,
+        },
+    )?;
+    Ok(Response::new().add_submessage(SubMsg::reply_on_success(swap_msg, SWAP_REPLY_ID)))
+}
+
+/// Adds the return of the swap to the pending amounts, as reported by
+/// the pair or the market module, the router balance may have grown
+/// by more than the return in the meantime
+pub fn credit_swap_return(deps: DepsMut<TerraQuery>, msg: Reply) -> StdResult<Response<TerraMsg>> {
+    let pending_swap = PENDING_SWAP.load(deps.storage)?;
+    PENDING_SWAP.remove(deps.storage);
+
+    let events = msg
+        .result
+        .into_result()
+        .map_err(StdError::generic_err)?
+        .events;
+    let return_amount = match pending_swap.pair_contract {
+        Some(pair_contract) => {
+            let pair_contract = deps.api.addr_humanize(&pair_contract)?;
+            let event = events
+                .iter()
+                .find(|event| {
+                    // wasmd names the contract _contract_address, cw-multi-test _contract_addr
+                    let contract = event_attribute(event, "_contract_address")
+                        .or_else(|| event_attribute(event, "_contract_addr"));
+                    event.ty == "wasm"
+                        && contract == Some(pair_contract.as_str())
+                        && event_attribute(event, "action") == Some("swap")
+                })
+                .ok_or_else(|| {
+                    StdError::generic_err(format!("no swap reported by {}", pair_contract))
+                })?;
+
+            // the pairs deduct the tax of the native return after reporting it
+            let return_amount = parse_event_amount(event, "return_amount")?
+                .ok_or_else(|| StdError::generic_err("no return_amount reported by the pair"))?;
+            let tax_amount = parse_event_amount(event, "tax_amount")?.unwrap_or_default();
+            return_amount.checked_sub(tax_amount)?
+        }
+        None => {
+            let swap_coin = events
+                .iter()
+                .filter(|event| event.ty == "swap")
+                .find_map(|event| event_attribute(event, "swap_coin"))
+                .ok_or_else(|| StdError::generic_err("no swap reported by the market"))?;
+            let denom = match &pending_swap.ask_info {
+                AssetInfoRaw::NativeToken { denom } => denom.as_str(),
+                AssetInfoRaw::Token { .. } => "",
+            };
+            swap_coin
+                .strip_suffix(denom)
+                .and_then(|amount| Uint128::from_str(amount).ok())
+                .ok_or_else(|| {
+                    StdError::generic_err(format!("invalid market swap coin: {}", swap_coin))
+                })?
+        }
+    };
+    add_pending_amount(deps.storage, &pending_swap.ask_info, return_amount)?;
+
+    Ok(Response::default())
+}
+
+fn event_attribute<'a>(event: &'a Event, key: &str) -> Option<&'a str> {
+    event
+        .attributes
+        .iter()
+        .find(|attribute| attribute.key == key)
+        .map(|attribute| attribute.value.as_str())
+}
+
+fn parse_event_amount(event: &Event, key: &str) -> StdResult<Option<Uint128>> {
+    event_attribute(event, key)
+        .map(Uint128::from_str)
+        .transpose()
+}
+
+/// Sends the message as a submessage whose reply adds the increase
+/// of the router balances over `prev_balances` to the pending amounts
+pub fn track_balances(
+    deps: DepsMut<TerraQuery>,
+    msg: CosmosMsg<TerraMsg>,
+    prev_balances: Vec<Asset>,
+) -> StdResult<SubMsg<TerraMsg>> {
+    let prev_balances = prev_balances
+        .iter()
+        .map(|asset| asset.to_raw(deps.api))
+        .collect::<StdResult<Vec<_>>>()?;
+    TRACKED_BALANCES.save(deps.storage, &prev_balances)?;
+
+    Ok(SubMsg::reply_on_success(msg, TRACK_BALANCES_REPLY_ID))
+}
+
+/// Adds the increase of the tracked router balances to the pending amounts
+pub fn credit_tracked_balances(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+) -> StdResult<Response<TerraMsg>> {
+    let prev_balances = TRACKED_BALANCES.load(deps.storage)?;
+    TRACKED_BALANCES.remove(deps.storage);
+
+    for prev_balance in prev_balances {
+        let balance = prev_balance.info.to_normal(deps.api)?.query_pool(
+            &deps.querier,
+            deps.api,
+            env.contract.address.clone(),
+        )?;
+
+        add_pending_amount(
+            deps.storage,
+            &prev_balance.info,
+            balance.checked_sub(prev_balance.amount)?,
+        )?;
+    }
+
+    Ok(Response::default())
+}
+
+/// Returns the messages swapping along the operations, the first operation swaps
+/// the amount when given and the following ones swap the amount returned by
+/// the previous one, the last one sends the return to `to` or keeps it pending
+pub fn route_msgs(
+    env: &Env,
+    operations: Vec<SwapOperation>,
+    amount: Option<Uint128>,
+    to: Option<String>,
+) -> StdResult<Vec<CosmosMsg<TerraMsg>>> {
+    let operations_len = operations.len();
+    let mut messages: Vec<CosmosMsg<TerraMsg>> = vec![];
+    for (index, operation) in operations.into_iter().enumerate() {
+        let operation_to = if index == operations_len - 1 {
+            to.clone()
+        } else {
+            None
+        };
+
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                operation,
+                amount: if index == 0 { amount } else { None },
+                to: operation_to,
+                deadline: None,
+            })?,
+        }));
+    }
+
+    Ok(messages)
+}
+
+/// Swap the given amount of offer asset to ask asset,
+/// returns the message and the pair swapping, none for the market module
+pub fn swap_operation_msg(
+    deps: Deps<TerraQuery>,
+    operation: SwapOperation,
+    amount: Uint128,
+    to: Option<String>,
+) -> StdResult<(CosmosMsg<TerraMsg>, Option<Addr>)> {
+    let swap = match operation {
         SwapOperation::NativeSwap {
             offer_denom,
             ask_denom,
         } => {
-            let amount =
-                query_balance(&deps.querier, env.contract.address, offer_denom.to_string())?;
-            if let Some(to) = to {
+            let swap_msg = if let Some(to) = to {
                 // if the operation is last, and requires send
                 // deduct tax from the offer_coin
                 let amount =
                     amount.checked_sub(compute_tax(&deps.querier, amount, offer_denom.clone())?)?;
-                vec![CosmosMsg::from(TerraMsg::create_swap_send_msg(
+                CosmosMsg::from(TerraMsg::create_swap_send_msg(
                     to,
                     Coin {
                         denom: offer_denom,
                         amount,
                     },
                     ask_denom,
-                ))]
+                ))
             } else {
-                vec![CosmosMsg::from(TerraMsg::create_swap_msg(
+                CosmosMsg::from(TerraMsg::create_swap_msg(
                     Coin {
                         denom: offer_denom,
                         amount,
                     },
                     ask_denom,
-                ))]
-            }
+                ))
+            };
+            (swap_msg, None)
         }
-        SwapOperation::CyberSwap {
-            offer_asset_info,
-            ask_asset_info,
-        } => {
-            let config: Config = CONFIG.load(deps.as_ref().storage)?;
-            let cyberswap_factory = deps.api.addr_humanize(&config.cyberswap_factory)?;
-            let pair_info: PairInfo = query_pair_info(
-                &deps.querier,
-                cyberswap_factory,
-                &[offer_asset_info.clone(), ask_asset_info],
+        operation => {
+            let dex = operation_dex(&operation)?;
+            let offer_asset_info = operation.get_offer_asset_info();
+            let ask_asset_info = operation.get_target_asset_info();
+            let pair_contract = query_dex_pair(
+                deps,
+                dex,
+                [offer_asset_info.clone(), ask_asset_info.clone()],
             )?;
 
-            let amount = match offer_asset_info.clone() {
-                AssetInfo::NativeToken { denom } => {
-                    query_balance(&deps.querier, env.contract.address, denom)?
-                }
-
-                AssetInfo::Token { contract_addr } => {
-                    query_token_balance(
-                        &deps.querier,
-                        deps.api
-                            .addr_validate(contract_addr.as_str())
-                            .unwrap_or(Addr::unchecked(contract_addr)),
-                        //TODO: deps.api.addr_validate(contract_addr.as_str())?,
-                        env.contract.address,
-                    )?
-                }
-            };
             let offer_asset: Asset = Asset {
                 info: offer_asset_info,
                 amount,
             };
 
-            vec![asset_into_swap_msg(
-                deps.as_ref(),
-                Addr::unchecked(pair_info.contract_addr),
+            let swap_msg = dex_swap_msg(
+                deps,
+                dex,
+                pair_contract.clone(),
                 offer_asset,
-                None,
+                ask_asset_info,
                 to,
-            )?]
+            )?;
+            (swap_msg, Some(pair_contract))
         }
     };
 
-    Ok(Response::new().add_messages(messages))
+    Ok(swap)
 }
 
 pub fn asset_into_swap_msg(
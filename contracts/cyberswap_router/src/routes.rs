@@ -0,0 +1,152 @@
+use cosmwasm_std::{Deps, StdError, StdResult, Uint128};
+
+use crate::adapters::Dex;
+use crate::contract::simulate_return_amount;
+use crate::state::{Config, CONFIG};
+
+use classic_bindings::TerraQuery;
+
+use classic_cyberswap::asset::{Asset, AssetInfo};
+use classic_cyberswap::querier::query_is_blocked;
+use classic_cyberswap::router::{FindBestRouteResponse, SwapOperation};
+
+const DEFAULT_MAX_HOPS: u32 = 2;
+const MAX_HOPS: u32 = 4;
+
+/// Finds the route with the highest return among the direct pair
+/// and the routes through the configured route assets
+pub fn find_best_route(
+    deps: Deps<TerraQuery>,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+    max_hops: Option<u32>,
+) -> StdResult<FindBestRouteResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let cyberswap_factory = deps.api.addr_humanize(&config.cyberswap_factory)?;
+
+    // the route has to pass the hop limit of the config
+    let hops_limit = config
+        .max_hops
+        .map_or(MAX_HOPS, |max_hops| max_hops.min(MAX_HOPS));
+    let max_hops = max_hops.unwrap_or_else(|| DEFAULT_MAX_HOPS.min(hops_limit));
+    if max_hops == 0 || max_hops > hops_limit {
+        return Err(StdError::generic_err(format!(
+            "max_hops must be between 1 and {}",
+            hops_limit
+        )));
+    }
+
+    if offer_asset.info == ask_asset_info {
+        return Err(StdError::generic_err("same asset"));
+    }
+
+    let mut route_assets: Vec<AssetInfo> = vec![];
+    for route_asset in config.route_assets.iter() {
+        if config.blocked_intermediate_assets.contains(route_asset) {
+            continue;
+        }
+
+        let route_asset = route_asset.to_normal(deps.api)?;
+        if route_asset != offer_asset.info
+            && route_asset != ask_asset_info
+            && !query_is_blocked(&deps.querier, cyberswap_factory.clone(), &route_asset)?
+        {
+            route_assets.push(route_asset);
+        }
+    }
+
+    let mut finder = RouteFinder {
+        deps,
+        route_assets,
+        ask_asset_info,
+        max_hops,
+        path: vec![offer_asset.info],
+        best: None,
+    };
+    finder.explore(offer_asset.amount);
+
+    let (path, amount) = finder
+        .best
+        .ok_or_else(|| StdError::generic_err("no route found"))?;
+
+    Ok(FindBestRouteResponse {
+        operations: path
+            .windows(2)
+            .map(|assets| SwapOperation::CyberSwap {
+                offer_asset_info: assets[0].clone(),
+                ask_asset_info: assets[1].clone(),
+            })
+            .collect(),
+        amount,
+    })
+}
+
+struct RouteFinder<'a> {
+    deps: Deps<'a, TerraQuery>,
+    route_assets: Vec<AssetInfo>,
+    ask_asset_info: AssetInfo,
+    max_hops: u32,
+    /// Assets of the explored route, starting with the offer asset
+    path: Vec<AssetInfo>,
+    best: Option<(Vec<AssetInfo>, Uint128)>,
+}
+
+impl RouteFinder<'_> {
+    /// Depth first search from the last asset of the path holding the given amount
+    fn explore(&mut self, amount: Uint128) {
+        let last_asset = self.path.last().unwrap().clone();
+
+        if let Some(return_amount) = self.simulate(&last_asset, &self.ask_asset_info, amount) {
+            let hops = self.path.len();
+            // routes with less hops win the ties
+            let is_better = match &self.best {
+                Some((best_path, best_amount)) => {
+                    return_amount > *best_amount
+                        || (return_amount == *best_amount && hops + 1 < best_path.len())
+                }
+                None => true,
+            };
+
+            if is_better {
+                let mut path = self.path.clone();
+                path.push(self.ask_asset_info.clone());
+                self.best = Some((path, return_amount));
+            }
+        }
+
+        // one more route asset and the final hop
+        if self.path.len() as u32 + 1 > self.max_hops {
+            return;
+        }
+
+        for route_asset in self.route_assets.clone() {
+            if self.path.contains(&route_asset) {
+                continue;
+            }
+
+            if let Some(route_amount) = self.simulate(&last_asset, &route_asset, amount) {
+                self.path.push(route_asset);
+                self.explore(route_amount);
+                self.path.pop();
+            }
+        }
+    }
+
+    /// Returns None when the pair does not exist or returns nothing
+    fn simulate(
+        &self,
+        offer_asset_info: &AssetInfo,
+        ask_asset_info: &AssetInfo,
+        amount: Uint128,
+    ) -> Option<Uint128> {
+        simulate_return_amount(
+            self.deps,
+            Dex::CyberSwap,
+            amount,
+            offer_asset_info.clone(),
+            ask_asset_info.clone(),
+        )
+        .ok()
+        .filter(|return_amount| !return_amount.is_zero())
+    }
+}
@@ -0,0 +1,221 @@
+use cosmwasm_std::{
+    to_binary, Addr, CosmosMsg, Decimal, Deps, DepsMut, Env, Response, StdError, StdResult,
+    Uint128, WasmMsg,
+};
+
+use crate::contract::{assert_operations, simulate_swap_operations};
+use crate::operations::route_msgs;
+use crate::state::{Config, CONFIG};
+
+use classic_bindings::{TerraMsg, TerraQuery};
+
+use classic_cyberswap::asset::Asset;
+use classic_cyberswap::router::{ExecuteMsg, SimulateSplitSwapResponse, SplitRoute};
+use classic_cyberswap::util::assert_deadline;
+
+/// Number of parts the offer is divided into when optimizing a split
+const SPLIT_STEPS: u64 = 10;
+
+/// Divides the offer amount by the route fractions,
+/// the last route gets the remainder
+fn split_amount(amount: Uint128, routes: &[SplitRoute]) -> StdResult<Vec<Uint128>> {
+    if routes.is_empty() {
+        return Err(StdError::generic_err("must provide routes"));
+    }
+
+    let mut total_fraction = Decimal::zero();
+    for route in routes.iter() {
+        if route.fraction.is_zero() {
+            return Err(StdError::generic_err("route fraction must be positive"));
+        }
+        total_fraction += route.fraction;
+    }
+
+    if total_fraction != Decimal::one() {
+        return Err(StdError::generic_err("route fractions must sum to one"));
+    }
+
+    let mut remaining_amount = amount;
+    let mut amounts: Vec<Uint128> = vec![];
+    for route in routes[..routes.len() - 1].iter() {
+        let route_amount = amount * route.fraction;
+        remaining_amount = remaining_amount.checked_sub(route_amount)?;
+        amounts.push(route_amount);
+    }
+    amounts.push(remaining_amount);
+
+    Ok(amounts)
+}
+
+/// Swaps the fractions of the offer asset along their routes
+/// and asserts the total received amount
+#[allow(clippy::too_many_arguments)]
+pub fn execute_split_swap(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    routes: Vec<SplitRoute>,
+    minimum_receive: Option<Uint128>,
+    to: Option<Addr>,
+    deadline: Option<u64>,
+) -> StdResult<Response<TerraMsg>> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+
+    if offer_asset.amount.is_zero() {
+        return Err(StdError::generic_err("must provide offer amount"));
+    }
+
+    let amounts = split_amount(offer_asset.amount, &routes)?;
+    let target_asset_info = routes[0]
+        .operations
+        .last()
+        .ok_or_else(|| StdError::generic_err("must provide operations"))?
+        .get_target_asset_info();
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    for route in routes.iter() {
+        let (first_operation, last_operation) =
+            match (route.operations.first(), route.operations.last()) {
+                (Some(first_operation), Some(last_operation)) => (first_operation, last_operation),
+                _ => return Err(StdError::generic_err("must provide operations")),
+            };
+
+        if first_operation.get_offer_asset_info() != offer_asset.info {
+            return Err(StdError::generic_err(
+                "routes must start with the offer asset",
+            ));
+        }
+
+        if last_operation.get_target_asset_info() != target_asset_info {
+            return Err(StdError::generic_err("routes must end with the same asset"));
+        }
+
+        // the following operations swap the whole pending amount of their offer asset
+        if route.operations[1..]
+            .iter()
+            .any(|operation| operation.get_offer_asset_info() == offer_asset.info)
+        {
+            return Err(StdError::generic_err(
+                "routes cannot go through the offer asset",
+            ));
+        }
+
+        assert_operations(&deps.querier, deps.api, &config, &route.operations)?;
+    }
+
+    let to = if let Some(to) = to { to } else { sender };
+
+    let mut messages: Vec<CosmosMsg<TerraMsg>> = vec![];
+    for (route, amount) in routes.into_iter().zip(amounts) {
+        if amount.is_zero() {
+            continue;
+        }
+
+        // the first operation swaps the fraction of the offer asset
+        messages.extend(route_msgs(
+            &env,
+            route.operations,
+            Some(amount),
+            Some(to.to_string()),
+        )?);
+    }
+
+    // Execute minimum amount assertion
+    if let Some(minimum_receive) = minimum_receive {
+        let receiver_balance = target_asset_info.query_pool(&deps.querier, deps.api, to.clone())?;
+
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+                asset_info: target_asset_info,
+                prev_balance: receiver_balance,
+                minimum_receive,
+                receiver: to.to_string(),
+            })?,
+        }))
+    }
+
+    Ok(Response::new().add_messages(messages))
+}
+
+pub fn simulate_split_swap(
+    deps: Deps<TerraQuery>,
+    offer_amount: Uint128,
+    routes: Vec<SplitRoute>,
+    optimize: Option<bool>,
+) -> StdResult<SimulateSplitSwapResponse> {
+    let routes = if optimize.unwrap_or(false) {
+        optimize_split(deps, offer_amount, routes)?
+    } else {
+        routes
+    };
+
+    let amounts = split_amount(offer_amount, &routes)?;
+
+    let mut amount = Uint128::zero();
+    let mut route_amounts: Vec<Uint128> = vec![];
+    for (route, route_offer_amount) in routes.iter().zip(amounts) {
+        let route_amount = if route_offer_amount.is_zero() {
+            Uint128::zero()
+        } else {
+            simulate_swap_operations(deps, route_offer_amount, route.operations.clone())?.amount
+        };
+
+        amount += route_amount;
+        route_amounts.push(route_amount);
+    }
+
+    Ok(SimulateSplitSwapResponse {
+        amount,
+        routes,
+        route_amounts,
+    })
+}
+
+/// Allocates the offer to the routes part by part,
+/// each part going to the route returning the most for it
+fn optimize_split(
+    deps: Deps<TerraQuery>,
+    offer_amount: Uint128,
+    routes: Vec<SplitRoute>,
+) -> StdResult<Vec<SplitRoute>> {
+    let mut steps: Vec<u64> = vec![0; routes.len()];
+    let mut route_amounts: Vec<Uint128> = vec![Uint128::zero(); routes.len()];
+    for _ in 0..SPLIT_STEPS {
+        let mut best: Option<(usize, Uint128, Uint128)> = None;
+        for (index, route) in routes.iter().enumerate() {
+            let route_offer_amount = offer_amount.multiply_ratio(steps[index] + 1, SPLIT_STEPS);
+            let route_amount = match simulate_swap_operations(
+                deps,
+                route_offer_amount,
+                route.operations.clone(),
+            ) {
+                Ok(res) => res.amount,
+                Err(_) => continue,
+            };
+
+            let gain = route_amount.saturating_sub(route_amounts[index]);
+            match best {
+                Some((_, _, best_gain)) if best_gain >= gain => {}
+                _ => best = Some((index, route_amount, gain)),
+            }
+        }
+
+        let (index, route_amount, _) =
+            best.ok_or_else(|| StdError::generic_err("no route can be simulated"))?;
+        steps[index] += 1;
+        route_amounts[index] = route_amount;
+    }
+
+    Ok(routes
+        .into_iter()
+        .zip(steps)
+        .filter(|(_, route_steps)| *route_steps > 0)
+        .map(|(route, route_steps)| SplitRoute {
+            fraction: Decimal::from_ratio(route_steps, SPLIT_STEPS),
+            operations: route.operations,
+        })
+        .collect())
+}
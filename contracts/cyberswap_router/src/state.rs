@@ -1,12 +1,92 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::CanonicalAddr;
-use cw_storage_plus::Item;
+use classic_cyberswap::asset::{AssetInfoRaw, AssetRaw};
+use cosmwasm_std::{CanonicalAddr, Coin, StdResult, Storage, Uint128};
+use cw_storage_plus::{Item, Map};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Config {
     pub cyberswap_factory: CanonicalAddr,
+    /// Routers instantiated without an owner get their admin on migration
+    #[serde(default = "empty_addr")]
+    pub owner: CanonicalAddr,
+    /// Intermediary assets explored by the route discovery
+    #[serde(default)]
+    pub route_assets: Vec<AssetInfoRaw>,
+    /// Factories of the external DEXes the operations can go through
+    #[serde(default)]
+    pub terraswap_factory: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub astroport_factory: Option<CanonicalAddr>,
+    /// Rules the routes are validated by, the hops are not limited without max_hops
+    #[serde(default)]
+    pub max_hops: Option<u32>,
+    #[serde(default)]
+    pub blocked_intermediate_assets: Vec<AssetInfoRaw>,
+    #[serde(default)]
+    pub forbid_pair_revisit: bool,
+}
+
+fn empty_addr() -> CanonicalAddr {
+    CanonicalAddr::from(vec![])
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Amounts the router holds on behalf of the running transaction, by asset,
+/// the swaps never touch the rest of the router balances
+pub const PENDING_AMOUNTS: Map<&[u8], Uint128> = Map::new("pending_amounts");
+
+/// Router balances before the running tracked submessage,
+/// their increase is added to the pending amounts on reply
+pub const TRACKED_BALANCES: Item<Vec<AssetRaw>> = Item::new("tracked_balances");
+
+/// Swap of the running submessage, its return is added to the pending amounts on reply
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingSwap {
+    pub ask_info: AssetInfoRaw,
+    /// Pair reporting the return, the market module reports the native swaps
+    pub pair_contract: Option<CanonicalAddr>,
+}
+
+pub const PENDING_SWAP: Item<PendingSwap> = Item::new("pending_swap");
+
+/// IBC transfer sent by the router, refunded to refund_to if it fails
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct IbcTransfer {
+    pub channel: String,
+    pub refund_to: CanonicalAddr,
+    pub amount: Coin,
+}
+
+/// Transfer of the running submessage, stored by its sequence on reply
+pub const PENDING_IBC_TRANSFER: Item<IbcTransfer> = Item::new("pending_ibc_transfer");
+
+/// Transfers waiting for their acknowledgement, by channel and sequence
+pub const IBC_TRANSFERS: Map<(&str, u64), IbcTransfer> = Map::new("ibc_transfers");
+
+pub fn add_pending_amount(
+    storage: &mut dyn Storage,
+    asset_info: &AssetInfoRaw,
+    amount: Uint128,
+) -> StdResult<()> {
+    PENDING_AMOUNTS.update(storage, asset_info.as_bytes(), |pending| -> StdResult<_> {
+        Ok(pending.unwrap_or_default().checked_add(amount)?)
+    })?;
+
+    Ok(())
+}
+
+/// Removes and returns the pending amount of the asset
+pub fn take_pending_amount(
+    storage: &mut dyn Storage,
+    asset_info: &AssetInfoRaw,
+) -> StdResult<Uint128> {
+    let amount = PENDING_AMOUNTS
+        .may_load(storage, asset_info.as_bytes())?
+        .unwrap_or_default();
+    PENDING_AMOUNTS.remove(storage, asset_info.as_bytes());
+
+    Ok(amount)
+}
@@ -0,0 +1,328 @@
+use std::cell::Cell;
+
+use anyhow::{bail, Result as AnyResult};
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{
+    to_binary, Addr, Api, BankMsg, Binary, BlockInfo, Coin, CustomQuery, Decimal, Deps, DepsMut,
+    Empty, Env, IbcMsg, IbcQuery, MessageInfo, Querier, Response, StdError, StdResult, Storage,
+    Uint128,
+};
+use cw_multi_test::{
+    App, AppResponse, BankKeeper, BasicAppBuilder, ContractWrapper, CosmosRouter,
+    DistributionKeeper, Executor, Ibc, Module, StakeKeeper, WasmKeeper,
+};
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+
+use crate::contract::{execute, instantiate, query, reply, sudo};
+
+use classic_bindings::{TaxCapResponse, TaxRateResponse, TerraMsg, TerraQuery};
+
+use classic_cyberswap::asset::{Asset, AssetInfo, PairInfo};
+use classic_cyberswap::factory::{IsBlockedResponse, QueryMsg as FactoryQueryMsg};
+use classic_cyberswap::pair::ExecuteMsg as PairExecuteMsg;
+use classic_cyberswap::router::InstantiateMsg;
+
+const OWNER: &str = "owner";
+pub const TRANSFER_ESCROW: &str = "transfer";
+
+/// Answers the tax queries with no tax
+pub struct TerraModule;
+
+impl Module for TerraModule {
+    type ExecT = TerraMsg;
+    type QueryT = TerraQuery;
+    type SudoT = Empty;
+
+    fn execute<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        _sender: Addr,
+        msg: TerraMsg,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: std::fmt::Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        bail!("unsupported terra msg {:?}", msg)
+    }
+
+    fn sudo<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        _msg: Empty,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: std::fmt::Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        bail!("unsupported terra sudo")
+    }
+
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        request: TerraQuery,
+    ) -> AnyResult<Binary> {
+        match request {
+            TerraQuery::TaxRate {} => Ok(to_binary(&TaxRateResponse {
+                rate: Decimal::zero(),
+            })?),
+            TerraQuery::TaxCap { .. } => Ok(to_binary(&TaxCapResponse {
+                cap: Uint128::zero(),
+            })?),
+            request => bail!("unsupported terra query {:?}", request),
+        }
+    }
+}
+
+/// Escrows the transferred coins and returns the MsgTransferResponse
+/// with the sequence of the transfer
+#[derive(Default)]
+pub struct TransferModule {
+    sequence: Cell<u64>,
+}
+
+impl Module for TransferModule {
+    type ExecT = IbcMsg;
+    type QueryT = IbcQuery;
+    type SudoT = Empty;
+
+    fn execute<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: IbcMsg,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: std::fmt::Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let IbcMsg::Transfer { amount, .. } = msg else {
+            bail!("unsupported ibc msg {:?}", msg);
+        };
+
+        router.execute(
+            api,
+            storage,
+            block,
+            sender,
+            BankMsg::Send {
+                to_address: TRANSFER_ESCROW.to_string(),
+                amount: vec![amount],
+            }
+            .into(),
+        )?;
+
+        let sequence = self.sequence.get() + 1;
+        self.sequence.set(sequence);
+        Ok(AppResponse {
+            events: vec![],
+            data: Some(Binary::from(vec![0x08, sequence as u8])),
+        })
+    }
+
+    fn sudo<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        _msg: Empty,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: std::fmt::Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        bail!("unsupported ibc sudo")
+    }
+
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        request: IbcQuery,
+    ) -> AnyResult<Binary> {
+        bail!("unsupported ibc query {:?}", request)
+    }
+}
+
+impl Ibc for TransferModule {}
+
+pub type TerraApp = App<
+    BankKeeper,
+    MockApi,
+    MockStorage,
+    TerraModule,
+    WasmKeeper<TerraMsg, TerraQuery>,
+    StakeKeeper,
+    DistributionKeeper,
+    TransferModule,
+>;
+
+const FACTORY_PAIR: Item<Addr> = Item::new("pair");
+
+fn factory_instantiate(
+    deps: DepsMut<TerraQuery>,
+    _env: Env,
+    _info: MessageInfo,
+    pair: Addr,
+) -> StdResult<Response<TerraMsg>> {
+    FACTORY_PAIR.save(deps.storage, &pair)?;
+    Ok(Response::default())
+}
+
+fn factory_execute(
+    _deps: DepsMut<TerraQuery>,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response<TerraMsg>> {
+    Err(StdError::generic_err("unsupported"))
+}
+
+fn factory_query(deps: Deps<TerraQuery>, _env: Env, msg: FactoryQueryMsg) -> StdResult<Binary> {
+    match msg {
+        FactoryQueryMsg::Pair { asset_infos } => to_binary(&PairInfo {
+            asset_infos,
+            contract_addr: FACTORY_PAIR.load(deps.storage)?.to_string(),
+            liquidity_token: "liquidity".to_string(),
+            asset_decimals: [6u8, 6u8],
+        }),
+        FactoryQueryMsg::IsBlocked { .. } => to_binary(&IsBlockedResponse { blocked: false }),
+        _ => Err(StdError::generic_err("unsupported")),
+    }
+}
+
+const PAIR_ASK_ASSET: Item<AssetInfo> = Item::new("ask_asset");
+
+fn pair_instantiate(
+    deps: DepsMut<TerraQuery>,
+    _env: Env,
+    _info: MessageInfo,
+    ask_asset_info: AssetInfo,
+) -> StdResult<Response<TerraMsg>> {
+    PAIR_ASK_ASSET.save(deps.storage, &ask_asset_info)?;
+    Ok(Response::default())
+}
+
+/// Swaps the sent coins 1:1 into the ask asset
+fn pair_execute(
+    deps: DepsMut<TerraQuery>,
+    _env: Env,
+    info: MessageInfo,
+    msg: PairExecuteMsg,
+) -> StdResult<Response<TerraMsg>> {
+    let PairExecuteMsg::Swap {
+        offer_asset, to, ..
+    } = msg
+    else {
+        return Err(StdError::generic_err("unsupported"));
+    };
+
+    let return_asset = Asset {
+        info: PAIR_ASK_ASSET.load(deps.storage)?,
+        amount: offer_asset.amount,
+    };
+    let receiver = match to {
+        Some(to) => Addr::unchecked(to),
+        None => info.sender,
+    };
+    Ok(Response::new()
+        .add_message(return_asset.into_msg(&deps.querier, receiver)?)
+        .add_attributes(vec![
+            ("action", "swap".to_string()),
+            ("return_amount", offer_asset.amount.to_string()),
+            ("tax_amount", "0".to_string()),
+        ]))
+}
+
+fn pair_query(_deps: Deps<TerraQuery>, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    Err(StdError::generic_err("unsupported"))
+}
+
+/// Builds the app with the Terra and IBC transfer modules and the initial balances
+pub fn terra_app(balances: &[(&str, Vec<Coin>)]) -> TerraApp {
+    BasicAppBuilder::<TerraMsg, TerraQuery>::new_custom()
+        .with_custom(TerraModule)
+        .with_ibc(TransferModule::default())
+        .build(|router, _, storage| {
+            for (address, coins) in balances {
+                router
+                    .bank
+                    .init_balance(storage, &Addr::unchecked(*address), coins.clone())
+                    .unwrap();
+            }
+        })
+}
+
+/// Instantiates the router over a factory with a single pair swapping
+/// into the ask asset, returns the router and the pair
+pub fn setup_router(app: &mut TerraApp, ask_asset_info: AssetInfo) -> (Addr, Addr) {
+    let pair_code = app.store_code(Box::new(ContractWrapper::new(
+        pair_execute,
+        pair_instantiate,
+        pair_query,
+    )));
+    let pair = app
+        .instantiate_contract(
+            pair_code,
+            Addr::unchecked(OWNER),
+            &ask_asset_info,
+            &[],
+            "pair",
+            None,
+        )
+        .unwrap();
+
+    let factory_code = app.store_code(Box::new(ContractWrapper::new(
+        factory_execute,
+        factory_instantiate,
+        factory_query,
+    )));
+    let factory = app
+        .instantiate_contract(
+            factory_code,
+            Addr::unchecked(OWNER),
+            &pair,
+            &[],
+            "factory",
+            None,
+        )
+        .unwrap();
+
+    let router_code = app.store_code(Box::new(
+        ContractWrapper::new(execute, instantiate, query)
+            .with_reply(reply)
+            .with_sudo(sudo),
+    ));
+    let router = app
+        .instantiate_contract(
+            router_code,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                cyberswap_factory: factory.to_string(),
+            },
+            &[],
+            "router",
+            None,
+        )
+        .unwrap();
+
+    (router, pair)
+}
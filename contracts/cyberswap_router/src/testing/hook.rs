@@ -0,0 +1,154 @@
+use cosmwasm_std::{coins, to_binary, Addr, Uint128};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse};
+use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
+use cw_multi_test::{ContractWrapper, Executor};
+
+use crate::testing::app::{setup_router, terra_app, TerraApp};
+
+use classic_cyberswap::asset::AssetInfo;
+use classic_cyberswap::router::{ExecuteMsg, PostSwapHook, SwapOperation};
+use classic_cyberswap::staking::{
+    InstantiateMsg as StakingInstantiateMsg, PenaltyDestination, QueryMsg as StakingQueryMsg,
+    ReceiveMsg as StakingReceiveMsg, StakingResponse,
+};
+
+const USER: &str = "user";
+const MINTER: &str = "minter";
+
+fn token_balance(app: &TerraApp, token: &Addr, address: &Addr) -> Uint128 {
+    let res: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            token,
+            &Cw20QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    res.balance
+}
+
+#[test]
+fn swap_and_stake_for_the_swapper() {
+    let mut app = terra_app(&[(USER, coins(1000, "uluna"))]);
+
+    let token_code = app.store_code(Box::new(ContractWrapper::new_with_empty(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    )));
+    let token = app
+        .instantiate_contract(
+            token_code,
+            Addr::unchecked(MINTER),
+            &Cw20InstantiateMsg {
+                name: "Cyber Token".to_string(),
+                symbol: "CYBER".to_string(),
+                decimals: 6,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: MINTER.to_string(),
+                    cap: None,
+                }),
+                marketing: None,
+            },
+            &[],
+            "cyber",
+            None,
+        )
+        .unwrap();
+    let cyber = AssetInfo::Token {
+        contract_addr: token.to_string(),
+    };
+
+    let (router, pair) = setup_router(&mut app, cyber.clone());
+    app.execute_contract(
+        Addr::unchecked(MINTER),
+        token.clone(),
+        &Cw20ExecuteMsg::Mint {
+            recipient: pair.to_string(),
+            amount: Uint128::from(1000u128),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let staking_code = app.store_code(Box::new(ContractWrapper::new(
+        cyberswap_staking::contract::execute,
+        cyberswap_staking::contract::instantiate,
+        cyberswap_staking::contract::query,
+    )));
+    let staking = app
+        .instantiate_contract(
+            staking_code,
+            Addr::unchecked(MINTER),
+            &StakingInstantiateMsg {
+                staking_asset: cyber.clone(),
+                reward_asset: cyber.clone(),
+                router_address: router.clone(),
+                min_lock_week: 1,
+                max_lock_week: 52,
+                min_ratio: 1,
+                max_ratio: 10,
+                referral_rate: 0,
+                referral_lock_days: 0,
+                enabled: true,
+                early_unstake_penalty_rate: 0,
+                penalty_destination: PenaltyDestination::Redistribute {},
+            },
+            &[],
+            "staking",
+            None,
+        )
+        .unwrap();
+
+    // the swapper holds no CYBER, the router stakes the return for them
+    let user = Addr::unchecked(USER);
+    assert_eq!(token_balance(&app, &token, &user), Uint128::zero());
+    app.execute_contract(
+        user.clone(),
+        router.clone(),
+        &ExecuteMsg::ExecuteSwapOperations {
+            operations: vec![SwapOperation::CyberSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+                ask_asset_info: cyber,
+            }],
+            minimum_receive: Some(Uint128::from(1000u128)),
+            to: None,
+            deadline: None,
+            ibc_forward: None,
+            post_swap_hook: Some(PostSwapHook {
+                contract: staking.to_string(),
+                msg_template: to_binary(&StakingReceiveMsg::Stake {
+                    lock_week: 4,
+                    referral_addr: Addr::unchecked("referrer"),
+                    staker: Some(USER.to_string()),
+                })
+                .unwrap(),
+            }),
+        },
+        &coins(1000, "uluna"),
+    )
+    .unwrap();
+
+    let res: StakingResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &staking,
+            &StakingQueryMsg::StakerInfo {
+                address: user.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.info.stake_amount, Uint128::from(1000u128));
+    assert_eq!(res.info.position_count, 1);
+
+    assert_eq!(
+        token_balance(&app, &token, &staking),
+        Uint128::from(1000u128)
+    );
+    assert_eq!(token_balance(&app, &token, &router), Uint128::zero());
+    assert_eq!(token_balance(&app, &token, &user), Uint128::zero());
+}
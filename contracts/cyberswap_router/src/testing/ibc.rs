@@ -0,0 +1,365 @@
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    coin, coins, to_binary, Addr, BankMsg, Binary, CosmosMsg, IbcMsg, IbcTimeout, OwnedDeps, Reply,
+    StdError, SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
+};
+use cw_multi_test::{AppResponse, Executor};
+
+use crate::contract::{execute, instantiate, reply, sudo};
+use crate::ibc::IBC_TRANSFER_REPLY_ID;
+use crate::state::{add_pending_amount, IBC_TRANSFERS};
+use crate::testing::app::{setup_router, terra_app, TerraApp, TRANSFER_ESCROW};
+use classic_cyberswap::mock_querier::{mock_dependencies, WasmMockQuerier};
+
+use classic_bindings::{TerraMsg, TerraQuery};
+use classic_cyberswap::asset::AssetInfo;
+use classic_cyberswap::router::{
+    ExecuteMsg, IbcForward, IbcLifecycleComplete, InstantiateMsg, SudoMsg, SwapOperation,
+};
+
+const USER: &str = "user";
+
+type TerraDeps = OwnedDeps<MockStorage, MockApi, WasmMockQuerier, TerraQuery>;
+
+fn setup() -> (TerraApp, Addr) {
+    let mut app = terra_app(&[(USER, coins(10000, "uluna"))]);
+    let (router, pair) = setup_router(&mut app, uusd());
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &pair, coins(10000, "uusd"))
+            .unwrap();
+    });
+
+    (app, router)
+}
+
+fn swap_and_forward(app: &mut TerraApp, router: &Addr) -> AppResponse {
+    app.execute_contract(
+        Addr::unchecked(USER),
+        router.clone(),
+        &ExecuteMsg::ExecuteSwapOperations {
+            operations: vec![SwapOperation::CyberSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+                ask_asset_info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+            }],
+            minimum_receive: Some(Uint128::from(1000u128)),
+            to: None,
+            deadline: None,
+            ibc_forward: Some(IbcForward {
+                channel: "channel-0".to_string(),
+                receiver: "remote".to_string(),
+                timeout: 600,
+            }),
+            post_swap_hook: None,
+        },
+        &coins(1000, "uluna"),
+    )
+    .unwrap()
+}
+
+fn balance(app: &TerraApp, address: &str) -> u128 {
+    app.wrap()
+        .query_balance(address, "uusd")
+        .unwrap()
+        .amount
+        .u128()
+}
+
+#[test]
+fn ibc_forward() {
+    let (mut app, router) = setup();
+
+    // the return is escrowed by the transfer
+    let res = swap_and_forward(&mut app, &router);
+    assert!(res.events.iter().any(|event| event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "sequence" && attr.value == "1")));
+    assert_eq!(balance(&app, TRANSFER_ESCROW), 1000);
+    assert_eq!(balance(&app, router.as_str()), 0);
+    assert_eq!(balance(&app, USER), 0);
+
+    // the timed out transfer comes back to the router and is refunded
+    app.send_tokens(
+        Addr::unchecked(TRANSFER_ESCROW),
+        router.clone(),
+        &coins(1000, "uusd"),
+    )
+    .unwrap();
+    app.wasm_sudo(
+        router.clone(),
+        &SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout {
+            channel: "channel-0".to_string(),
+            sequence: 1,
+        }),
+    )
+    .unwrap();
+    assert_eq!(balance(&app, router.as_str()), 0);
+    assert_eq!(balance(&app, USER), 1000);
+
+    // the acknowledged transfer is forgotten without refund
+    swap_and_forward(&mut app, &router);
+    assert_eq!(balance(&app, TRANSFER_ESCROW), 1000);
+
+    let ack = SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcAck {
+        channel: "channel-0".to_string(),
+        sequence: 2,
+        ack: "AQ==".to_string(),
+        success: true,
+    });
+    app.wasm_sudo(router.clone(), &ack).unwrap();
+    assert_eq!(balance(&app, USER), 1000);
+
+    // the failed transfer comes back to the router and is refunded
+    swap_and_forward(&mut app, &router);
+    app.send_tokens(
+        Addr::unchecked(TRANSFER_ESCROW),
+        router.clone(),
+        &coins(1000, "uusd"),
+    )
+    .unwrap();
+    app.wasm_sudo(
+        router.clone(),
+        &SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcAck {
+            channel: "channel-0".to_string(),
+            sequence: 3,
+            ack: "error".to_string(),
+            success: false,
+        }),
+    )
+    .unwrap();
+    assert_eq!(balance(&app, router.as_str()), 0);
+    assert_eq!(balance(&app, USER), 2000);
+
+    let err = app.wasm_sudo(router, &ack).unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Generic error: unknown ibc transfer"
+    );
+}
+
+fn uusd() -> AssetInfo {
+    AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    }
+}
+
+fn setup_deps() -> TerraDeps {
+    let mut deps = mock_dependencies(&[coin(1000, "uusd")]);
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        InstantiateMsg {
+            cyberswap_factory: "cyberswapfactory".to_string(),
+        },
+    )
+    .unwrap();
+    deps
+}
+
+fn transfer_pending(deps: &mut TerraDeps) -> Vec<SubMsg<TerraMsg>> {
+    let asset_info = uusd().to_raw(deps.as_ref().api).unwrap();
+    add_pending_amount(deps.as_mut().storage, &asset_info, Uint128::from(1000u128)).unwrap();
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::IbcTransferPending {
+            asset_info: uusd(),
+            ibc_forward: IbcForward {
+                channel: "channel-0".to_string(),
+                receiver: "remote".to_string(),
+                timeout: 600,
+            },
+            refund_to: USER.to_string(),
+        },
+    )
+    .unwrap()
+    .messages
+}
+
+fn transfer_reply(deps: &mut TerraDeps, data: Option<Binary>) {
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: IBC_TRANSFER_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data,
+            }),
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn swap_operations_forward_over_ibc() {
+    let mut deps = setup_deps();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER, &[coin(1000, "uluna")]),
+        ExecuteMsg::ExecuteSwapOperations {
+            operations: vec![SwapOperation::CyberSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+                ask_asset_info: uusd(),
+            }],
+            minimum_receive: None,
+            to: None,
+            deadline: None,
+            ibc_forward: Some(IbcForward {
+                channel: "channel-0".to_string(),
+                receiver: "remote".to_string(),
+                timeout: 600,
+            }),
+            post_swap_hook: None,
+        },
+    )
+    .unwrap();
+
+    // the return is kept by the router and transferred with the refund to the swapper
+    assert_eq!(
+        res.messages.last().unwrap(),
+        &SubMsg::new(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::IbcTransferPending {
+                asset_info: uusd(),
+                ibc_forward: IbcForward {
+                    channel: "channel-0".to_string(),
+                    receiver: "remote".to_string(),
+                    timeout: 600,
+                },
+                refund_to: USER.to_string(),
+            })
+            .unwrap(),
+        })
+    );
+}
+
+#[test]
+fn ibc_transfer_pending() {
+    let mut deps = setup_deps();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER, &[]),
+        ExecuteMsg::IbcTransferPending {
+            asset_info: uusd(),
+            ibc_forward: IbcForward {
+                channel: "channel-0".to_string(),
+                receiver: "remote".to_string(),
+                timeout: 600,
+            },
+            refund_to: USER.to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("unauthorized"));
+
+    let messages = transfer_pending(&mut deps);
+    assert_eq!(
+        messages,
+        vec![SubMsg::reply_on_success(
+            IbcMsg::Transfer {
+                channel_id: "channel-0".to_string(),
+                to_address: "remote".to_string(),
+                amount: coin(1000, "uusd"),
+                timeout: IbcTimeout::with_timestamp(mock_env().block.time.plus_seconds(600)),
+            },
+            IBC_TRANSFER_REPLY_ID
+        )]
+    );
+}
+
+#[test]
+fn ibc_lifecycle_refunds() {
+    let mut deps = setup_deps();
+
+    // the timed out transfer came back to the router and is refunded
+    transfer_pending(&mut deps);
+    transfer_reply(&mut deps, Some(Binary::from(vec![0x08, 1])));
+    let res = sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout {
+            channel: "channel-0".to_string(),
+            sequence: 1,
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: USER.to_string(),
+            amount: vec![coin(1000, "uusd")],
+        }))]
+    );
+
+    // the acknowledged transfer is forgotten without refund
+    transfer_pending(&mut deps);
+    transfer_reply(&mut deps, Some(Binary::from(vec![0x08, 2])));
+    let ack = SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcAck {
+        channel: "channel-0".to_string(),
+        sequence: 2,
+        ack: "AQ==".to_string(),
+        success: true,
+    });
+    let res = sudo(deps.as_mut(), mock_env(), ack.clone()).unwrap();
+    assert!(res.messages.is_empty());
+    assert!(!IBC_TRANSFERS.has(deps.as_ref().storage, ("channel-0", 2)));
+
+    let err = sudo(deps.as_mut(), mock_env(), ack).unwrap_err();
+    assert_eq!(err, StdError::generic_err("unknown ibc transfer"));
+}
+
+#[test]
+fn ibc_transfer_without_sequence() {
+    let mut deps = setup_deps();
+
+    // the refund could not be matched without the sequence
+    transfer_pending(&mut deps);
+    let err = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: IBC_TRANSFER_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("missing ibc transfer sequence"));
+
+    transfer_pending(&mut deps);
+    let err = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: IBC_TRANSFER_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(Binary::from(vec![0xff])),
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::parse_err("MsgTransferResponse", "failed to parse data")
+    );
+}
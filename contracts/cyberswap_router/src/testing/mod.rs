@@ -1 +1,4 @@
+mod app;
+mod hook;
+mod ibc;
 mod tests;
@@ -1,18 +1,25 @@
-use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    coin, from_binary, to_binary, Coin, CosmosMsg, Decimal, StdError, SubMsg, Uint128, WasmMsg,
+    coin, from_binary, to_binary, Addr, Binary, Coin, CosmosMsg, Decimal, Deps, Event, OwnedDeps,
+    Reply, StdError, SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
 };
 
-use crate::contract::{execute, instantiate, query};
-use classic_cyberswap::mock_querier::mock_dependencies;
+use crate::contract::{execute, instantiate, query, reply};
+use crate::operations::{SWAP_REPLY_ID, TRACK_BALANCES_REPLY_ID};
+use crate::state::{add_pending_amount, PENDING_AMOUNTS};
+use classic_cyberswap::mock_querier::{mock_dependencies, WasmMockQuerier};
 
-use classic_bindings::TerraMsg;
-use classic_cyberswap::asset::{Asset, AssetInfo, PairInfo};
-use classic_cyberswap::pair::ExecuteMsg as PairExecuteMsg;
+use classic_bindings::{TerraMsg, TerraQuery};
+use classic_cyberswap::asset::{Asset, AssetInfo, AssetInfoRaw, PairInfo};
+use classic_cyberswap::pair::{Cw20HookMsg as PairCw20HookMsg, ExecuteMsg as PairExecuteMsg};
 use classic_cyberswap::router::{
-    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
-    SimulateSwapOperationsResponse, SwapOperation,
+    BatchSwapInput, ConfigResponse, Cw20HookMsg, ExecuteMsg, FindBestRouteResponse, InstantiateMsg,
+    PostSwapHook, QueryMsg, RouteError, RouteSimulation, SimulateManyResponse,
+    SimulateSplitSwapResponse, SimulateSwapOperationsDetailedResponse,
+    SimulateSwapOperationsResponse, SplitRoute, SwapHopSimulation, SwapOperation,
+    ValidateRouteResponse,
 };
+use classic_cyberswap::staking::ReceiveMsg as StakingReceiveMsg;
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 
 #[test]
@@ -56,6 +63,8 @@ fn execute_swap_operations() {
         minimum_receive: None,
         to: None,
         deadline: None,
+        ibc_forward: None,
+        post_swap_hook: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -99,9 +108,16 @@ fn execute_swap_operations() {
         minimum_receive: Some(Uint128::from(1000000u128)),
         to: None,
         deadline: None,
+        ibc_forward: None,
+        post_swap_hook: None,
     };
 
+    // the offer asset is the one sent along with the message
     let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    assert_eq!(res, Err(StdError::generic_err("must provide offer amount")));
+
+    let info = mock_info("addr0000", &[coin(1000000, "uusd")]);
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(
         res.messages,
@@ -114,6 +130,7 @@ fn execute_swap_operations() {
                         offer_denom: "uusd".to_string(),
                         ask_denom: "ukrw".to_string(),
                     },
+                    amount: Some(Uint128::from(1000000u128)),
                     to: None,
                     deadline: None,
                 })
@@ -131,6 +148,7 @@ fn execute_swap_operations() {
                             contract_addr: "asset0001".to_string(),
                         },
                     },
+                    amount: None,
                     to: None,
                     deadline: None,
                 })
@@ -148,6 +166,7 @@ fn execute_swap_operations() {
                             denom: "uluna".to_string(),
                         },
                     },
+                    amount: None,
                     to: None,
                     deadline: None,
                 })
@@ -165,6 +184,7 @@ fn execute_swap_operations() {
                             contract_addr: "asset0002".to_string(),
                         },
                     },
+                    amount: None,
                     to: Some("addr0000".to_string()),
                     deadline: None,
                 })
@@ -191,9 +211,13 @@ fn execute_swap_operations() {
         amount: Uint128::from(1000000u128),
         msg: to_binary(&Cw20HookMsg::ExecuteSwapOperations {
             operations: vec![
-                SwapOperation::NativeSwap {
-                    offer_denom: "uusd".to_string(),
-                    ask_denom: "ukrw".to_string(),
+                SwapOperation::CyberSwap {
+                    offer_asset_info: AssetInfo::Token {
+                        contract_addr: "asset0000".to_string(),
+                    },
+                    ask_asset_info: AssetInfo::NativeToken {
+                        denom: "ukrw".to_string(),
+                    },
                 },
                 SwapOperation::CyberSwap {
                     offer_asset_info: AssetInfo::NativeToken {
@@ -223,6 +247,8 @@ fn execute_swap_operations() {
             minimum_receive: None,
             to: Some("addr0002".to_string()),
             deadline: None,
+            ibc_forward: None,
+            post_swap_hook: None,
         })
         .unwrap(),
     });
@@ -236,10 +262,15 @@ fn execute_swap_operations() {
                 contract_addr: MOCK_CONTRACT_ADDR.to_string(),
                 funds: vec![],
                 msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
-                    operation: SwapOperation::NativeSwap {
-                        offer_denom: "uusd".to_string(),
-                        ask_denom: "ukrw".to_string(),
+                    operation: SwapOperation::CyberSwap {
+                        offer_asset_info: AssetInfo::Token {
+                            contract_addr: "asset0000".to_string(),
+                        },
+                        ask_asset_info: AssetInfo::NativeToken {
+                            denom: "ukrw".to_string(),
+                        },
                     },
+                    amount: Some(Uint128::from(1000000u128)),
                     to: None,
                     deadline: None,
                 })
@@ -257,6 +288,7 @@ fn execute_swap_operations() {
                             contract_addr: "asset0001".to_string(),
                         },
                     },
+                    amount: None,
                     to: None,
                     deadline: None,
                 })
@@ -274,6 +306,7 @@ fn execute_swap_operations() {
                             denom: "uluna".to_string(),
                         },
                     },
+                    amount: None,
                     to: None,
                     deadline: None,
                 })
@@ -291,6 +324,7 @@ fn execute_swap_operations() {
                             contract_addr: "asset0002".to_string(),
                         },
                     },
+                    amount: None,
                     to: Some("addr0002".to_string()),
                     deadline: None,
                 })
@@ -349,6 +383,7 @@ fn execute_swap_operation() {
             offer_denom: "uusd".to_string(),
             ask_denom: "uluna".to_string(),
         },
+        amount: None,
         to: None,
         deadline: None,
     };
@@ -359,17 +394,65 @@ fn execute_swap_operation() {
         _ => panic!("DO NOT ENTER HERE"),
     }
 
+    // the router balance is not pending for the transaction
     let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "no amount of uusd to swap"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the pending amount is swapped and the return tracked
+    add_pending_amount(
+        deps.as_mut().storage,
+        &AssetInfoRaw::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        Uint128::from(1000u128),
+    )
+    .unwrap();
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(TerraMsg::create_swap_msg(
-            Coin {
-                denom: "uusd".to_string(),
-                amount: Uint128::from(1000000u128),
-            },
-            "uluna".to_string()
-        ))],
+        vec![SubMsg::reply_on_success(
+            TerraMsg::create_swap_msg(
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(1000u128),
+                },
+                "uluna".to_string()
+            ),
+            SWAP_REPLY_ID
+        )],
+    );
+    assert_eq!(
+        PENDING_AMOUNTS
+            .may_load(deps.as_ref().storage, b"uusd")
+            .unwrap(),
+        None
+    );
+
+    // the return reported by the market is pending, not the router balance
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: SWAP_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![Event::new("swap")
+                    .add_attribute("offer", "1000uusd")
+                    .add_attribute("swap_coin", "80uluna")
+                    .add_attribute("swap_fee", "1uluna")],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        PENDING_AMOUNTS
+            .may_load(deps.as_ref().storage, b"uluna")
+            .unwrap(),
+        Some(Uint128::from(80u128))
     );
 
     // optional to address
@@ -379,6 +462,7 @@ fn execute_swap_operation() {
             offer_denom: "uusd".to_string(),
             ask_denom: "uluna".to_string(),
         },
+        amount: Some(Uint128::from(1000000u128)),
         to: Some("addr0000".to_string()),
         deadline: None,
     };
@@ -428,6 +512,7 @@ fn execute_swap_operation() {
                 denom: "uusd".to_string(),
             },
         },
+        amount: Some(Uint128::from(1000000u128)),
         to: Some("addr0000".to_string()),
         deadline: None,
     };
@@ -459,6 +544,70 @@ fn execute_swap_operation() {
             .unwrap()
         }))]
     );
+
+    // the pair reports the return, the tax of the native return is deducted
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: SwapOperation::CyberSwap {
+            offer_asset_info: AssetInfo::Token {
+                contract_addr: "asset".to_string(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+        },
+        amount: Some(Uint128::from(1000000u128)),
+        to: None,
+        deadline: None,
+    };
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+    assert_eq!(res.messages[0].id, SWAP_REPLY_ID);
+
+    let swap_reply = |events: Vec<Event>| Reply {
+        id: SWAP_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse { events, data: None }),
+    };
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        swap_reply(vec![Event::new("wasm")
+            .add_attribute("_contract_address", "asset")
+            .add_attribute("action", "send")]),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err("no swap reported by pair0000"))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages[0].id, SWAP_REPLY_ID);
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        swap_reply(vec![
+            Event::new("wasm")
+                .add_attribute("_contract_address", "asset")
+                .add_attribute("action", "send"),
+            Event::new("wasm")
+                .add_attribute("_contract_address", "pair0000")
+                .add_attribute("action", "swap")
+                .add_attribute("return_amount", "990000")
+                .add_attribute("tax_amount", "9900"),
+        ]),
+    )
+    .unwrap();
+    assert_eq!(
+        PENDING_AMOUNTS
+            .may_load(deps.as_ref().storage, b"uusd")
+            .unwrap(),
+        Some(Uint128::from(980100u128))
+    );
 }
 
 #[test]
@@ -590,10 +739,16 @@ fn query_reverse_routes_with_from_native() {
         .to_vec(),
     )]);
 
-    deps.querier.with_token_balances(&[(
-        &"asset0001".to_string(),
-        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000000u128))],
-    )]);
+    deps.querier.with_token_balances(&[
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+        ),
+        (
+            &"asset0001".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000000u128))],
+        ),
+    ]);
 
     let msg = QueryMsg::ReverseSimulateSwapOperations {
         ask_amount: Uint128::from(target_amount),
@@ -666,6 +821,7 @@ fn query_reverse_routes_with_from_native() {
                 contract_addr: "asset0000".to_string(),
             },
         },
+        amount: Some(Uint128::from(1000000u128)),
         to: None,
         deadline: None,
     };
@@ -681,23 +837,26 @@ fn query_reverse_routes_with_from_native() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "pair0000".to_string(),
-            funds: vec![coin(target_amount, "ukrw")],
-            msg: to_binary(&PairExecuteMsg::Swap {
-                offer_asset: Asset {
-                    info: AssetInfo::NativeToken {
-                        denom: "ukrw".to_string(),
+        vec![SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "pair0000".to_string(),
+                funds: vec![coin(target_amount, "ukrw")],
+                msg: to_binary(&PairExecuteMsg::Swap {
+                    offer_asset: Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: "ukrw".to_string(),
+                        },
+                        amount: Uint128::from(target_amount),
                     },
-                    amount: Uint128::from(target_amount),
-                },
-                belief_price: None,
-                max_spread: None,
-                to: None,
-                deadline: None,
-            })
-            .unwrap(),
-        })),],
+                    belief_price: None,
+                    max_spread: None,
+                    to: None,
+                    deadline: None,
+                })
+                .unwrap(),
+            }),
+            SWAP_REPLY_ID
+        )],
     );
 }
 
@@ -789,10 +948,11 @@ fn query_reverse_routes_with_to_native() {
     let res: SimulateSwapOperationsResponse =
         from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
 
+    // the ukrw tax paid by the pair is added to the ask amount
     assert_eq!(
         res,
         SimulateSwapOperationsResponse {
-            amount: Uint128::from(target_amount),
+            amount: Uint128::from(1050000u128),
         }
     );
 
@@ -813,10 +973,12 @@ fn query_reverse_routes_with_to_native() {
             minimum_receive: None,
             to: None,
             deadline: None,
+            ibc_forward: None,
+            post_swap_hook: None,
         })
         .unwrap(),
     });
-    let info = mock_info("addr0", &[]);
+    let info = mock_info("asset0000", &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     assert_eq!(
@@ -833,6 +995,7 @@ fn query_reverse_routes_with_to_native() {
                         denom: "ukrw".to_string(),
                     },
                 },
+                amount: Some(offer_amount),
                 to: Some("addr0".to_string()),
                 deadline: None,
             })
@@ -849,6 +1012,7 @@ fn query_reverse_routes_with_to_native() {
                 denom: "ukrw".to_string(),
             },
         },
+        amount: Some(offer_amount),
         to: None,
         deadline: None,
     };
@@ -858,28 +1022,31 @@ fn query_reverse_routes_with_to_native() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "asset0000".to_string(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Send {
-                contract: "pair0000".to_string(),
-                amount: Uint128::from(target_amount),
-                msg: to_binary(&PairExecuteMsg::Swap {
-                    offer_asset: Asset {
-                        info: AssetInfo::Token {
-                            contract_addr: "asset0000".to_string(),
+        vec![SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "asset0000".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: "pair0000".to_string(),
+                    amount: offer_amount,
+                    msg: to_binary(&PairExecuteMsg::Swap {
+                        offer_asset: Asset {
+                            info: AssetInfo::Token {
+                                contract_addr: "asset0000".to_string(),
+                            },
+                            amount: offer_amount,
                         },
-                        amount: Uint128::from(target_amount),
-                    },
-                    belief_price: None,
-                    max_spread: None,
-                    to: None,
-                    deadline: None,
+                        belief_price: None,
+                        max_spread: None,
+                        to: None,
+                        deadline: None,
+                    })
+                    .unwrap(),
                 })
                 .unwrap(),
-            })
-            .unwrap(),
-        }))],
+            }),
+            SWAP_REPLY_ID
+        )],
     );
 }
 
@@ -964,3 +1131,1808 @@ fn assert_minimum_receive_token() {
         _ => panic!("DO NOT ENTER HERE"),
     }
 }
+
+#[test]
+fn sweep() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &"asset0000".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000u128))],
+    )]);
+
+    let msg = InstantiateMsg {
+        cyberswap_factory: "cyberswapfactory".to_string(),
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let sweep = |amount: Option<u128>, receiver: Option<&str>| ExecuteMsg::Sweep {
+        asset_info: AssetInfo::Token {
+            contract_addr: "asset0000".to_string(),
+        },
+        amount: amount.map(Uint128::from),
+        receiver: receiver.map(|receiver| receiver.to_string()),
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        sweep(None, None),
+    );
+    assert_eq!(res, Err(StdError::generic_err("unauthorized")));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        sweep(Some(1001), None),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "insufficient balance; balance: 1000, amount: 1001"
+        ))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        sweep(Some(400), Some("addr0002")),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "asset0000".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0002".to_string(),
+                amount: Uint128::from(400u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    // the whole balance goes to the owner by default
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        sweep(None, None),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "asset0000".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(1000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+}
+
+fn native_pair(denom: &str, contract_addr: &str, pair_addr: &str) -> (String, PairInfo) {
+    (
+        format!("{}{}", denom, contract_addr),
+        PairInfo {
+            asset_infos: [
+                AssetInfo::NativeToken {
+                    denom: denom.to_string(),
+                },
+                AssetInfo::Token {
+                    contract_addr: contract_addr.to_string(),
+                },
+            ],
+            contract_addr: pair_addr.to_string(),
+            liquidity_token: format!("liquidity{}", pair_addr),
+            asset_decimals: [6u8, 6u8],
+        },
+    )
+}
+
+#[test]
+fn find_best_route() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        cyberswap_factory: "cyberswapfactory".to_string(),
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    deps.querier.with_token_balances(&[(
+        &"asset0001".to_string(),
+        &[(&"addr0000".to_string(), &Uint128::zero())],
+    )]);
+
+    // the tax on uusd makes the route through uluna better
+    deps.querier.with_tax(
+        Decimal::percent(5),
+        &[
+            (&"uusd".to_string(), &Uint128::from(1000000u128)),
+            (&"uluna".to_string(), &Uint128::from(10u128)),
+        ],
+    );
+
+    let pairs = [
+        native_pair("uusd", "asset0000", "pair0000"),
+        native_pair("uusd", "asset0001", "pair0001"),
+        native_pair("uluna", "asset0000", "pair0002"),
+        native_pair("uluna", "asset0001", "pair0003"),
+    ];
+    deps.querier.with_cyberswap_factory(
+        &pairs
+            .iter()
+            .map(|(key, pair)| (key, pair))
+            .collect::<Vec<(&String, &PairInfo)>>(),
+        &[],
+    );
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let uluna = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+    let offer_asset = Asset {
+        info: AssetInfo::Token {
+            contract_addr: "asset0000".to_string(),
+        },
+        amount: Uint128::from(1000000u128),
+    };
+    let ask_asset_info = AssetInfo::Token {
+        contract_addr: "asset0001".to_string(),
+    };
+    let find_best_route = QueryMsg::FindBestRoute {
+        offer_asset: offer_asset.clone(),
+        ask_asset_info: ask_asset_info.clone(),
+        max_hops: None,
+    };
+
+    // no direct pair and no route assets
+    let res = query(deps.as_ref(), mock_env(), find_best_route.clone());
+    assert_eq!(res, Err(StdError::generic_err("no route found")));
+
+    let msg = ExecuteMsg::UpdateConfig {
+        route_assets: Some(vec![uusd.clone(), uluna.clone()]),
+        terraswap_factory: None,
+        astroport_factory: None,
+        max_hops: None,
+        blocked_intermediate_assets: None,
+        forbid_pair_revisit: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res, Err(StdError::generic_err("unauthorized")));
+    execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.owner, "addr0000");
+    assert_eq!(config.route_assets, vec![uusd.clone(), uluna.clone()]);
+
+    let res: FindBestRouteResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), find_best_route).unwrap()).unwrap();
+    let expected_operations = vec![
+        SwapOperation::CyberSwap {
+            offer_asset_info: offer_asset.info.clone(),
+            ask_asset_info: uluna.clone(),
+        },
+        SwapOperation::CyberSwap {
+            offer_asset_info: uluna,
+            ask_asset_info: ask_asset_info.clone(),
+        },
+    ];
+    assert_eq!(res.operations, expected_operations);
+
+    let simulation: SimulateSwapOperationsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateSwapOperations {
+                offer_amount: offer_asset.amount,
+                operations: expected_operations.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.amount, simulation.amount);
+
+    // a single hop is not enough
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::FindBestRoute {
+            offer_asset: offer_asset.clone(),
+            ask_asset_info: ask_asset_info.clone(),
+            max_hops: Some(1),
+        },
+    );
+    assert_eq!(res, Err(StdError::generic_err("no route found")));
+
+    // swap along the best route
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: offer_asset.amount,
+        msg: to_binary(&Cw20HookMsg::ExecuteBestSwap {
+            ask_asset_info,
+            max_hops: None,
+            minimum_receive: Uint128::from(999000u128),
+            to: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("asset0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[..2],
+        expected_operations
+            .into_iter()
+            .enumerate()
+            .map(
+                |(i, operation)| SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                    funds: vec![],
+                    msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                        operation,
+                        amount: if i == 0 {
+                            Some(offer_asset.amount)
+                        } else {
+                            None
+                        },
+                        to: if i == 1 {
+                            Some("addr0000".to_string())
+                        } else {
+                            None
+                        },
+                        deadline: None,
+                    })
+                    .unwrap(),
+                }))
+            )
+            .collect::<Vec<SubMsg<TerraMsg>>>()
+    );
+    assert_eq!(res.messages.len(), 3);
+}
+
+#[test]
+fn execute_best_swap_with_token_offer() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        cyberswap_factory: "cyberswapfactory".to_string(),
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::ExecuteBestSwap {
+        offer_asset: Asset {
+            info: AssetInfo::Token {
+                contract_addr: "asset0000".to_string(),
+            },
+            amount: Uint128::from(1000000u128),
+        },
+        ask_asset_info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        max_hops: None,
+        minimum_receive: Uint128::from(1u128),
+        to: None,
+        deadline: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "token offers must be sent through the cw20 receive hook"
+        ))
+    );
+}
+
+fn split_routes(fractions: [u64; 2]) -> Vec<SplitRoute> {
+    ["uusd", "uluna"]
+        .iter()
+        .zip(fractions)
+        .map(|(denom, fraction)| SplitRoute {
+            fraction: Decimal::percent(fraction),
+            operations: vec![
+                SwapOperation::CyberSwap {
+                    offer_asset_info: AssetInfo::Token {
+                        contract_addr: "asset0000".to_string(),
+                    },
+                    ask_asset_info: AssetInfo::NativeToken {
+                        denom: denom.to_string(),
+                    },
+                },
+                SwapOperation::CyberSwap {
+                    offer_asset_info: AssetInfo::NativeToken {
+                        denom: denom.to_string(),
+                    },
+                    ask_asset_info: AssetInfo::Token {
+                        contract_addr: "asset0001".to_string(),
+                    },
+                },
+            ],
+        })
+        .collect()
+}
+
+fn split_swap_dependencies() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier, TerraQuery> {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        cyberswap_factory: "cyberswapfactory".to_string(),
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    deps.querier.with_token_balances(&[(
+        &"asset0001".to_string(),
+        &[(&"addr0000".to_string(), &Uint128::zero())],
+    )]);
+
+    // the tax on uusd makes the route through uluna better
+    deps.querier.with_tax(
+        Decimal::percent(5),
+        &[
+            (&"uusd".to_string(), &Uint128::from(1000000u128)),
+            (&"uluna".to_string(), &Uint128::from(10u128)),
+        ],
+    );
+
+    let pairs = [
+        native_pair("uusd", "asset0000", "pair0000"),
+        native_pair("uusd", "asset0001", "pair0001"),
+        native_pair("uluna", "asset0000", "pair0002"),
+        native_pair("uluna", "asset0001", "pair0003"),
+    ];
+    deps.querier.with_cyberswap_factory(
+        &pairs
+            .iter()
+            .map(|(key, pair)| (key, pair))
+            .collect::<Vec<(&String, &PairInfo)>>(),
+        &[],
+    );
+
+    deps
+}
+
+#[test]
+fn execute_split_swap() {
+    let mut deps = split_swap_dependencies();
+
+    let split_swap = |routes: Vec<SplitRoute>| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(1000000u128),
+            msg: to_binary(&Cw20HookMsg::ExecuteSplitSwap {
+                routes,
+                minimum_receive: Some(Uint128::from(900000u128)),
+                to: None,
+                deadline: None,
+            })
+            .unwrap(),
+        })
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        split_swap(split_routes([70, 40])),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err("route fractions must sum to one"))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        split_swap(split_routes([100, 0])),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err("route fraction must be positive"))
+    );
+
+    let mut routes = split_routes([70, 30]);
+    routes[1].operations.pop();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        split_swap(routes),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err("routes must end with the same asset"))
+    );
+
+    // the first operations swap the fractions of the offer,
+    // the following ones swap the amount pending from the previous one
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        split_swap(split_routes([70, 30])),
+    )
+    .unwrap();
+
+    let mut expected_messages: Vec<SubMsg<TerraMsg>> = vec![];
+    for (route, amount) in split_routes([70, 30])
+        .into_iter()
+        .zip([700000u128, 300000u128])
+    {
+        for (index, operation) in route.operations.into_iter().enumerate() {
+            expected_messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation,
+                    amount: if index == 0 {
+                        Some(Uint128::from(amount))
+                    } else {
+                        None
+                    },
+                    to: if index == 1 {
+                        Some("addr0000".to_string())
+                    } else {
+                        None
+                    },
+                    deadline: None,
+                })
+                .unwrap(),
+            })));
+        }
+    }
+    expected_messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+            asset_info: AssetInfo::Token {
+                contract_addr: "asset0001".to_string(),
+            },
+            prev_balance: Uint128::zero(),
+            minimum_receive: Uint128::from(900000u128),
+            receiver: "addr0000".to_string(),
+        })
+        .unwrap(),
+    })));
+    assert_eq!(res.messages, expected_messages);
+}
+
+#[test]
+fn simulate_split_swap() {
+    let deps = split_swap_dependencies();
+
+    let res: SimulateSplitSwapResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateSplitSwap {
+                offer_amount: Uint128::from(1000000u128),
+                routes: split_routes([70, 30]),
+                optimize: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.routes, split_routes([70, 30]));
+    assert_eq!(res.route_amounts.len(), 2);
+    assert_eq!(res.amount, res.route_amounts[0] + res.route_amounts[1]);
+
+    // the whole offer goes through the route with the lower tax
+    let optimized: SimulateSplitSwapResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateSplitSwap {
+                offer_amount: Uint128::from(1000000u128),
+                routes: split_routes([70, 30]),
+                optimize: Some(true),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let expected_routes = split_routes([0, 100]).split_off(1);
+    assert_eq!(optimized.routes, expected_routes);
+    assert!(optimized.amount > res.amount);
+}
+
+#[test]
+fn simulate_many() {
+    let deps = split_swap_dependencies();
+
+    let mut routes: Vec<Vec<SwapOperation>> = vec![vec![]];
+    routes.extend(
+        split_routes([50, 50])
+            .into_iter()
+            .map(|route| route.operations),
+    );
+
+    let simulate = |operations: Vec<SwapOperation>| {
+        let res: SimulateSwapOperationsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::SimulateSwapOperations {
+                    offer_amount: Uint128::from(1000000u128),
+                    operations,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.amount
+    };
+
+    // the route through uluna returns the most, the failed route goes last
+    let res: SimulateManyResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateMany {
+                offer_amount: Uint128::from(1000000u128),
+                routes: routes.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.routes,
+        vec![
+            RouteSimulation {
+                index: 2,
+                amount: Some(simulate(routes[2].clone())),
+                error: None,
+            },
+            RouteSimulation {
+                index: 1,
+                amount: Some(simulate(routes[1].clone())),
+                error: None,
+            },
+            RouteSimulation {
+                index: 0,
+                amount: None,
+                error: Some("Generic error: must provide operations".to_string()),
+            },
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::SimulateMany {
+            offer_amount: Uint128::from(1000000u128),
+            routes: vec![],
+        },
+    );
+    assert_eq!(res, Err(StdError::generic_err("must provide routes")));
+}
+
+#[test]
+fn execute_swap_operations_exact_out() {
+    let mut deps = split_swap_dependencies();
+
+    let operations = split_routes([0, 100]).remove(1).operations;
+    let exact_out = |max_offer: u128| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(1000000u128),
+            msg: to_binary(&Cw20HookMsg::ExecuteSwapOperationsExactOut {
+                operations: operations.clone(),
+                ask_amount: Uint128::from(500000u128),
+                max_offer: Uint128::from(max_offer),
+                to: None,
+                deadline: None,
+            })
+            .unwrap(),
+        })
+    };
+
+    // the uluna tax paid by the first pair and by the router is added to the offer
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        exact_out(500000u128),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "assertion failed; maximum offer amount: 500000, required offer amount: 500020"
+        ))
+    );
+
+    // the required offer is the reverse simulation of the ask amount
+    // and the smallest offer returning the whole ask amount
+    let simulate = |deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier, TerraQuery>,
+                    msg: QueryMsg| {
+        from_binary::<SimulateSwapOperationsResponse>(
+            &query(deps.as_ref(), mock_env(), msg).unwrap(),
+        )
+        .unwrap()
+        .amount
+    };
+    assert_eq!(
+        simulate(
+            &deps,
+            QueryMsg::ReverseSimulateSwapOperations {
+                ask_amount: Uint128::from(500000u128),
+                operations: operations.clone(),
+            }
+        ),
+        Uint128::from(500020u128)
+    );
+    assert_eq!(
+        simulate(
+            &deps,
+            QueryMsg::SimulateSwapOperations {
+                offer_amount: Uint128::from(500020u128),
+                operations: operations.clone(),
+            }
+        ),
+        Uint128::from(500000u128)
+    );
+    assert_eq!(
+        simulate(
+            &deps,
+            QueryMsg::SimulateSwapOperations {
+                offer_amount: Uint128::from(500019u128),
+                operations: operations.clone(),
+            }
+        ),
+        Uint128::from(499999u128)
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        exact_out(600000u128),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "asset0000".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::from(499980u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: operations[0].clone(),
+                    amount: Some(Uint128::from(500020u128)),
+                    to: None,
+                    deadline: None,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: operations[1].clone(),
+                    amount: None,
+                    to: Some("addr0000".to_string()),
+                    deadline: None,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+                    asset_info: AssetInfo::Token {
+                        contract_addr: "asset0001".to_string(),
+                    },
+                    prev_balance: Uint128::zero(),
+                    minimum_receive: Uint128::from(500000u128),
+                    receiver: "addr0000".to_string(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    // native offers are sent along with the message
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ExecuteSwapOperationsExactOut {
+            operations,
+            ask_amount: Uint128::from(500000u128),
+            max_offer: Uint128::from(600000u128),
+            to: None,
+            deadline: None,
+        },
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "token offers must be sent through the cw20 receive hook"
+        ))
+    );
+}
+
+#[test]
+fn simulate_swap_operations_detailed() {
+    let mut deps = split_swap_dependencies();
+    deps.querier
+        .with_pair_simulation(Decimal::percent(10), Decimal::percent(10));
+
+    let operations = split_routes([0, 100]).remove(1).operations;
+    let res: SimulateSwapOperationsDetailedResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateSwapOperationsDetailed {
+                offer_amount: Uint128::from(1000000u128),
+                operations: operations.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    // the uluna tax is deducted from the return of the first hop
+    // and from the offer of the second hop
+    assert_eq!(
+        res.hops,
+        vec![
+            SwapHopSimulation {
+                pair_addr: "pair0002".to_string(),
+                offer_asset_info: operations[0].get_offer_asset_info(),
+                ask_asset_info: operations[0].get_target_asset_info(),
+                offer_amount: Uint128::from(1000000u128),
+                return_amount: Uint128::from(809990u128),
+                commission_amount: Uint128::from(90000u128),
+                spread_amount: Uint128::from(100000u128),
+                price_impact: Decimal::percent(10),
+            },
+            SwapHopSimulation {
+                pair_addr: "pair0003".to_string(),
+                offer_asset_info: operations[1].get_offer_asset_info(),
+                ask_asset_info: operations[1].get_target_asset_info(),
+                offer_amount: Uint128::from(809990u128),
+                return_amount: Uint128::from(656084u128),
+                commission_amount: Uint128::from(72898u128),
+                spread_amount: Uint128::from(80998u128),
+                price_impact: Decimal::percent(10),
+            },
+        ]
+    );
+    assert_eq!(res.amount, Uint128::from(656084u128));
+    assert_eq!(
+        res.effective_price,
+        Decimal::from_ratio(656084u128, 1000000u128)
+    );
+    assert_eq!(res.price_impact, Decimal::percent(19));
+    assert!(res.total_fee_rate > Decimal::percent(19) && res.total_fee_rate < Decimal::percent(20));
+
+    let simulation: SimulateSwapOperationsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateSwapOperations {
+                offer_amount: Uint128::from(1000000u128),
+                operations,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.amount, simulation.amount);
+}
+
+#[test]
+fn execute_swap_operation_through_external_dexes() {
+    let mut deps = split_swap_dependencies();
+    deps.querier.with_token_balances(&[
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000000u128))],
+        ),
+        (
+            &"asset0001".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+        ),
+    ]);
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![coin(1000000u128, "uluna")],
+    )]);
+
+    let asset0000 = AssetInfo::Token {
+        contract_addr: "asset0000".to_string(),
+    };
+    let asset0001 = AssetInfo::Token {
+        contract_addr: "asset0001".to_string(),
+    };
+    let uluna = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+    let terraswap_operation = SwapOperation::TerraSwap {
+        offer_asset_info: asset0000.clone(),
+        ask_asset_info: uluna.clone(),
+    };
+    let astroport_operation = SwapOperation::Astroport {
+        offer_asset_info: uluna.clone(),
+        ask_asset_info: asset0001.clone(),
+    };
+
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: terraswap_operation.clone(),
+        amount: Some(Uint128::from(1000000u128)),
+        to: Some("addr0000".to_string()),
+        deadline: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg.clone(),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err("terraswap factory is not configured"))
+    );
+
+    let update_config = ExecuteMsg::UpdateConfig {
+        route_assets: None,
+        terraswap_factory: Some("terraswapfactory".to_string()),
+        astroport_factory: Some("astroportfactory".to_string()),
+        max_hops: None,
+        blocked_intermediate_assets: None,
+        forbid_pair_revisit: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        update_config,
+    )
+    .unwrap();
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config.terraswap_factory,
+        Some("terraswapfactory".to_string())
+    );
+    assert_eq!(
+        config.astroport_factory,
+        Some("astroportfactory".to_string())
+    );
+
+    // terraswap pairs take no ask asset
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "asset0000".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: "pair0002".to_string(),
+                amount: Uint128::from(1000000u128),
+                msg: Binary::from(
+                    br#"{"swap":{"belief_price":null,"max_spread":null,"to":"addr0000"}}"#
+                        .as_slice()
+                ),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    // astroport pairs take the ask asset, the offer is taxed
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: astroport_operation.clone(),
+        amount: Some(Uint128::from(1000000u128)),
+        to: None,
+        deadline: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "pair0003".to_string(),
+                funds: vec![coin(999990u128, "uluna")],
+                msg: Binary::from(
+                    br#"{"swap":{"offer_asset":{"info":{"native_token":{"denom":"uluna"}},"amount":"999990"},"ask_asset_info":{"token":{"contract_addr":"asset0001"}},"belief_price":null,"max_spread":null,"to":null}}"#
+                        .as_slice()
+                ),
+            }),
+            SWAP_REPLY_ID
+        )]
+    );
+
+    // one route can hop across the dexes
+    let simulate = |operations: Vec<SwapOperation>| -> SimulateSwapOperationsResponse {
+        from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::SimulateSwapOperations {
+                    offer_amount: Uint128::from(1000000u128),
+                    operations,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        simulate(vec![terraswap_operation, astroport_operation]),
+        simulate(split_routes([0, 100]).remove(1).operations)
+    );
+}
+
+#[test]
+fn execute_zap_in() {
+    let mut deps = split_swap_dependencies();
+    deps.querier.with_token_balances(&[
+        (
+            &"liquiditypair0003".to_string(),
+            &[(&"addr0000".to_string(), &Uint128::zero())],
+        ),
+        (
+            &"asset0001".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(500000u128))],
+        ),
+        (
+            &"asset0000".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+        ),
+    ]);
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![coin(1000000u128, "uluna")],
+    )]);
+
+    let asset0000 = AssetInfo::Token {
+        contract_addr: "asset0000".to_string(),
+    };
+    let asset0001 = AssetInfo::Token {
+        contract_addr: "asset0001".to_string(),
+    };
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let uluna = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+    let operations_a = vec![SwapOperation::CyberSwap {
+        offer_asset_info: asset0000.clone(),
+        ask_asset_info: uluna.clone(),
+    }];
+    let zap_in = |operations_b: Vec<SwapOperation>| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(1000000u128),
+            msg: to_binary(&Cw20HookMsg::ZapIn {
+                pair: [uluna.clone(), asset0001.clone()],
+                operations_a: operations_a.clone(),
+                operations_b,
+                min_lp: Uint128::from(1u128),
+                receiver: None,
+                deadline: None,
+            })
+            .unwrap(),
+        })
+    };
+
+    // the leg would swap the other half of the pair too
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        zap_in(vec![
+            operations_a[0].clone(),
+            SwapOperation::CyberSwap {
+                offer_asset_info: uluna.clone(),
+                ask_asset_info: asset0001.clone(),
+            },
+        ]),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "zap operations cannot go through the offer asset or the pair assets"
+        ))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        zap_in(vec![]),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "zap operations must swap asset0000 into asset0001"
+        ))
+    );
+
+    let operations_b = vec![
+        SwapOperation::CyberSwap {
+            offer_asset_info: asset0000.clone(),
+            ask_asset_info: uusd.clone(),
+        },
+        SwapOperation::CyberSwap {
+            offer_asset_info: uusd,
+            ask_asset_info: asset0001.clone(),
+        },
+    ];
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("asset0000", &[]),
+        zap_in(operations_b.clone()),
+    )
+    .unwrap();
+
+    let self_call = |msg: ExecuteMsg| {
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            funds: vec![],
+            msg: to_binary(&msg).unwrap(),
+        }))
+    };
+    assert_eq!(
+        res.messages,
+        vec![
+            self_call(ExecuteMsg::ExecuteSwapOperation {
+                operation: operations_a[0].clone(),
+                amount: Some(Uint128::from(500000u128)),
+                to: None,
+                deadline: None,
+            }),
+            self_call(ExecuteMsg::ExecuteSwapOperation {
+                operation: operations_b[0].clone(),
+                amount: Some(Uint128::from(500000u128)),
+                to: None,
+                deadline: None,
+            }),
+            self_call(ExecuteMsg::ExecuteSwapOperation {
+                operation: operations_b[1].clone(),
+                amount: None,
+                to: None,
+                deadline: None,
+            }),
+            self_call(ExecuteMsg::ZapProvideLiquidity {
+                pair_contract: "pair0003".to_string(),
+                receiver: "addr0000".to_string(),
+            }),
+            self_call(ExecuteMsg::TransferPending {
+                asset_info: uluna.clone(),
+                receiver: "addr0000".to_string(),
+            }),
+            self_call(ExecuteMsg::TransferPending {
+                asset_info: asset0001.clone(),
+                receiver: "addr0000".to_string(),
+            }),
+            self_call(ExecuteMsg::AssertMinimumReceive {
+                asset_info: AssetInfo::Token {
+                    contract_addr: "liquiditypair0003".to_string(),
+                },
+                prev_balance: Uint128::zero(),
+                minimum_receive: Uint128::from(1u128),
+                receiver: "addr0000".to_string(),
+            }),
+        ]
+    );
+
+    // the internal messages can only be called by the router
+    let provide_liquidity = ExecuteMsg::ZapProvideLiquidity {
+        pair_contract: "pair0003".to_string(),
+        receiver: "addr0000".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        provide_liquidity.clone(),
+    );
+    assert_eq!(res, Err(StdError::generic_err("unauthorized")));
+
+    // the pending amounts are provided, the native one net of tax,
+    // the rest of the router balances is left alone
+    for (asset_info, amount) in [(&uluna, 900000u128), (&asset0001, 500000u128)] {
+        let asset_info = asset_info.to_raw(deps.as_ref().api).unwrap();
+        add_pending_amount(deps.as_mut().storage, &asset_info, Uint128::from(amount)).unwrap();
+    }
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        provide_liquidity,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "asset0001".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                    spender: "pair0003".to_string(),
+                    amount: Uint128::from(500000u128),
+                    expires: None,
+                })
+                .unwrap(),
+            })),
+            SubMsg::reply_on_success(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "pair0003".to_string(),
+                    funds: vec![coin(899990u128, "uluna")],
+                    msg: to_binary(&PairExecuteMsg::ProvideLiquidity {
+                        assets: [
+                            Asset {
+                                info: uluna.clone(),
+                                amount: Uint128::from(899990u128),
+                            },
+                            Asset {
+                                info: asset0001.clone(),
+                                amount: Uint128::from(500000u128),
+                            },
+                        ],
+                        receiver: Some("addr0000".to_string()),
+                        deadline: None,
+                        slippage_tolerance: None,
+                    })
+                    .unwrap(),
+                }),
+                TRACK_BALANCES_REPLY_ID
+            ),
+        ]
+    );
+
+    // the amount the pair did not take is pending again
+    deps.querier.with_token_balances(&[(
+        &"asset0001".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(20000u128))],
+    )]);
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![coin(100000u128, "uluna")],
+    )]);
+    reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: TRACK_BALANCES_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::TransferPending {
+            asset_info: asset0001,
+            receiver: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "asset0001".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(20000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    // nothing is left to transfer
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::TransferPending {
+            asset_info: asset0000,
+            receiver: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 0);
+}
+
+#[test]
+fn execute_zap_out() {
+    let mut deps = split_swap_dependencies();
+
+    let asset0001 = AssetInfo::Token {
+        contract_addr: "asset0001".to_string(),
+    };
+    let uluna = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+    let operations = vec![SwapOperation::CyberSwap {
+        offer_asset_info: asset0001,
+        ask_asset_info: uluna.clone(),
+    }];
+    let zap_out = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(1000u128),
+        msg: to_binary(&Cw20HookMsg::ZapOut {
+            operations_to: [vec![], operations.clone()],
+            ask_asset_info: uluna.clone(),
+            min_receive: Uint128::from(900u128),
+            receiver: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+
+    // only the liquidity tokens of the factory pairs can be zapped out
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        zap_out.clone(),
+    );
+    assert!(res.is_err());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquiditypair0003", &[]),
+        zap_out,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::reply_on_success(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "liquiditypair0003".to_string(),
+                    funds: vec![],
+                    msg: to_binary(&Cw20ExecuteMsg::Send {
+                        contract: "pair0003".to_string(),
+                        amount: Uint128::from(1000u128),
+                        msg: to_binary(&PairCw20HookMsg::WithdrawLiquidity {
+                            min_assets: None,
+                            deadline: None,
+                        })
+                        .unwrap(),
+                    })
+                    .unwrap(),
+                }),
+                TRACK_BALANCES_REPLY_ID
+            ),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: operations[0].clone(),
+                    amount: None,
+                    to: None,
+                    deadline: None,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::TransferPending {
+                    asset_info: uluna.clone(),
+                    receiver: "addr0000".to_string(),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+                    asset_info: uluna,
+                    prev_balance: Uint128::zero(),
+                    minimum_receive: Uint128::from(900u128),
+                    receiver: "addr0000".to_string(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+}
+
+#[test]
+fn execute_batch_swap() {
+    let mut deps = split_swap_dependencies();
+
+    let asset0000 = AssetInfo::Token {
+        contract_addr: "asset0000".to_string(),
+    };
+    let asset0001 = AssetInfo::Token {
+        contract_addr: "asset0001".to_string(),
+    };
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let uluna = AssetInfo::NativeToken {
+        denom: "uluna".to_string(),
+    };
+    let token_operations = vec![
+        SwapOperation::CyberSwap {
+            offer_asset_info: asset0000.clone(),
+            ask_asset_info: uusd.clone(),
+        },
+        SwapOperation::CyberSwap {
+            offer_asset_info: uusd,
+            ask_asset_info: asset0001.clone(),
+        },
+    ];
+    let inputs = vec![
+        BatchSwapInput {
+            offer_asset: Asset {
+                info: uluna.clone(),
+                amount: Uint128::from(1000u128),
+            },
+            operations: vec![SwapOperation::CyberSwap {
+                offer_asset_info: uluna.clone(),
+                ask_asset_info: asset0001.clone(),
+            }],
+        },
+        BatchSwapInput {
+            offer_asset: Asset {
+                info: asset0000.clone(),
+                amount: Uint128::from(2000u128),
+            },
+            operations: token_operations.clone(),
+        },
+        BatchSwapInput {
+            offer_asset: Asset {
+                info: asset0001.clone(),
+                amount: Uint128::from(300u128),
+            },
+            operations: vec![],
+        },
+    ];
+    let batch_swap = |inputs: Vec<BatchSwapInput>| ExecuteMsg::ExecuteBatchSwap {
+        inputs,
+        ask_asset_info: asset0001.clone(),
+        minimum_receive: Some(Uint128::from(3000u128)),
+        to: None,
+        deadline: None,
+    };
+
+    let mut duplicate_inputs = inputs.clone();
+    duplicate_inputs.push(inputs[1].clone());
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin(1000u128, "uluna")]),
+        batch_swap(duplicate_inputs),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err("duplicate offer asset asset0000"))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "addr0000",
+            &[coin(1000u128, "uluna"), coin(1000u128, "uusd")],
+        ),
+        batch_swap(inputs.clone()),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err("no input for the sent uusd"))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin(999u128, "uluna")]),
+        batch_swap(inputs.clone()),
+    );
+    assert!(res.is_err());
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin(1000u128, "uluna")]),
+        batch_swap(inputs.clone()),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: inputs[0].operations[0].clone(),
+                    amount: Some(Uint128::from(1000u128)),
+                    to: Some("addr0000".to_string()),
+                    deadline: None,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "asset0000".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: "addr0000".to_string(),
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    amount: Uint128::from(2000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: token_operations[0].clone(),
+                    amount: Some(Uint128::from(2000u128)),
+                    to: None,
+                    deadline: None,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: token_operations[1].clone(),
+                    amount: None,
+                    to: Some("addr0000".to_string()),
+                    deadline: None,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "asset0001".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: "addr0000".to_string(),
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::from(300u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+                    asset_info: asset0001,
+                    prev_balance: Uint128::zero(),
+                    minimum_receive: Uint128::from(3000u128),
+                    receiver: "addr0000".to_string(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+}
+
+#[test]
+fn execute_swap_operations_with_post_swap_hook() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        cyberswap_factory: "cyberswapfactory".to_string(),
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let asset0001 = AssetInfo::Token {
+        contract_addr: "asset0001".to_string(),
+    };
+    let post_swap_hook = PostSwapHook {
+        contract: "staking0000".to_string(),
+        msg_template: to_binary(&StakingReceiveMsg::Stake {
+            lock_week: 4,
+            referral_addr: Addr::unchecked("addr0000"),
+            staker: Some("addr0000".to_string()),
+        })
+        .unwrap(),
+    };
+    let swap = |to: Option<String>| ExecuteMsg::ExecuteSwapOperations {
+        operations: vec![SwapOperation::CyberSwap {
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            ask_asset_info: asset0001.clone(),
+        }],
+        minimum_receive: None,
+        to,
+        deadline: None,
+        ibc_forward: None,
+        post_swap_hook: Some(post_swap_hook.clone()),
+    };
+
+    let info = mock_info("addr0000", &[coin(1000000, "uusd")]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        swap(Some("addr0001".to_string())),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "cannot call a post swap hook with a receiver"
+        ))
+    );
+
+    // the stake hook can only stake for the swapper
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[coin(1000000, "uusd")]),
+        swap(None),
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "the post swap hook must stake for the swapper"
+        ))
+    );
+
+    // nor for the router
+    let mut router_stake = swap(None);
+    if let ExecuteMsg::ExecuteSwapOperations { post_swap_hook, .. } = &mut router_stake {
+        *post_swap_hook = Some(PostSwapHook {
+            contract: "staking0000".to_string(),
+            msg_template: to_binary(&StakingReceiveMsg::Stake {
+                lock_week: 4,
+                referral_addr: Addr::unchecked("addr0001"),
+                staker: None,
+            })
+            .unwrap(),
+        });
+    }
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), router_stake);
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "the post swap hook must stake for the swapper"
+        ))
+    );
+
+    // the return stays in the router for the hook
+    let res = execute(deps.as_mut(), mock_env(), info, swap(None)).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: SwapOperation::CyberSwap {
+                        offer_asset_info: AssetInfo::NativeToken {
+                            denom: "uusd".to_string(),
+                        },
+                        ask_asset_info: asset0001.clone(),
+                    },
+                    amount: Some(Uint128::from(1000000u128)),
+                    to: None,
+                    deadline: None,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::ExecutePostSwapHook {
+                    asset_info: asset0001.clone(),
+                    post_swap_hook: post_swap_hook.clone(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let msg = ExecuteMsg::ExecutePostSwapHook {
+        asset_info: asset0001.clone(),
+        post_swap_hook: post_swap_hook.clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res, Err(StdError::generic_err("unauthorized")));
+
+    // the pending return is sent to the hook contract with its message
+    let asset_info = asset0001.to_raw(deps.as_ref().api).unwrap();
+    add_pending_amount(
+        deps.as_mut().storage,
+        &asset_info,
+        Uint128::from(500000u128),
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "asset0001".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: "staking0000".to_string(),
+                amount: Uint128::from(500000u128),
+                msg: post_swap_hook.msg_template,
+            })
+            .unwrap(),
+        }))]
+    );
+}
+
+#[test]
+fn validate_route() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        cyberswap_factory: "cyberswapfactory".to_string(),
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let update_config = |max_hops: u32| ExecuteMsg::UpdateConfig {
+        route_assets: None,
+        terraswap_factory: None,
+        astroport_factory: None,
+        max_hops: Some(max_hops),
+        blocked_intermediate_assets: Some(vec![AssetInfo::Token {
+            contract_addr: "asset0001".to_string(),
+        }]),
+        forbid_pair_revisit: Some(true),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        update_config(0),
+    );
+    assert_eq!(res, Err(StdError::generic_err("max_hops must be positive")));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        update_config(1),
+    )
+    .unwrap();
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.max_hops, Some(1));
+    assert_eq!(
+        config.blocked_intermediate_assets,
+        vec![AssetInfo::Token {
+            contract_addr: "asset0001".to_string(),
+        }]
+    );
+    assert!(config.forbid_pair_revisit);
+
+    let operations = vec![
+        SwapOperation::CyberSwap {
+            offer_asset_info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            ask_asset_info: AssetInfo::Token {
+                contract_addr: "asset0001".to_string(),
+            },
+        },
+        SwapOperation::CyberSwap {
+            offer_asset_info: AssetInfo::Token {
+                contract_addr: "asset0001".to_string(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "uluna".to_string(),
+            },
+        },
+    ];
+    let validate_route = |deps: Deps<TerraQuery>, operations: Vec<SwapOperation>| {
+        from_binary::<ValidateRouteResponse>(
+            &query(deps, mock_env(), QueryMsg::ValidateRoute { operations }).unwrap(),
+        )
+        .unwrap()
+    };
+
+    assert_eq!(
+        validate_route(deps.as_ref(), operations.clone()),
+        ValidateRouteResponse {
+            valid: false,
+            error: Some(RouteError::TooManyHops {
+                hops: 2,
+                max_hops: 1,
+            }),
+        }
+    );
+    assert_eq!(
+        validate_route(deps.as_ref(), operations[..1].to_vec()),
+        ValidateRouteResponse {
+            valid: true,
+            error: None,
+        }
+    );
+
+    // the route is rejected on execution with the failing hop
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        update_config(2),
+    )
+    .unwrap();
+    assert_eq!(
+        validate_route(deps.as_ref(), operations.clone()).error,
+        Some(RouteError::BlockedIntermediateAsset {
+            hop: 0,
+            asset_info: AssetInfo::Token {
+                contract_addr: "asset0001".to_string(),
+            },
+        })
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[coin(1000000, "uusd")]),
+        ExecuteMsg::ExecuteSwapOperations {
+            operations,
+            minimum_receive: None,
+            to: None,
+            deadline: None,
+            ibc_forward: None,
+            post_swap_hook: None,
+        },
+    );
+    assert_eq!(
+        res,
+        Err(StdError::generic_err(
+            "invalid operations; hop 0 returns asset0001 which cannot be an intermediate asset"
+        ))
+    );
+}
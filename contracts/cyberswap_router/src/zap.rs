@@ -0,0 +1,362 @@
+use cosmwasm_std::{
+    to_binary, Addr, Api, Coin, CosmosMsg, DepsMut, Env, MessageInfo, QuerierWrapper, Response,
+    StdError, StdResult, Uint128, WasmMsg,
+};
+
+use crate::contract::assert_operations;
+use crate::operations::{route_msgs, track_balances};
+use crate::state::{add_pending_amount, take_pending_amount, Config, CONFIG};
+
+use classic_bindings::{TerraMsg, TerraQuery};
+
+use classic_cyberswap::asset::{Asset, AssetInfo, PairInfo};
+use classic_cyberswap::pair::{Cw20HookMsg as PairCw20HookMsg, ExecuteMsg as PairExecuteMsg};
+use classic_cyberswap::querier::{
+    query_pair_info, query_pair_info_by_liquidity_token, query_pair_info_from_pair,
+};
+use classic_cyberswap::router::{ExecuteMsg, SwapOperation};
+use classic_cyberswap::util::assert_deadline;
+use cw20::Cw20ExecuteMsg;
+
+/// Swaps halves of the offer asset into the pair assets, provides them as liquidity
+/// and refunds the assets left over by the provision
+#[allow(clippy::too_many_arguments)]
+pub fn execute_zap_in(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    pair: [AssetInfo; 2],
+    operations_a: Vec<SwapOperation>,
+    operations_b: Vec<SwapOperation>,
+    min_lp: Uint128,
+    receiver: Option<Addr>,
+    deadline: Option<u64>,
+) -> StdResult<Response<TerraMsg>> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+
+    if offer_asset.amount.is_zero() {
+        return Err(StdError::generic_err("must provide offer amount"));
+    }
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    let cyberswap_factory = deps.api.addr_humanize(&config.cyberswap_factory)?;
+    let pair_info: PairInfo = query_pair_info(&deps.querier, cyberswap_factory.clone(), &pair)?;
+
+    let reserved_asset_infos = [offer_asset.info.clone(), pair[0].clone(), pair[1].clone()];
+    for (operations, ask_asset_info) in [(&operations_a, &pair[0]), (&operations_b, &pair[1])] {
+        assert_zap_operations(
+            &deps.querier,
+            deps.api,
+            &config,
+            operations,
+            &offer_asset.info,
+            ask_asset_info,
+            &reserved_asset_infos,
+        )?;
+    }
+
+    let amount_a = offer_asset.amount.multiply_ratio(1u128, 2u128);
+    let amount_b = offer_asset.amount.checked_sub(amount_a)?;
+    let receiver = receiver.unwrap_or_else(|| sender.clone());
+
+    // the halves already in a pair asset are pending for the provision
+    for (operations, amount) in [(&operations_a, amount_a), (&operations_b, amount_b)] {
+        if operations.is_empty() {
+            add_pending_amount(deps.storage, &offer_asset.info.to_raw(deps.api)?, amount)?;
+        }
+    }
+
+    let mut messages: Vec<CosmosMsg<TerraMsg>> = vec![];
+    messages.extend(route_msgs(&env, operations_a, Some(amount_a), None)?);
+    messages.extend(route_msgs(&env, operations_b, Some(amount_b), None)?);
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::ZapProvideLiquidity {
+            pair_contract: pair_info.contract_addr,
+            receiver: receiver.to_string(),
+        })?,
+    }));
+
+    // refund the assets the pair did not take
+    for asset_info in pair {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::TransferPending {
+                asset_info,
+                receiver: sender.to_string(),
+            })?,
+        }));
+    }
+
+    let lp_token_info = AssetInfo::Token {
+        contract_addr: pair_info.liquidity_token,
+    };
+    let receiver_balance = lp_token_info.query_pool(&deps.querier, deps.api, receiver.clone())?;
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+            asset_info: lp_token_info,
+            prev_balance: receiver_balance,
+            minimum_receive: min_lp,
+            receiver: receiver.to_string(),
+        })?,
+    }));
+
+    Ok(Response::new().add_messages(messages))
+}
+
+/// Withdraws the liquidity and swaps both pair assets into the ask asset
+#[allow(clippy::too_many_arguments)]
+pub fn execute_zap_out(
+    mut deps: DepsMut<TerraQuery>,
+    env: Env,
+    sender: Addr,
+    lp_token: Addr,
+    amount: Uint128,
+    operations_to: [Vec<SwapOperation>; 2],
+    ask_asset_info: AssetInfo,
+    min_receive: Uint128,
+    receiver: Option<Addr>,
+    deadline: Option<u64>,
+) -> StdResult<Response<TerraMsg>> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    let cyberswap_factory = deps.api.addr_humanize(&config.cyberswap_factory)?;
+    let pair_info: PairInfo = query_pair_info_by_liquidity_token(
+        &deps.querier,
+        cyberswap_factory.clone(),
+        lp_token.to_string(),
+    )?;
+
+    for (operations, offer_asset_info) in operations_to.iter().zip(pair_info.asset_infos.iter()) {
+        assert_zap_operations(
+            &deps.querier,
+            deps.api,
+            &config,
+            operations,
+            offer_asset_info,
+            &ask_asset_info,
+            &pair_info.asset_infos,
+        )?;
+    }
+
+    let receiver = receiver.unwrap_or(sender);
+
+    // the withdrawn assets are pending for the swaps
+    let prev_balances = pair_info
+        .asset_infos
+        .iter()
+        .map(|asset_info| {
+            Ok(Asset {
+                info: asset_info.clone(),
+                amount: asset_info.query_pool(
+                    &deps.querier,
+                    deps.api,
+                    env.contract.address.clone(),
+                )?,
+            })
+        })
+        .collect::<StdResult<Vec<Asset>>>()?;
+    let withdraw_msg = track_balances(
+        deps.branch(),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: lp_token.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: pair_info.contract_addr,
+                amount,
+                msg: to_binary(&PairCw20HookMsg::WithdrawLiquidity {
+                    min_assets: None,
+                    deadline: None,
+                })?,
+            })?,
+        }),
+        prev_balances,
+    )?;
+
+    let mut messages: Vec<CosmosMsg<TerraMsg>> = vec![];
+    for operations in operations_to {
+        messages.extend(route_msgs(&env, operations, None, None)?);
+    }
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::TransferPending {
+            asset_info: ask_asset_info.clone(),
+            receiver: receiver.to_string(),
+        })?,
+    }));
+
+    let receiver_balance = ask_asset_info.query_pool(&deps.querier, deps.api, receiver.clone())?;
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::AssertMinimumReceive {
+            asset_info: ask_asset_info,
+            prev_balance: receiver_balance,
+            minimum_receive: min_receive,
+            receiver: receiver.to_string(),
+        })?,
+    }));
+
+    Ok(Response::new()
+        .add_submessage(withdraw_msg)
+        .add_messages(messages))
+}
+
+/// Checks the operations swap the offer asset into the ask asset,
+/// an empty list is only valid when both are the same asset
+fn assert_zap_operations(
+    querier: &QuerierWrapper<TerraQuery>,
+    api: &dyn Api,
+    config: &Config,
+    operations: &[SwapOperation],
+    offer_asset_info: &AssetInfo,
+    ask_asset_info: &AssetInfo,
+    reserved_asset_infos: &[AssetInfo],
+) -> StdResult<()> {
+    let (first_operation, last_operation) = match (operations.first(), operations.last()) {
+        (Some(first_operation), Some(last_operation)) => (first_operation, last_operation),
+        _ if offer_asset_info == ask_asset_info => return Ok(()),
+        _ => {
+            return Err(StdError::generic_err(format!(
+                "zap operations must swap {} into {}",
+                offer_asset_info, ask_asset_info
+            )))
+        }
+    };
+
+    if first_operation.get_offer_asset_info() != *offer_asset_info
+        || last_operation.get_target_asset_info() != *ask_asset_info
+    {
+        return Err(StdError::generic_err(format!(
+            "zap operations must swap {} into {}",
+            offer_asset_info, ask_asset_info
+        )));
+    }
+
+    // the following operations swap the whole pending amount of their offer asset
+    if operations[1..]
+        .iter()
+        .any(|operation| reserved_asset_infos.contains(&operation.get_offer_asset_info()))
+    {
+        return Err(StdError::generic_err(
+            "zap operations cannot go through the offer asset or the pair assets",
+        ));
+    }
+
+    assert_operations(querier, api, config, operations)
+}
+
+/// Provides the pending amounts of the pair assets as liquidity,
+/// the amounts the pair does not take are pending again
+pub fn zap_provide_liquidity(
+    mut deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+    pair_contract: Addr,
+    receiver: Addr,
+) -> StdResult<Response<TerraMsg>> {
+    if env.contract.address != info.sender {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let pair_info: PairInfo = query_pair_info_from_pair(&deps.querier, pair_contract.clone())?;
+
+    let mut messages: Vec<CosmosMsg<TerraMsg>> = vec![];
+    let mut funds: Vec<Coin> = vec![];
+    let mut assets: Vec<Asset> = vec![];
+    let mut prev_balances: Vec<Asset> = vec![];
+    for asset_info in pair_info.asset_infos {
+        let asset = Asset {
+            amount: take_pending_amount(deps.storage, &asset_info.to_raw(deps.api)?)?,
+            info: asset_info,
+        };
+
+        let balance =
+            asset
+                .info
+                .query_pool(&deps.querier, deps.api, env.contract.address.clone())?;
+        prev_balances.push(Asset {
+            info: asset.info.clone(),
+            amount: balance.checked_sub(asset.amount)?,
+        });
+
+        match &asset.info {
+            AssetInfo::NativeToken { .. } => {
+                // deduct tax first
+                let coin = asset.deduct_tax(&deps.querier)?;
+                assets.push(Asset {
+                    amount: coin.amount,
+                    ..asset
+                });
+                funds.push(coin);
+            }
+            AssetInfo::Token { contract_addr } => {
+                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract_addr.to_string(),
+                    funds: vec![],
+                    msg: to_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                        spender: pair_contract.to_string(),
+                        amount: asset.amount,
+                        expires: None,
+                    })?,
+                }));
+                assets.push(asset);
+            }
+        }
+    }
+
+    funds.sort_by(|a, b| a.denom.cmp(&b.denom));
+    let provide_msg = track_balances(
+        deps.branch(),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: pair_contract.to_string(),
+            funds,
+            msg: to_binary(&PairExecuteMsg::ProvideLiquidity {
+                assets: [assets[0].clone(), assets[1].clone()],
+                receiver: Some(receiver.to_string()),
+                deadline: None,
+                slippage_tolerance: None,
+            })?,
+        }),
+        prev_balances,
+    )?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessage(provide_msg))
+}
+
+/// Transfers the pending amount of the asset
+pub fn transfer_pending(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+    asset_info: AssetInfo,
+    receiver: Addr,
+) -> StdResult<Response<TerraMsg>> {
+    if env.contract.address != info.sender {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let amount = take_pending_amount(deps.storage, &asset_info.to_raw(deps.api)?)?;
+    if amount.is_zero() {
+        return Ok(Response::default());
+    }
+
+    Ok(Response::new().add_message(
+        Asset {
+            info: asset_info,
+            amount,
+        }
+        .into_msg(&deps.querier, receiver)?,
+    ))
+}
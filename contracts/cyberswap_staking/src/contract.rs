@@ -2,22 +2,27 @@ use crate::error::ContractError;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    from_binary, to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order,
-    QuerierWrapper, Response, StdError, StdResult, Storage, Uint128,
+    from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo,
+    Order, QuerierWrapper, Response, StdError, StdResult, Storage, Uint128,
 };
 use cw2::set_contract_version;
 use cw20::Cw20ReceiveMsg;
 use cw_storage_plus::Bound;
 
-use crate::state::{Config, CONFIG, STAKED_DETAIL, STAKER_INFO};
+use crate::state::{
+    load_staker_info, read_position, read_staker_info, Config, OwnershipProposal, CONFIG,
+    LEGACY_STAKER_INFO, OWNERSHIP_PROPOSAL, POSITIONS, STAKED_DETAIL, STAKER_INFO,
+};
 use crate::util;
 use classic_bindings::{TerraMsg, TerraQuery};
 use classic_cyberswap::asset::AssetInfo;
 use classic_cyberswap::querier::compute_tax;
 use classic_cyberswap::staking::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReceiveMsg, StakedDetail,
-    StakingList, StakingListResponse, StakingResponse,
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, PenaltyDestination, PositionResponse,
+    PositionsResponse, QueryMsg, ReceiveMsg, SimulateEarlyUnstakeResponse, StakedDetail,
+    StakingList, StakingListResponse, StakingPosition, StakingResponse,
 };
+use classic_cyberswap::util::migrate_version;
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cyberswap-staking";
@@ -34,6 +39,7 @@ pub const ONE_YEAR_MONTH: u64 = 12; // 12 months
 pub const ONE_MONTH_DAY: u64 = 30; // 30 days
 pub const ONE_WEEK_DAY: u64 = 7; // 7 days
 pub const DEFAULT_PRECISION: u64 = 1_000_000_000;
+pub const MAX_OWNERSHIP_PROPOSAL_TTL: u64 = 14 * ONE_DAY; // 14 days to sec
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -41,9 +47,16 @@ pub fn instantiate(
     _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response<TerraMsg>> {
+) -> Result<Response<TerraMsg>, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    if msg.early_unstake_penalty_rate > 100 {
+        return Err(ContractError::InvalidInput {});
+    }
+    if let PenaltyDestination::Treasury { address } = &msg.penalty_destination {
+        deps.api.addr_validate(address.as_str())?;
+    }
+
     let config = Config {
         owner: deps.api.addr_canonicalize(info.sender.as_str())?,
         staking_asset: msg.staking_asset,
@@ -56,6 +69,8 @@ pub fn instantiate(
         referral_rate: msg.referral_rate,
         referral_lock_days: msg.referral_lock_days,
         enabled: msg.enabled,
+        early_unstake_penalty_rate: msg.early_unstake_penalty_rate,
+        penalty_destination: msg.penalty_destination,
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -64,6 +79,7 @@ pub fn instantiate(
         total_acc: Uint128::zero(),
         total_wcc: Uint128::zero(),
         total_staked: Uint128::zero(),
+        penalty_index: Decimal::zero(),
     };
 
     STAKED_DETAIL.save(deps.storage, &staked_detail)?;
@@ -79,7 +95,6 @@ pub fn execute(
 ) -> Result<Response<TerraMsg>, ContractError> {
     match msg {
         ExecuteMsg::UpdateConfig {
-            owner,
             staking_asset,
             reward_asset,
             router_address,
@@ -90,11 +105,12 @@ pub fn execute(
             referral_rate,
             referral_lock_days,
             enabled,
+            early_unstake_penalty_rate,
+            penalty_destination,
         } => execute_update_config(
             deps,
             env,
             info,
-            owner,
             staking_asset,
             reward_asset,
             router_address,
@@ -105,6 +121,8 @@ pub fn execute(
             referral_rate,
             referral_lock_days,
             enabled,
+            early_unstake_penalty_rate,
+            penalty_destination,
         ),
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
         ExecuteMsg::Stake {
@@ -116,20 +134,47 @@ pub fn execute(
                 .api
                 .addr_validate(&referral_addr.to_string())
                 .unwrap_or(referral_addr);
+
+            // a direct stake is backed by the wallet of the sender,
+            // the received tokens are held by the contract already
+            let config = CONFIG.load(deps.storage)?;
+            let balance = util::get_token_amount(
+                deps.querier,
+                config.staking_asset.to_denom()?,
+                info.sender.clone(),
+            )?;
+            if amount > balance {
+                return Err(ContractError::Insufficient {});
+            }
+
             execute_stake(deps, env, info.sender, lock_week, amount, referral_address)
         }
-        ExecuteMsg::Unstake { amount } => execute_unstake(deps, env, info, amount),
-        ExecuteMsg::ClaimReward {} => execute_claim_reward(deps, env, info),
+        ExecuteMsg::Unstake {
+            position_id,
+            amount,
+        } => execute_unstake(deps, env, info, position_id, amount),
+        ExecuteMsg::EarlyUnstake {
+            position_id,
+            amount,
+        } => execute_early_unstake(deps, env, info, position_id, amount),
+        ExecuteMsg::ClaimReward { position_id } => {
+            execute_claim_reward(deps, env, info, position_id)
+        }
         ExecuteMsg::ClaimReferralReward {} => execute_claim_referral_reward(deps, env, info),
         ExecuteMsg::Withdraw {} => execute_withdraw(deps, env, info),
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            execute_propose_new_owner(deps, env, info, owner, expires_in)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => execute_drop_ownership_proposal(deps, env, info),
+        ExecuteMsg::ClaimOwnership {} => execute_claim_ownership(deps, env, info),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_update_config(
     deps: DepsMut<TerraQuery>,
     _env: Env,
     info: MessageInfo,
-    owner: Option<String>,
     staking_asset: Option<AssetInfo>,
     reward_asset: Option<AssetInfo>,
     router_address: Option<Addr>,
@@ -140,20 +185,13 @@ pub fn execute_update_config(
     referral_rate: Option<u64>,
     referral_lock_days: Option<u64>,
     enabled: Option<bool>,
+    early_unstake_penalty_rate: Option<u64>,
+    penalty_destination: Option<PenaltyDestination>,
 ) -> Result<Response<TerraMsg>, ContractError> {
     let mut config: Config = CONFIG.load(deps.storage)?;
 
     check_owner(&deps, &info)?;
 
-    if let Some(owner) = owner {
-        // validate address format
-        let _ = deps
-            .api
-            .addr_validate(&owner)
-            .unwrap_or(Addr::unchecked(owner.clone()));
-        config.owner = deps.api.addr_canonicalize(&owner)?;
-    }
-
     if let Some(staking_asset) = staking_asset {
         config.staking_asset = staking_asset;
     }
@@ -194,11 +232,105 @@ pub fn execute_update_config(
         config.enabled = enabled;
     }
 
+    if let Some(early_unstake_penalty_rate) = early_unstake_penalty_rate {
+        if early_unstake_penalty_rate > 100 {
+            return Err(ContractError::InvalidInput {});
+        }
+        config.early_unstake_penalty_rate = early_unstake_penalty_rate;
+    }
+
+    if let Some(penalty_destination) = penalty_destination {
+        if let PenaltyDestination::Treasury { address } = &penalty_destination {
+            deps.api.addr_validate(address.as_str())?;
+        }
+        config.penalty_destination = penalty_destination;
+    }
+
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new().add_attribute("action", "update_config"))
 }
 
+pub fn execute_propose_new_owner(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    expires_in: u64,
+) -> Result<Response<TerraMsg>, ContractError> {
+    check_owner(&deps, &info)?;
+
+    if expires_in > MAX_OWNERSHIP_PROPOSAL_TTL {
+        return Err(ContractError::InvalidOwnershipProposalTtl {
+            max: MAX_OWNERSHIP_PROPOSAL_TTL,
+        });
+    }
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    let new_owner = deps.api.addr_validate(&owner)?;
+    let new_owner_raw = deps.api.addr_canonicalize(new_owner.as_str())?;
+    if new_owner_raw == config.owner {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    let expires_at = env.block.time.seconds() + expires_in;
+    OWNERSHIP_PROPOSAL.save(
+        deps.storage,
+        &OwnershipProposal {
+            owner: new_owner_raw,
+            expires_at,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "propose_new_owner"),
+        ("new_owner", new_owner.as_str()),
+        ("expires_at", &expires_at.to_string()),
+    ]))
+}
+
+pub fn execute_drop_ownership_proposal(
+    deps: DepsMut<TerraQuery>,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response<TerraMsg>, ContractError> {
+    check_owner(&deps, &info)?;
+
+    OWNERSHIP_PROPOSAL.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "drop_ownership_proposal"))
+}
+
+pub fn execute_claim_ownership(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<TerraMsg>, ContractError> {
+    let proposal = OWNERSHIP_PROPOSAL
+        .may_load(deps.storage)?
+        .ok_or(ContractError::OwnershipProposalNotFound {})?;
+
+    if deps.api.addr_canonicalize(info.sender.as_str())? != proposal.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if env.block.time.seconds() > proposal.expires_at {
+        return Err(ContractError::OwnershipProposalExpired {});
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let previous_owner = deps.api.addr_humanize(&config.owner)?;
+    config.owner = proposal.owner;
+    CONFIG.save(deps.storage, &config)?;
+    OWNERSHIP_PROPOSAL.remove(deps.storage);
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "claim_ownership"),
+        ("previous_owner", previous_owner.as_str()),
+        ("new_owner", info.sender.as_str()),
+    ]))
+}
+
 pub fn execute_receive(
     deps: DepsMut<TerraQuery>,
     env: Env,
@@ -225,16 +357,28 @@ pub fn execute_receive(
         ReceiveMsg::Stake {
             lock_week,
             referral_addr,
+            staker,
         } => {
             let referral_address = deps
                 .api
                 .addr_validate(&referral_addr.to_string())
                 .unwrap_or(referral_addr);
 
+            // the router stakes the return of a swap for the swapper
+            let staker = match staker {
+                Some(staker) => {
+                    if *user_addr != config.router_address {
+                        return Err(ContractError::Unauthorized {});
+                    }
+                    deps.api.addr_validate(&staker)?
+                }
+                None => user_addr.clone(),
+            };
+
             execute_stake(
                 deps,
                 env,
-                user_addr.clone(),
+                staker,
                 lock_week,
                 wrapper.amount,
                 referral_address,
@@ -297,38 +441,88 @@ pub fn calc_reward(
     Ok(return_reward)
 }
 
-pub fn update_reward(
-    storage: &mut dyn Storage,
+// This calculate the rewards accrued by a position since its reward timestamp.
+pub fn calc_position_reward(
     querier: QuerierWrapper<TerraQuery>,
-    env: Env,
-    address: Addr,
-) -> Result<Response<TerraMsg>, ContractError> {
-    let config = CONFIG.load(storage)?;
-    let mut staker_info = STAKER_INFO
-        .load(storage, address.clone())
-        .unwrap_or_default();
-    if staker_info.stake_amount.is_zero() {
-        return Ok(Response::new());
+    config: Config,
+    now: u64,
+    position: &StakingPosition,
+) -> Result<Uint128, ContractError> {
+    if position.stake_amount.is_zero() {
+        return Ok(Uint128::zero());
     }
     let swap_amount = util::get_simulate_swap_operations(
         querier,
         config.router_address.clone().into(),
-        staker_info.stake_amount,
+        position.stake_amount,
         config.staking_asset.clone().into(),
         config.reward_asset.clone().into(),
     )?;
-    let reward = calc_reward(
+    calc_reward(
         config,
-        env.block.time.seconds(),
-        staker_info.lock_week,
+        now,
+        position.lock_week,
         swap_amount,
-        staker_info.reward_timestamp,
-    )?;
-    staker_info.reward_amount = reward;
-    STAKER_INFO
-        .save(storage, address.clone(), &staker_info.clone())
-        .unwrap();
-    Ok(Response::new().add_attribute("action", "update_reward"))
+        position.reward_timestamp,
+    )
+}
+
+pub fn update_reward(
+    storage: &mut dyn Storage,
+    querier: QuerierWrapper<TerraQuery>,
+    env: Env,
+    address: Addr,
+    position_id: u64,
+) -> Result<StakingPosition, ContractError> {
+    let config = CONFIG.load(storage)?;
+    load_staker_info(storage, address.clone())?;
+    let mut position = POSITIONS
+        .may_load(storage, (address.clone(), position_id))?
+        .ok_or(ContractError::PositionNotFound {})?;
+    position.reward_amount =
+        calc_position_reward(querier, config, env.block.time.seconds(), &position)?;
+    POSITIONS.save(storage, (address, position_id), &position)?;
+    Ok(position)
+}
+
+// This calculate the penalty of an early unstake and the remaining lock time,
+// the penalty decreases linearly from the full rate at staking to zero at expiry.
+pub fn calc_penalty(
+    config: &Config,
+    now: u64,
+    position: &StakingPosition,
+    amount: Uint128,
+) -> (Uint128, u64) {
+    let lock_times = position.lock_week * ONE_WEEK;
+    let remaining_lock = (position.staked_timestamp + lock_times).saturating_sub(now);
+    if remaining_lock == 0 {
+        return (Uint128::zero(), 0);
+    }
+    let penalty = amount.multiply_ratio(
+        Uint128::from(config.early_unstake_penalty_rate) * Uint128::from(remaining_lock),
+        Uint128::from(100u64) * Uint128::from(lock_times),
+    );
+    (penalty, remaining_lock)
+}
+
+// This calculate the penalty and the staking asset returned by an early unstake of a settled
+// position, the redistributed penalties accrued by the position are returned as well.
+pub fn calc_early_unstake(
+    config: &Config,
+    now: u64,
+    position: &StakingPosition,
+    amount: Uint128,
+) -> (Uint128, Uint128, u64) {
+    let (penalty, remaining_lock) = calc_penalty(config, now, position, amount);
+    let return_amount = amount - penalty + position.penalty_reward;
+    (penalty, return_amount, remaining_lock)
+}
+
+// This settles the redistributed penalties accrued by a position since its last settlement.
+pub fn settle_penalty_share(detail: &StakedDetail, position: &mut StakingPosition) {
+    position.penalty_reward +=
+        position.stake_amount * (detail.penalty_index - position.penalty_index);
+    position.penalty_index = detail.penalty_index;
 }
 
 pub fn check_lock(now: u64, staked_timestamp: u64, lock_week: u64) -> bool {
@@ -353,41 +547,11 @@ pub fn execute_stake(
     }
     let config = CONFIG.load(deps.storage)?;
 
-    let balance = util::get_token_amount(
-        deps.querier,
-        config.staking_asset.to_denom()?,
-        sender.clone(),
-    )?;
-
     if lock_week < config.min_lock_week || lock_week > config.max_lock_week {
         return Err(ContractError::InvalidInput {});
     }
 
-    if amount > balance {
-        return Err(ContractError::Insufficient {});
-    }
-
-    let mut staker_info = STAKER_INFO
-        .load(deps.storage, sender.clone())
-        .unwrap_or_default();
-
-    let mut last_reward = Uint128::zero();
-    if staker_info.lock_week > 0 {
-        let swap_amount = util::get_simulate_swap_operations(
-            deps.querier,
-            config.router_address.clone().into(),
-            staker_info.stake_amount,
-            config.staking_asset.clone().into(),
-            config.reward_asset.clone().into(),
-        )?;
-        last_reward = calc_reward(
-            config.clone(),
-            env.block.time.seconds(),
-            staker_info.lock_week,
-            swap_amount,
-            staker_info.reward_timestamp,
-        )?;
-    }
+    let mut staker_info = load_staker_info(deps.storage, sender.clone())?;
 
     let referral_staked_amount = amount
         .checked_mul(Uint128::from(config.referral_rate))?
@@ -406,9 +570,7 @@ pub fn execute_stake(
         real_referral_addr = staker_info.referral_addr.clone().into();
     }
 
-    let mut referral_info = STAKER_INFO
-        .load(deps.storage, real_referral_addr.clone())
-        .unwrap_or_default();
+    let mut referral_info = load_staker_info(deps.storage, real_referral_addr.clone())?;
     referral_info.referral_reward += referral_reward;
     if staker_info.referral_addr.to_string().is_empty() {
         referral_info.referral_count += 1u64;
@@ -419,16 +581,28 @@ pub fn execute_stake(
 
     STAKER_INFO.save(deps.storage, real_referral_addr.clone(), &referral_info)?;
 
-    staker_info.lock_week = lock_week;
     staker_info.stake_amount += amount;
-    staker_info.last_reward += last_reward;
-    staker_info.staked_timestamp = env.block.time.seconds();
-    staker_info.reward_timestamp = env.block.time.seconds();
+    staker_info.position_count += 1;
     staker_info.referral_addr = real_referral_addr.clone();
     STAKER_INFO.save(deps.storage, sender.clone(), &staker_info)?;
 
-    let ratio = calc_ratio(config.clone(), lock_week)?;
     let mut detail = STAKED_DETAIL.load(deps.storage)?;
+
+    // every stake opens its own position with its own lock
+    let position = StakingPosition {
+        id: staker_info.position_count,
+        lock_week,
+        stake_amount: amount,
+        reward_amount: Uint128::zero(),
+        last_reward: Uint128::zero(),
+        staked_timestamp: env.block.time.seconds(),
+        reward_timestamp: env.block.time.seconds(),
+        penalty_index: detail.penalty_index,
+        penalty_reward: Uint128::zero(),
+    };
+    POSITIONS.save(deps.storage, (sender.clone(), position.id), &position)?;
+
+    let ratio = calc_ratio(config.clone(), lock_week)?;
     detail.total_acc += Uint128::from(ratio)
         .checked_mul(Uint128::from(amount))?
         .checked_div(Uint128::from(DEFAULT_PRECISION))
@@ -440,6 +614,7 @@ pub fn execute_stake(
     return Ok(Response::new().add_attributes(vec![
         ("action", "stake"),
         ("staker", &sender.to_string()),
+        ("position_id", &position.id.to_string()),
         ("stake_amount", &amount.to_string()),
     ]));
 }
@@ -448,49 +623,64 @@ pub fn execute_unstake(
     deps: DepsMut<TerraQuery>,
     env: Env,
     info: MessageInfo,
+    position_id: u64,
     amount: Uint128,
 ) -> Result<Response<TerraMsg>, ContractError> {
     check_enabled(&deps, &info)?;
-    update_reward(deps.storage, deps.querier, env.clone(), info.sender.clone()).unwrap();
+    let mut position = update_reward(
+        deps.storage,
+        deps.querier,
+        env.clone(),
+        info.sender.clone(),
+        position_id,
+    )?;
 
     let config = CONFIG.load(deps.storage)?;
-    let mut staker_info = STAKER_INFO
-        .load(deps.storage, info.sender.clone())
-        .unwrap_or_default();
+    let mut staker_info = load_staker_info(deps.storage, info.sender.clone())?;
 
-    if amount > staker_info.stake_amount {
+    if amount > position.stake_amount {
         return Err(ContractError::InvalidInput {});
     }
 
     if check_lock(
         env.block.time.seconds(),
-        staker_info.staked_timestamp,
-        staker_info.lock_week,
+        position.staked_timestamp,
+        position.lock_week,
     ) == true
     {
-        let ratio = calc_ratio(config.clone(), staker_info.lock_week)?;
+        let ratio = calc_ratio(config.clone(), position.lock_week)?;
         let mut detail = STAKED_DETAIL.load(deps.storage)?;
+        settle_penalty_share(&detail, &mut position);
         detail.total_acc -= Uint128::from(ratio) * amount / Uint128::from(DEFAULT_PRECISION);
-        detail.total_wcc -= Uint128::from(staker_info.lock_week) * amount;
+        detail.total_wcc -= Uint128::from(position.lock_week) * amount;
         detail.total_staked -= amount;
         STAKED_DETAIL.save(deps.storage, &detail)?;
     } else {
         return Err(ContractError::StillInLock {});
     }
 
-    let total_rewards = staker_info.reward_amount + staker_info.last_reward;
+    let total_rewards = position.reward_amount + position.last_reward;
 
     if total_rewards.is_zero() {
         return Err(ContractError::NotEnoughReward {});
     }
 
+    let penalty_reward = position.penalty_reward;
+    position.stake_amount -= amount;
+    if position.stake_amount.is_zero() {
+        POSITIONS.remove(deps.storage, (info.sender.clone(), position_id));
+    } else {
+        position.reward_amount = Uint128::zero();
+        position.last_reward = Uint128::zero();
+        position.penalty_reward = Uint128::zero();
+        position.reward_timestamp = env.block.time.seconds();
+        POSITIONS.save(deps.storage, (info.sender.clone(), position_id), &position)?;
+    }
+
     staker_info.stake_amount -= amount;
-    staker_info.reward_amount = Uint128::zero();
-    staker_info.last_reward = Uint128::zero();
-    staker_info.reward_timestamp = env.block.time.seconds();
     STAKER_INFO.save(deps.storage, info.sender.clone(), &staker_info)?;
 
-    let mut amount1 = amount;
+    let mut amount1 = amount + penalty_reward;
     let mut amount2 = total_rewards;
     if let AssetInfo::NativeToken { denom } = config.staking_asset.clone() {
         amount1 = amount1.checked_sub(compute_tax(&deps.querier, amount1, denom.clone())?)?;
@@ -515,42 +705,156 @@ pub fn execute_unstake(
     return Ok(Response::new().add_messages(msgs).add_attributes(vec![
         ("action", "unstake"),
         ("unstaker", info.sender.as_str()),
+        ("position_id", &position_id.to_string()),
         ("ustaked_amount", &amount.to_string()),
     ]));
 }
 
-pub fn execute_claim_reward(
+pub fn execute_early_unstake(
     deps: DepsMut<TerraQuery>,
     env: Env,
     info: MessageInfo,
+    position_id: u64,
+    amount: Uint128,
 ) -> Result<Response<TerraMsg>, ContractError> {
     check_enabled(&deps, &info)?;
-    let config = CONFIG.load(deps.storage)?;
-    update_reward(deps.storage, deps.querier, env.clone(), info.sender.clone()).unwrap();
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let mut position = update_reward(
+        deps.storage,
+        deps.querier,
+        env.clone(),
+        info.sender.clone(),
+        position_id,
+    )?;
 
+    let config = CONFIG.load(deps.storage)?;
     let mut staker_info = STAKER_INFO
         .load(deps.storage, info.sender.clone())
         .unwrap_or_default();
 
+    if amount > position.stake_amount {
+        return Err(ContractError::InvalidInput {});
+    }
+
+    let ratio = calc_ratio(config.clone(), position.lock_week)?;
+    let mut detail = STAKED_DETAIL.load(deps.storage)?;
+    settle_penalty_share(&detail, &mut position);
+
+    let now = env.block.time.seconds();
+    let (penalty, return_amount, _) = calc_early_unstake(&config, now, &position, amount);
+
+    detail.total_acc -= Uint128::from(ratio) * amount / Uint128::from(DEFAULT_PRECISION);
+    detail.total_wcc -= Uint128::from(position.lock_week) * amount;
+    detail.total_staked -= amount;
+
+    // the rewards of the withdrawn amount are forfeited, the rest stays on the position
+    let remaining_amount = position.stake_amount - amount;
+    position.last_reward += position
+        .reward_amount
+        .multiply_ratio(remaining_amount, position.stake_amount);
+    position.reward_amount = Uint128::zero();
+    position.reward_timestamp = now;
+    position.stake_amount = remaining_amount;
+
+    let mut msgs: Vec<CosmosMsg<TerraMsg>> = vec![];
+    if !penalty.is_zero() {
+        match config.penalty_destination.clone() {
+            PenaltyDestination::Treasury { address } => {
+                let mut penalty_amount = penalty;
+                if let AssetInfo::NativeToken { denom } = config.staking_asset.clone() {
+                    penalty_amount = penalty_amount.checked_sub(compute_tax(
+                        &deps.querier,
+                        penalty_amount,
+                        denom.clone(),
+                    )?)?;
+                }
+                msgs.push(util::transfer_token_message(
+                    config.staking_asset.to_denom()?,
+                    penalty_amount,
+                    address,
+                )?);
+            }
+            PenaltyDestination::Redistribute {} => {
+                // with no other stakers left the penalty stays in the contract
+                let others_staked = detail.total_staked - position.stake_amount;
+                if !others_staked.is_zero() {
+                    detail.penalty_index += Decimal::from_ratio(penalty, others_staked);
+                }
+            }
+        }
+    }
+    // the unstaker takes no share of its own penalty
+    position.penalty_index = detail.penalty_index;
+    STAKED_DETAIL.save(deps.storage, &detail)?;
+
+    position.penalty_reward = Uint128::zero();
+    if position.stake_amount.is_zero() {
+        POSITIONS.remove(deps.storage, (info.sender.clone(), position_id));
+    } else {
+        POSITIONS.save(deps.storage, (info.sender.clone(), position_id), &position)?;
+    }
+
+    staker_info.stake_amount -= amount;
+    STAKER_INFO.save(deps.storage, info.sender.clone(), &staker_info)?;
+
+    if !return_amount.is_zero() {
+        let mut amount1 = return_amount;
+        if let AssetInfo::NativeToken { denom } = config.staking_asset.clone() {
+            amount1 = amount1.checked_sub(compute_tax(&deps.querier, amount1, denom.clone())?)?;
+        }
+        msgs.push(util::transfer_token_message(
+            config.staking_asset.to_denom()?,
+            amount1,
+            info.sender.clone(),
+        )?);
+    }
+
+    return Ok(Response::new().add_messages(msgs).add_attributes(vec![
+        ("action", "early_unstake"),
+        ("unstaker", info.sender.as_str()),
+        ("position_id", &position_id.to_string()),
+        ("unstaked_amount", &amount.to_string()),
+        ("penalty", &penalty.to_string()),
+    ]));
+}
+
+pub fn execute_claim_reward(
+    deps: DepsMut<TerraQuery>,
+    env: Env,
+    info: MessageInfo,
+    position_id: u64,
+) -> Result<Response<TerraMsg>, ContractError> {
+    check_enabled(&deps, &info)?;
+    let config = CONFIG.load(deps.storage)?;
+    let mut position = update_reward(
+        deps.storage,
+        deps.querier,
+        env.clone(),
+        info.sender.clone(),
+        position_id,
+    )?;
+
     if check_lock(
         env.block.time.seconds(),
-        staker_info.staked_timestamp,
-        staker_info.lock_week,
+        position.staked_timestamp,
+        position.lock_week,
     ) == false
     {
         return Err(ContractError::StillInLock {});
     }
 
-    let reward_amount = staker_info.reward_amount + staker_info.last_reward;
+    let reward_amount = position.reward_amount + position.last_reward;
 
     if reward_amount.is_zero() {
         return Err(ContractError::NotEnoughReward {});
     }
 
-    staker_info.reward_timestamp = env.block.time.seconds();
-    staker_info.reward_amount = Uint128::zero();
-    staker_info.last_reward = Uint128::zero();
-    STAKER_INFO.save(deps.storage, info.sender.clone(), &staker_info)?;
+    position.reward_timestamp = env.block.time.seconds();
+    position.reward_amount = Uint128::zero();
+    position.last_reward = Uint128::zero();
+    POSITIONS.save(deps.storage, (info.sender.clone(), position_id), &position)?;
 
     let mut msgs: Vec<CosmosMsg<TerraMsg>> = vec![];
     msgs.push(util::transfer_token_message(
@@ -562,6 +866,7 @@ pub fn execute_claim_reward(
     return Ok(Response::new().add_messages(msgs).add_attributes(vec![
         ("action", "claim_reward"),
         ("address", info.sender.as_str()),
+        ("position_id", &position_id.to_string()),
         ("reward_amount", &reward_amount.to_string()),
     ]));
 }
@@ -573,11 +878,8 @@ pub fn execute_claim_referral_reward(
 ) -> Result<Response<TerraMsg>, ContractError> {
     check_enabled(&deps, &info)?;
     let config = CONFIG.load(deps.storage)?;
-    update_reward(deps.storage, deps.querier, env.clone(), info.sender.clone()).unwrap();
 
-    let mut staker_info = STAKER_INFO
-        .load(deps.storage, info.sender.clone())
-        .unwrap_or_default();
+    let mut staker_info = load_staker_info(deps.storage, info.sender.clone())?;
 
     if env.block.time.seconds()
         < (staker_info.referral_timestamp + config.referral_lock_days * ONE_DAY)
@@ -694,10 +996,30 @@ pub fn check_enabled(
 pub fn query(deps: Deps<TerraQuery>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
-        QueryMsg::StakerInfo { address } => to_binary(&query_staking_info(deps, _env, address)?),
+        QueryMsg::StakerInfo { address } => to_binary(&query_staking_info(deps, address)?),
+        QueryMsg::Position {
+            address,
+            position_id,
+        } => to_binary(&query_position(deps, _env, address, position_id)?),
+        QueryMsg::Positions {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&query_positions(deps, _env, address, start_after, limit)?),
         QueryMsg::StakingList { start_after, limit } => {
             to_binary(&query_get_staking_list(deps, start_after, limit)?)
         }
+        QueryMsg::SimulateEarlyUnstake {
+            address,
+            position_id,
+            amount,
+        } => to_binary(&query_simulate_early_unstake(
+            deps,
+            _env,
+            address,
+            position_id,
+            amount,
+        )?),
         QueryMsg::StakedDetail {} => to_binary(&query_staked_detail(deps)?),
         QueryMsg::Now {} => to_binary(&query_get_now(_env)?),
     }
@@ -717,43 +1039,84 @@ pub fn query_config(deps: Deps<TerraQuery>) -> StdResult<ConfigResponse> {
         referral_rate: config.referral_rate,
         referral_lock_days: config.referral_lock_days,
         enabled: config.enabled,
+        early_unstake_penalty_rate: config.early_unstake_penalty_rate,
+        penalty_destination: config.penalty_destination,
+    })
+}
+
+fn query_staking_info(deps: Deps<TerraQuery>, address: Addr) -> StdResult<StakingResponse> {
+    let (staker_info, _) = read_staker_info(deps.storage, address.clone())?;
+
+    Ok(StakingResponse {
+        address: address.to_string(),
+        info: staker_info,
     })
 }
 
-fn query_staking_info(
+// The reward and penalty share of a queried position are accrued up to the current block.
+fn position_with_reward(
+    deps: Deps<TerraQuery>,
+    config: Config,
+    detail: &StakedDetail,
+    env: &Env,
+    mut position: StakingPosition,
+) -> StakingPosition {
+    settle_penalty_share(detail, &mut position);
+    position.reward_amount =
+        calc_position_reward(deps.querier, config, env.block.time.seconds(), &position)
+            .unwrap_or(Uint128::zero());
+    position
+}
+
+fn query_position(
     deps: Deps<TerraQuery>,
     env: Env,
     address: Addr,
-) -> StdResult<StakingResponse> {
+    position_id: u64,
+) -> StdResult<PositionResponse> {
     let config = CONFIG.load(deps.storage)?;
-    let mut staker_info = STAKER_INFO
-        .load(deps.storage, address.clone())
-        .unwrap_or_default();
+    let detail = STAKED_DETAIL.load(deps.storage)?;
+    let position = read_position(deps.storage, address.clone(), position_id)?;
 
-    let mut reward = Uint128::zero();
-    if !staker_info.stake_amount.is_zero() {
-        let swap_amount = util::get_simulate_swap_operations(
-            deps.querier,
-            config.router_address.clone().into(),
-            staker_info.stake_amount,
-            config.staking_asset.clone().into(),
-            config.reward_asset.clone().into(),
-        )
-        .unwrap_or(Uint128::zero());
-        reward = calc_reward(
-            config,
-            env.block.time.seconds(),
-            staker_info.lock_week,
-            swap_amount,
-            staker_info.reward_timestamp,
+    Ok(PositionResponse {
+        address: address.to_string(),
+        position: position_with_reward(deps, config, &detail, &env, position),
+    })
+}
+
+fn query_positions(
+    deps: Deps<TerraQuery>,
+    env: Env,
+    address: Addr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PositionsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let detail = STAKED_DETAIL.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    // a staker not converted yet has only its legacy stake as first position
+    let (_, legacy_position) = read_staker_info(deps.storage, address.clone())?;
+    let positions = legacy_position
+        .filter(|_| start_after.is_none())
+        .map(Ok)
+        .into_iter()
+        .chain(
+            POSITIONS
+                .prefix(address.clone())
+                .range(deps.storage, start, None, Order::Ascending)
+                .map(|item| item.map(|(_, position)| position)),
         )
-        .unwrap_or(Uint128::zero());
-    }
-    staker_info.reward_amount = reward;
+        .take(limit)
+        .map(|item| {
+            item.map(|position| position_with_reward(deps, config.clone(), &detail, &env, position))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
 
-    Ok(StakingResponse {
+    Ok(PositionsResponse {
         address: address.to_string(),
-        info: staker_info,
+        positions,
     })
 }
 
@@ -765,13 +1128,13 @@ pub fn query_get_staking_list(
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
 
-    let amount_list = STAKER_INFO
+    let amount_list = LEGACY_STAKER_INFO
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
             item.map(|(address, info)| StakingList {
                 address: address.to_string(),
-                info,
+                info: info.into_staker_info().0,
             })
         })
         .collect::<StdResult<Vec<_>>>()?;
@@ -779,6 +1142,42 @@ pub fn query_get_staking_list(
     Ok(StakingListResponse { list: amount_list })
 }
 
+fn query_simulate_early_unstake(
+    deps: Deps<TerraQuery>,
+    env: Env,
+    address: Addr,
+    position_id: u64,
+    amount: Uint128,
+) -> StdResult<SimulateEarlyUnstakeResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let detail = STAKED_DETAIL.load(deps.storage)?;
+    let position = read_position(deps.storage, address, position_id)?;
+    if amount > position.stake_amount {
+        return Err(StdError::generic_err("amount exceeds the position stake"));
+    }
+
+    let position = position_with_reward(deps, config.clone(), &detail, &env, position);
+    let (penalty, return_amount, remaining_lock) =
+        calc_early_unstake(&config, env.block.time.seconds(), &position, amount);
+    // withdrawing the whole position also forfeits its earlier rewards
+    let forfeited_reward = if position.stake_amount.is_zero() {
+        Uint128::zero()
+    } else if amount == position.stake_amount {
+        position.reward_amount + position.last_reward
+    } else {
+        position
+            .reward_amount
+            .multiply_ratio(amount, position.stake_amount)
+    };
+
+    Ok(SimulateEarlyUnstakeResponse {
+        penalty,
+        return_amount,
+        forfeited_reward,
+        remaining_lock,
+    })
+}
+
 pub fn query_staked_detail(deps: Deps<TerraQuery>) -> StdResult<StakedDetail> {
     let detail = STAKED_DETAIL.load(deps.storage)?;
     Ok(detail)
@@ -788,12 +1187,21 @@ pub fn query_get_now(env: Env) -> StdResult<u64> {
     Ok(env.block.time.seconds())
 }
 
+const TARGET_CONTRACT_VERSION: &str = "0.1.0";
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(
     deps: DepsMut<TerraQuery>,
     _env: Env,
     _msg: MigrateMsg,
 ) -> StdResult<Response<TerraMsg>> {
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    migrate_version(
+        deps,
+        TARGET_CONTRACT_VERSION,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+    )?;
+
+    // the merged stake of a staker becomes its first position when the staker is first touched
     Ok(Response::default())
 }
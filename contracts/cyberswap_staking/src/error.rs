@@ -23,7 +23,7 @@ pub enum ContractError {
 
     #[error("Invalid Referral Address")]
     InvalidReferralAddr {},
-    
+
     #[error("Insufficient Balance")]
     Insufficient {},
 
@@ -39,6 +39,9 @@ pub enum ContractError {
     #[error("Too small offer amount")]
     TooSmallOfferAmount {},
 
+    #[error("Position not found")]
+    PositionNotFound {},
+
     #[error("Still in Lock period")]
     StillInLock {},
 
@@ -47,4 +50,13 @@ pub enum ContractError {
 
     #[error("Disabled")]
     Disabled {},
+
+    #[error("Ownership proposal not found")]
+    OwnershipProposalNotFound {},
+
+    #[error("Ownership proposal expired")]
+    OwnershipProposalExpired {},
+
+    #[error("Ownership proposal cannot expire later than {max} seconds")]
+    InvalidOwnershipProposalTtl { max: u64 },
 }
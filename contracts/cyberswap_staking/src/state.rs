@@ -2,8 +2,8 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use classic_cyberswap::asset::AssetInfo;
-use classic_cyberswap::staking::{StakedDetail, StakerInfo};
-use cosmwasm_std::{Addr, CanonicalAddr};
+use classic_cyberswap::staking::{PenaltyDestination, StakedDetail, StakerInfo, StakingPosition};
+use cosmwasm_std::{Addr, CanonicalAddr, Decimal, StdError, StdResult, Storage, Uint128};
 use cw_storage_plus::{Item, Map};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -19,13 +19,128 @@ pub struct Config {
     pub referral_rate: u64,
     pub referral_lock_days: u64,
     pub enabled: bool,
+    /// Zero for the configs stored before early unstakes, until the owner sets a rate
+    #[serde(default)]
+    pub early_unstake_penalty_rate: u64,
+    #[serde(default)]
+    pub penalty_destination: PenaltyDestination,
 }
 
 pub const CONFIG_KEY: &str = "config";
 pub const CONFIG: Item<Config> = Item::new("config");
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipProposal {
+    pub owner: CanonicalAddr,
+    pub expires_at: u64,
+}
+
+pub const OWNERSHIP_PROPOSAL_KEY: &str = "ownership_proposal";
+pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new(OWNERSHIP_PROPOSAL_KEY);
+
 pub const STAKING_KEY: &str = "staking";
 pub const STAKER_INFO: Map<Addr, StakerInfo> = Map::new(STAKING_KEY);
 
+pub const POSITIONS_KEY: &str = "positions";
+pub const POSITIONS: Map<(Addr, u64), StakingPosition> = Map::new(POSITIONS_KEY);
+
+/// LegacyStakerInfo is the single merged position stored before positions,
+/// converted into the first position when the staker is first touched
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyStakerInfo {
+    #[serde(default)]
+    pub lock_week: u64,
+    pub stake_amount: Uint128,
+    #[serde(default)]
+    pub reward_amount: Uint128,
+    #[serde(default)]
+    pub last_reward: Uint128,
+    pub referral_addr: Addr,
+    pub referral_reward: Uint128,
+    pub referral_count: u64,
+    #[serde(default)]
+    pub staked_timestamp: u64,
+    #[serde(default)]
+    pub reward_timestamp: u64,
+    pub referral_timestamp: u64,
+    #[serde(default)]
+    pub position_count: Option<u64>,
+}
+
+pub const LEGACY_STAKER_INFO: Map<Addr, LegacyStakerInfo> = Map::new(STAKING_KEY);
+
+impl LegacyStakerInfo {
+    /// Returns the staker info, and the merged stake as its first position
+    /// when the entry has not been converted yet
+    pub fn into_staker_info(self) -> (StakerInfo, Option<StakingPosition>) {
+        let mut position = None;
+        let position_count = match self.position_count {
+            Some(position_count) => position_count,
+            None if self.stake_amount.is_zero() => 0,
+            None => {
+                position = Some(StakingPosition {
+                    id: 1,
+                    lock_week: self.lock_week,
+                    stake_amount: self.stake_amount,
+                    reward_amount: Uint128::zero(),
+                    last_reward: self.last_reward,
+                    staked_timestamp: self.staked_timestamp,
+                    reward_timestamp: self.reward_timestamp,
+                    penalty_index: Decimal::zero(),
+                    penalty_reward: Uint128::zero(),
+                });
+                1
+            }
+        };
+
+        let staker_info = StakerInfo {
+            stake_amount: self.stake_amount,
+            position_count,
+            referral_addr: self.referral_addr,
+            referral_reward: self.referral_reward,
+            referral_count: self.referral_count,
+            referral_timestamp: self.referral_timestamp,
+        };
+        (staker_info, position)
+    }
+}
+
+/// Reads the staker info without converting a legacy entry,
+/// its unconverted first position is returned with it
+pub fn read_staker_info(
+    storage: &dyn Storage,
+    address: Addr,
+) -> StdResult<(StakerInfo, Option<StakingPosition>)> {
+    Ok(match LEGACY_STAKER_INFO.may_load(storage, address)? {
+        Some(info) => info.into_staker_info(),
+        None => (StakerInfo::default(), None),
+    })
+}
+
+/// Reads a position, the legacy stake of a staker not converted yet is its first position
+pub fn read_position(
+    storage: &dyn Storage,
+    address: Addr,
+    position_id: u64,
+) -> StdResult<StakingPosition> {
+    let (_, legacy_position) = read_staker_info(storage, address.clone())?;
+    match legacy_position.filter(|position| position.id == position_id) {
+        Some(position) => Ok(position),
+        None => POSITIONS
+            .may_load(storage, (address, position_id))?
+            .ok_or_else(|| StdError::not_found("position")),
+    }
+}
+
+/// Loads the staker info, converting a legacy entry on first touch
+pub fn load_staker_info(storage: &mut dyn Storage, address: Addr) -> StdResult<StakerInfo> {
+    let (staker_info, position) = read_staker_info(storage, address.clone())?;
+    if let Some(position) = position {
+        POSITIONS.save(storage, (address.clone(), position.id), &position)?;
+        STAKER_INFO.save(storage, address, &staker_info)?;
+    }
+    Ok(staker_info)
+}
+
 pub const STAKED_DETAIL_KEY: &str = "staked_detail";
 pub const STAKED_DETAIL: Item<StakedDetail> = Item::new(STAKED_DETAIL_KEY);
@@ -0,0 +1,573 @@
+use crate::contract::{calc_penalty, execute, instantiate, migrate, query, ONE_WEEK};
+use crate::error::ContractError;
+use crate::state::{
+    Config, LegacyStakerInfo, CONFIG, LEGACY_STAKER_INFO, POSITIONS, STAKED_DETAIL, STAKER_INFO,
+};
+use classic_bindings::{TerraMsg, TerraQuery};
+use classic_cyberswap::asset::AssetInfo;
+use classic_cyberswap::mock_querier::{mock_dependencies, WasmMockQuerier};
+use classic_cyberswap::staking::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, PenaltyDestination, PositionsResponse,
+    QueryMsg, ReceiveMsg, SimulateEarlyUnstakeResponse, StakerInfo, StakingListResponse,
+    StakingPosition, StakingResponse,
+};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage};
+use cosmwasm_std::{
+    attr, from_binary, to_binary, to_vec, Addr, CosmosMsg, Decimal, Env, OwnedDeps, StdError,
+    Uint128, WasmMsg,
+};
+use cw2::set_contract_version;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+
+type TerraDeps = OwnedDeps<MockStorage, MockApi, WasmMockQuerier, TerraQuery>;
+
+const TOKEN: &str = "asset0000";
+
+fn setup(penalty_destination: PenaltyDestination) -> TerraDeps {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &TOKEN.to_string(),
+        &[
+            (&"addr0000".to_string(), &Uint128::from(1_000_000u128)),
+            (&"addr0001".to_string(), &Uint128::from(1_000_000u128)),
+        ],
+    )]);
+
+    // the reward asset is the staking asset, so no router simulation is queried
+    let msg = InstantiateMsg {
+        staking_asset: AssetInfo::Token {
+            contract_addr: TOKEN.to_string(),
+        },
+        reward_asset: AssetInfo::Token {
+            contract_addr: TOKEN.to_string(),
+        },
+        router_address: Addr::unchecked("router0000"),
+        min_lock_week: 1,
+        max_lock_week: 52,
+        min_ratio: 10,
+        max_ratio: 100,
+        referral_rate: 0,
+        referral_lock_days: 0,
+        enabled: true,
+        early_unstake_penalty_rate: 50,
+        penalty_destination,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    deps
+}
+
+fn env_after(seconds: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(seconds);
+    env
+}
+
+fn stake(deps: &mut TerraDeps, env: Env, staker: &str, amount: u128, lock_week: u64) {
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: staker.to_string(),
+        amount: Uint128::from(amount),
+        msg: to_binary(&ReceiveMsg::Stake {
+            lock_week,
+            referral_addr: Addr::unchecked("referral0000"),
+            staker: None,
+        })
+        .unwrap(),
+    });
+    execute(deps.as_mut(), env, mock_info(TOKEN, &[]), msg).unwrap();
+}
+
+fn transfer(recipient: &str, amount: u128) -> CosmosMsg<TerraMsg> {
+    CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: TOKEN.to_string(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount: Uint128::from(amount),
+        })
+        .unwrap(),
+    })
+}
+
+fn query_positions(deps: &TerraDeps, address: &str) -> Vec<StakingPosition> {
+    let res: PositionsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Positions {
+                address: Addr::unchecked(address),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    res.positions
+}
+
+#[test]
+fn legacy_stake_becomes_first_position_on_first_touch() {
+    let mut deps = setup(PenaltyDestination::Redistribute {});
+    set_contract_version(
+        deps.as_mut().storage,
+        "crates.io:cyberswap-staking",
+        "0.1.0",
+    )
+    .unwrap();
+
+    LEGACY_STAKER_INFO
+        .save(
+            deps.as_mut().storage,
+            Addr::unchecked("addr0000"),
+            &LegacyStakerInfo {
+                lock_week: 4,
+                stake_amount: Uint128::from(1000u128),
+                reward_amount: Uint128::from(3u128),
+                last_reward: Uint128::from(7u128),
+                referral_addr: Addr::unchecked("referral0000"),
+                referral_reward: Uint128::from(5u128),
+                referral_count: 2,
+                staked_timestamp: 100,
+                reward_timestamp: 200,
+                referral_timestamp: 300,
+                position_count: None,
+            },
+        )
+        .unwrap();
+    // a referrer that never staked keeps its referral reward without position
+    LEGACY_STAKER_INFO
+        .save(
+            deps.as_mut().storage,
+            Addr::unchecked("referral0000"),
+            &LegacyStakerInfo {
+                lock_week: 0,
+                stake_amount: Uint128::zero(),
+                reward_amount: Uint128::zero(),
+                last_reward: Uint128::zero(),
+                referral_addr: Addr::unchecked(""),
+                referral_reward: Uint128::from(11u128),
+                referral_count: 1,
+                staked_timestamp: 0,
+                reward_timestamp: 0,
+                referral_timestamp: 300,
+                position_count: None,
+            },
+        )
+        .unwrap();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("invalid contract version. target 0.1.0, but source is 0.2.0")
+    );
+
+    // the legacy stake is queried as the first position before it is converted
+    let staker_info = StakerInfo {
+        stake_amount: Uint128::from(1000u128),
+        position_count: 1,
+        referral_addr: Addr::unchecked("referral0000"),
+        referral_reward: Uint128::from(5u128),
+        referral_count: 2,
+        referral_timestamp: 300,
+    };
+    let res: StakingResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::StakerInfo {
+                address: Addr::unchecked("addr0000"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.info, staker_info);
+    let positions = query_positions(&deps, "addr0000");
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].id, 1);
+    assert_eq!(positions[0].stake_amount, Uint128::from(1000u128));
+    assert!(!POSITIONS.has(deps.as_ref().storage, (Addr::unchecked("addr0000"), 1)));
+
+    let res: StakingListResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::StakingList {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.list.len(), 2);
+    assert_eq!(res.list[0].info, staker_info);
+    assert_eq!(res.list[1].info.position_count, 0);
+    assert_eq!(res.list[1].info.referral_reward, Uint128::from(11u128));
+
+    // claiming converts the legacy stake into a stored position
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::ClaimReward { position_id: 1 },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+
+    let position = POSITIONS
+        .load(deps.as_ref().storage, (Addr::unchecked("addr0000"), 1))
+        .unwrap();
+    assert_eq!(position.lock_week, 4);
+    assert_eq!(position.stake_amount, Uint128::from(1000u128));
+    assert_eq!(position.staked_timestamp, 100);
+    assert_eq!(position.last_reward, Uint128::zero());
+    assert_eq!(position.reward_timestamp, mock_env().block.time.seconds());
+    assert_eq!(
+        STAKER_INFO
+            .load(deps.as_ref().storage, Addr::unchecked("addr0000"))
+            .unwrap(),
+        staker_info
+    );
+    assert_eq!(query_positions(&deps, "addr0000"), vec![position]);
+
+    // a new stake opens the next position
+    stake(&mut deps, mock_env(), "addr0000", 500, 1);
+    let positions = query_positions(&deps, "addr0000");
+    assert_eq!(positions.len(), 2);
+    assert_eq!(positions[1].id, 2);
+    assert_eq!(positions[1].stake_amount, Uint128::from(500u128));
+}
+
+#[test]
+fn positions_keep_their_own_lock() {
+    let mut deps = setup(PenaltyDestination::Redistribute {});
+    stake(&mut deps, mock_env(), "addr0000", 100_000, 1);
+    stake(&mut deps, mock_env(), "addr0000", 200_000, 10);
+
+    let env = env_after(2 * ONE_WEEK);
+    let info = mock_info("addr0000", &[]);
+
+    // the second position is still locked
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::Unstake {
+            position_id: 2,
+            amount: Uint128::from(200_000u128),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::StillInLock {});
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::ClaimReward { position_id: 2 },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::StillInLock {});
+
+    // while the first one is claimed and unstaked
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info.clone(),
+        ExecuteMsg::ClaimReward { position_id: 1 },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+
+    let res = execute(
+        deps.as_mut(),
+        env_after(3 * ONE_WEEK),
+        info,
+        ExecuteMsg::Unstake {
+            position_id: 1,
+            amount: Uint128::from(100_000u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages[0].msg, transfer("addr0000", 100_000));
+
+    let positions = query_positions(&deps, "addr0000");
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].id, 2);
+    assert_eq!(positions[0].stake_amount, Uint128::from(200_000u128));
+
+    let staker_info = STAKER_INFO
+        .load(deps.as_ref().storage, Addr::unchecked("addr0000"))
+        .unwrap();
+    assert_eq!(staker_info.stake_amount, Uint128::from(200_000u128));
+    assert_eq!(staker_info.position_count, 2);
+}
+
+fn simulate_early_unstake(
+    deps: &TerraDeps,
+    env: Env,
+    address: &str,
+    position_id: u64,
+    amount: u128,
+) -> SimulateEarlyUnstakeResponse {
+    from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::SimulateEarlyUnstake {
+                address: Addr::unchecked(address),
+                position_id,
+                amount: Uint128::from(amount),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn penalty_decreases_over_the_lock() {
+    let deps = setup(PenaltyDestination::Redistribute {});
+    let config: Config = CONFIG.load(deps.as_ref().storage).unwrap();
+    let position = StakingPosition {
+        id: 1,
+        lock_week: 10,
+        stake_amount: Uint128::from(1000u128),
+        reward_amount: Uint128::zero(),
+        last_reward: Uint128::zero(),
+        staked_timestamp: 0,
+        reward_timestamp: 0,
+        penalty_index: Decimal::zero(),
+        penalty_reward: Uint128::zero(),
+    };
+    let amount = Uint128::from(1000u128);
+
+    assert_eq!(
+        calc_penalty(&config, 0, &position, amount),
+        (Uint128::from(500u128), 10 * ONE_WEEK)
+    );
+    assert_eq!(
+        calc_penalty(&config, 5 * ONE_WEEK, &position, amount),
+        (Uint128::from(250u128), 5 * ONE_WEEK)
+    );
+    assert_eq!(
+        calc_penalty(&config, 10 * ONE_WEEK, &position, amount),
+        (Uint128::zero(), 0)
+    );
+    assert_eq!(
+        calc_penalty(&config, 11 * ONE_WEEK, &position, amount),
+        (Uint128::zero(), 0)
+    );
+}
+
+#[test]
+fn early_unstake_penalty_to_treasury() {
+    let mut deps = setup(PenaltyDestination::Treasury {
+        address: Addr::unchecked("treasury0000"),
+    });
+    stake(&mut deps, mock_env(), "addr0000", 1000, 10);
+    let info = mock_info("addr0000", &[]);
+
+    // right after staking the full rate is forfeited
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::EarlyUnstake {
+            position_id: 1,
+            amount: Uint128::from(400u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|msg| msg.msg)
+            .collect::<Vec<_>>(),
+        vec![transfer("treasury0000", 200), transfer("addr0000", 200)]
+    );
+    assert!(res.attributes.contains(&attr("penalty", "200")));
+
+    let position = POSITIONS
+        .load(deps.as_ref().storage, (Addr::unchecked("addr0000"), 1))
+        .unwrap();
+    assert_eq!(position.stake_amount, Uint128::from(600u128));
+    let detail = STAKED_DETAIL.load(deps.as_ref().storage).unwrap();
+    assert_eq!(detail.total_staked, Uint128::from(600u128));
+    assert_eq!(detail.penalty_index, Decimal::zero());
+
+    // at expiry nothing is forfeited
+    let res = execute(
+        deps.as_mut(),
+        env_after(10 * ONE_WEEK),
+        info,
+        ExecuteMsg::EarlyUnstake {
+            position_id: 1,
+            amount: Uint128::from(600u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|msg| msg.msg)
+            .collect::<Vec<_>>(),
+        vec![transfer("addr0000", 600)]
+    );
+    assert!(res.attributes.contains(&attr("penalty", "0")));
+    assert!(!POSITIONS.has(deps.as_ref().storage, (Addr::unchecked("addr0000"), 1)));
+}
+
+#[test]
+fn early_unstake_redistributes_penalty() {
+    let mut deps = setup(PenaltyDestination::Redistribute {});
+    stake(&mut deps, mock_env(), "addr0000", 1000, 10);
+    stake(&mut deps, mock_env(), "addr0001", 2500, 10);
+
+    // half way through the lock, half the rate is forfeited
+    let env = env_after(5 * ONE_WEEK);
+    let simulation = simulate_early_unstake(&deps, env.clone(), "addr0000", 1, 1000);
+    assert_eq!(simulation.penalty, Uint128::from(250u128));
+    assert_eq!(simulation.return_amount, Uint128::from(750u128));
+    assert_eq!(simulation.remaining_lock, 5 * ONE_WEEK);
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::EarlyUnstake {
+            position_id: 1,
+            amount: Uint128::from(1000u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|msg| msg.msg)
+            .collect::<Vec<_>>(),
+        vec![transfer("addr0000", 750)]
+    );
+    assert!(res.attributes.contains(&attr("penalty", "250")));
+
+    // the penalty is shared by the remaining stakers
+    let detail = STAKED_DETAIL.load(deps.as_ref().storage).unwrap();
+    assert_eq!(detail.total_staked, Uint128::from(2500u128));
+    assert_eq!(detail.penalty_index, Decimal::percent(10));
+
+    // and paid out with their stake, as simulated
+    let simulation = simulate_early_unstake(&deps, env.clone(), "addr0001", 1, 2500);
+    assert_eq!(simulation.penalty, Uint128::from(625u128));
+    assert_eq!(simulation.return_amount, Uint128::from(2125u128));
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0001", &[]),
+        ExecuteMsg::EarlyUnstake {
+            position_id: 1,
+            amount: Uint128::from(2500u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|msg| msg.msg)
+            .collect::<Vec<_>>(),
+        vec![transfer("addr0001", 2125)]
+    );
+
+    // with no stakers left the last penalty stays in the contract
+    let detail = STAKED_DETAIL.load(deps.as_ref().storage).unwrap();
+    assert_eq!(detail.total_staked, Uint128::zero());
+    assert_eq!(detail.penalty_index, Decimal::percent(10));
+}
+
+#[test]
+fn reject_penalty_rate_above_hundred() {
+    let mut deps = setup(PenaltyDestination::Redistribute {});
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::UpdateConfig {
+            staking_asset: None,
+            reward_asset: None,
+            router_address: None,
+            min_lock_week: None,
+            max_lock_week: None,
+            min_ratio: None,
+            max_ratio: None,
+            referral_rate: None,
+            referral_lock_days: None,
+            enabled: None,
+            early_unstake_penalty_rate: Some(101),
+            penalty_destination: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+
+    let config: Config = CONFIG.load(deps.as_ref().storage).unwrap();
+    let msg = InstantiateMsg {
+        staking_asset: config.staking_asset,
+        reward_asset: config.reward_asset,
+        router_address: config.router_address,
+        min_lock_week: config.min_lock_week,
+        max_lock_week: config.max_lock_week,
+        min_ratio: config.min_ratio,
+        max_ratio: config.max_ratio,
+        referral_rate: config.referral_rate,
+        referral_lock_days: config.referral_lock_days,
+        enabled: config.enabled,
+        early_unstake_penalty_rate: 101,
+        penalty_destination: config.penalty_destination,
+    };
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidInput {});
+}
+
+#[test]
+fn legacy_config_charges_no_penalty() {
+    let mut deps = setup(PenaltyDestination::Treasury {
+        address: Addr::unchecked("treasury0000"),
+    });
+
+    // a config stored before early unstakes has no penalty fields
+    let config =
+        String::from_utf8(to_vec(&CONFIG.load(deps.as_ref().storage).unwrap()).unwrap()).unwrap();
+    let legacy_config = format!(
+        "{}}}",
+        &config[..config.find(",\"early_unstake_penalty_rate\"").unwrap()]
+    );
+    deps.as_mut()
+        .storage
+        .set(b"config", legacy_config.as_bytes());
+
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.early_unstake_penalty_rate, 0);
+    assert_eq!(res.penalty_destination, PenaltyDestination::Redistribute {});
+
+    stake(&mut deps, mock_env(), "addr0000", 1000, 10);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::EarlyUnstake {
+            position_id: 1,
+            amount: Uint128::from(1000u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|msg| msg.msg)
+            .collect::<Vec<_>>(),
+        vec![transfer("addr0000", 1000)]
+    );
+}
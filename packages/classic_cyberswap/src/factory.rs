@@ -3,6 +3,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::asset::{Asset, AssetInfo, PairInfo};
 
+/// Total swap fee of the pairs without a configured fee, 0.3%
+pub const DEFAULT_TOTAL_FEE_BPS: u16 = 30;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {
     /// Pair contract code ID, which is used to
@@ -10,27 +13,114 @@ pub struct InstantiateMsg {
     pub token_code_id: u64,
 }
 
+/// PairCreationMode decides who can execute CreatePair
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PairCreationMode {
+    /// Only the owner can create pairs
+    OwnerOnly,
+    /// The owner and the allowlisted creators can create pairs
+    Allowlist,
+    /// Anyone can create pairs, paying the creation fee if one is set
+    #[default]
+    Permissionless,
+}
+
+/// FeeConfig is the swap fee of a pair in basis points.
+/// The protocol fee is the part of the total fee sent to the fee treasury,
+/// the rest is absorbed by the pool
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeConfig {
+    pub total_fee_bps: u16,
+    pub protocol_fee_bps: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PairFeeConfig {
+    pub pair_addr: String,
+    pub fee_config: FeeConfig,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    /// UpdateConfig update relevant code IDs
+    /// UpdateConfig update relevant code IDs, pair creation rules and swap fees
     UpdateConfig {
-        owner: Option<String>,
         token_code_id: Option<u64>,
         pair_code_id: Option<u64>,
+        pair_creation_mode: Option<PairCreationMode>,
+        /// A zero amount removes the creation fee
+        creation_fee: Option<Asset>,
+        fee_treasury: Option<String>,
+        add_allowed_creators: Option<Vec<String>>,
+        remove_allowed_creators: Option<Vec<String>>,
+        /// Fee of the pairs without an override
+        default_fee_config: Option<FeeConfig>,
+        /// Fee overrides of single pairs
+        pair_fee_configs: Option<Vec<PairFeeConfig>>,
+        remove_pair_fee_configs: Option<Vec<String>>,
     },
-    /// CreatePair instantiates pair contract
+    /// CreatePair instantiates pair contract and, when the asset amounts are not zero,
+    /// provides them as initial liquidity on behalf of the sender.
+    /// Native tokens must be sent along and tokens need an allowance for the factory.
     CreatePair {
         assets: [Asset; 2],
     },
+    /// AddNativeTokenDecimals registers the decimals of a native token.
+    /// They are checked against the denom metadata if the chain has it,
+    /// otherwise the factory has to hold a balance of the denom
     AddNativeTokenDecimals {
         denom: String,
         decimals: u8,
     },
+    /// AddNativeTokenDecimalsBatch registers the decimals of many native tokens at once
+    AddNativeTokenDecimalsBatch {
+        tokens: Vec<NativeTokenDecimals>,
+    },
+    RemoveNativeTokenDecimals {
+        denom: String,
+    },
     MigratePair {
         contract: String,
         code_id: Option<u64>,
     },
+    /// DeregisterPair removes a pair from the registry and keeps it as deprecated
+    DeregisterPair {
+        asset_infos: [AssetInfo; 2],
+    },
+    /// RegisterPair adds an externally deployed pair to the registry
+    RegisterPair {
+        pair_addr: String,
+    },
+    /// MigratePairs migrates a page of the registered pairs.
+    /// Without start_after, it resumes after the last pair migrated to the same code id.
+    /// Reset drops the progress of the code id and starts over from the first pair
+    MigratePairs {
+        start_after: Option<[AssetInfo; 2]>,
+        limit: Option<u32>,
+        code_id: Option<u64>,
+        reset: Option<bool>,
+    },
+    /// IndexPairs indexes and counts a page of the pairs stored before
+    /// the pair indexes existed, until all of them are indexed
+    IndexPairs {
+        limit: Option<u32>,
+    },
+    /// UpdateBlocklist blocks or unblocks assets for pair creation and routing
+    UpdateBlocklist {
+        add: Option<Vec<AssetInfo>>,
+        remove: Option<Vec<AssetInfo>>,
+    },
+    /// ProposeNewOwner proposes a new owner, who has to claim the ownership
+    /// within expires_in seconds
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    /// DropOwnershipProposal removes the pending ownership proposal
+    DropOwnershipProposal {},
+    /// ClaimOwnership transfers the ownership to the proposed owner
+    ClaimOwnership {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -47,6 +137,62 @@ pub enum QueryMsg {
     NativeTokenDecimals {
         denom: String,
     },
+    NativeTokenDecimalsList {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// PairsByAsset returns the pairs that contain the given asset
+    PairsByAsset {
+        asset_info: AssetInfo,
+        start_after: Option<[AssetInfo; 2]>,
+        limit: Option<u32>,
+    },
+    PairCount {},
+    PairByLiquidityToken {
+        liquidity_token: String,
+    },
+    PairByAddress {
+        pair_addr: String,
+    },
+    AllowedCreators {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    DeprecatedPairs {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    PairMigration {},
+    /// OutdatedPairs checks a page of the registered pairs
+    /// and returns the ones not running the given code id
+    OutdatedPairs {
+        code_id: Option<u64>,
+        start_after: Option<[AssetInfo; 2]>,
+        limit: Option<u32>,
+    },
+    /// OwnershipProposal returns the pending ownership proposal
+    OwnershipProposal {},
+    /// IsBlocked returns whether the asset is on the blocklist
+    IsBlocked {
+        asset_info: AssetInfo,
+    },
+    BlockedAssets {
+        start_after: Option<AssetInfo>,
+        limit: Option<u32>,
+    },
+    /// FeeConfig returns the fee applied by the given pair,
+    /// its override if one is set, otherwise the default fee
+    FeeConfig {
+        pair_addr: String,
+    },
+    PairFeeConfigs {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// PredictPairAddress returns the address the pair of the given assets is instantiated at
+    PredictPairAddress {
+        asset_infos: [AssetInfo; 2],
+    },
 }
 
 // We define a custom struct for each query response
@@ -55,6 +201,10 @@ pub struct ConfigResponse {
     pub owner: String,
     pub pair_code_id: u64,
     pub token_code_id: u64,
+    pub pair_creation_mode: PairCreationMode,
+    pub creation_fee: Option<Asset>,
+    pub fee_treasury: Option<String>,
+    pub default_fee_config: FeeConfig,
 }
 
 /// We currently take no arguments for migrations
@@ -65,9 +215,90 @@ pub struct MigrateMsg {}
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct PairsResponse {
     pub pairs: Vec<PairInfo>,
+    /// Contract addresses of the listed pairs that contain blocked assets
+    #[serde(default)]
+    pub blocked_pairs: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct IsBlockedResponse {
+    pub blocked: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BlockedAssetsResponse {
+    pub assets: Vec<AssetInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PairCountResponse {
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AllowedCreatorsResponse {
+    pub creators: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PairMigrationResponse {
+    pub code_id: u64,
+    pub last_migrated: Option<[AssetInfo; 2]>,
+    pub migrated_count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PredictPairAddressResponse {
+    pub contract_addr: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeConfigResponse {
+    pub total_fee_bps: u16,
+    pub protocol_fee_bps: u16,
+    /// Receiver of the protocol fee
+    pub fee_treasury: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PairFeeConfigsResponse {
+    pub fee_configs: Vec<PairFeeConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct OwnershipProposalResponse {
+    pub owner: String,
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct OutdatedPair {
+    pub asset_infos: [AssetInfo; 2],
+    pub contract_addr: String,
+    pub code_id: u64,
+    /// cw2 version reported by the pair
+    pub version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct OutdatedPairsResponse {
+    pub pairs: Vec<OutdatedPair>,
+    /// the pair to start after for the next page
+    pub last_checked: Option<[AssetInfo; 2]>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct NativeTokenDecimalsResponse {
     pub decimals: u8,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct NativeTokenDecimals {
+    pub denom: String,
+    pub decimals: u8,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct NativeTokenDecimalsListResponse {
+    pub tokens: Vec<NativeTokenDecimals>,
+}
@@ -11,6 +11,8 @@ pub struct InstantiateMsg {
     pub staking_token_decimals: u8,
     pub referral_rate: u64,
     pub referral_lock_days: u64,
+    /// Router allowed to stake the return of a swap for the swapper
+    pub router_address: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -19,11 +21,10 @@ pub enum ExecuteMsg {
     /// Open a new user position or add to an existing position
     /// @dev Increase the total LP shares Staked by equal no. of shares as sent by the user
     Receive(Cw20ReceiveMsg),
-    /// @param new_owner The new owner address
     UpdateConfig {
-        owner: Option<String>,
         referral_rate: Option<u64>,
         referral_lock_days: Option<u64>,
+        router_address: Option<String>,
     },
     /// Decrease the total LP shares Staked by the user
     /// Accrued rewards are claimed along-with this function
@@ -35,13 +36,29 @@ pub enum ExecuteMsg {
     /// Claim pending rewards
     Claim {},
     ClaimReferralReward {},
+    /// Propose a new owner, who has to claim the ownership before the proposal expires
+    /// @param owner The new owner address
+    /// @param expires_in The no. of seconds the proposal stays claimable
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    /// Remove the pending ownership proposal
+    DropOwnershipProposal {},
+    /// Claim the ownership proposed to the sender
+    ClaimOwnership {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
-    /// Open a new user position or add to an existing position (Cw20ReceiveMsg)
-    Stake { referral_addr: Addr },
+    /// Open a new user position or add to an existing position (Cw20ReceiveMsg),
+    /// of the staker when set instead of the cw20 sender,
+    /// only the configured router may stake for another address
+    Stake {
+        referral_addr: Addr,
+        staker: Option<String>,
+    },
     UpdateRewardSchedule {
         period_start: u64,
         period_finish: u64,
@@ -80,6 +97,7 @@ pub struct ConfigResponse {
     pub distribution_schedule: (u64, u64, Uint128),
     pub referral_rate: u64,
     pub referral_lock_days: u64,
+    pub router_address: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
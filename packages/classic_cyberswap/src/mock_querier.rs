@@ -1,18 +1,27 @@
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, from_slice, to_binary, Coin, ContractResult, Decimal, OwnedDeps, Querier,
-    QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
+    from_binary, from_slice, to_binary, Binary, CodeInfoResponse, Coin, ContractInfoResponse,
+    ContractResult, Decimal, HexBinary, OwnedDeps, Querier, QuerierResult, QueryRequest,
+    SystemError, SystemResult, Uint128, WasmQuery,
 };
+use cw2::ContractVersion;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::panic;
 
 use crate::asset::{AssetInfo, PairInfo};
-use crate::factory::{NativeTokenDecimalsResponse, QueryMsg as FactoryQueryMsg};
+use crate::factory::{
+    FeeConfigResponse, IsBlockedResponse, NativeTokenDecimalsResponse, QueryMsg as FactoryQueryMsg,
+    DEFAULT_TOTAL_FEE_BPS,
+};
 use crate::pair::QueryMsg as PairQueryMsg;
 use crate::pair::{ReverseSimulationResponse, SimulationResponse};
+use crate::querier::{DenomMetadata, DenomMetadataResponse, DenomUnit, DENOM_METADATA_QUERY_PATH};
 use classic_bindings::{SwapResponse, TaxCapResponse, TaxRateResponse, TerraQuery};
-use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
+use cw20::{
+    BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, MinterResponse, TokenInfoResponse,
+};
+use protobuf::CodedInputStream;
 
 use std::iter::FromIterator;
 
@@ -37,18 +46,30 @@ pub struct WasmMockQuerier {
     token_querier: TokenQuerier,
     tax_querier: TaxQuerier,
     cyberswap_factory_querier: CyberswapFactoryQuerier,
+    // key : contract / value: (code id, cw2 version)
+    contract_infos: HashMap<String, (u64, String)>,
+    // key : contract / value: admin
+    contract_admins: HashMap<String, String>,
+    // key : code id / value: checksum
+    code_checksums: HashMap<u64, HexBinary>,
+    // key : denom / value: exponent of the display unit
+    denom_metadata: HashMap<String, u32>,
+    // commission rate and spread rate of the pair simulations
+    pair_simulation: (Decimal, Decimal),
 }
 
 #[derive(Clone, Default)]
 pub struct TokenQuerier {
     // this lets us iterate over all pairs that match the first string
     balances: HashMap<String, HashMap<String, Uint128>>,
+    minters: HashMap<String, String>,
 }
 
 impl TokenQuerier {
     pub fn new(balances: &[(&String, &[(&String, &Uint128)])]) -> Self {
         TokenQuerier {
             balances: balances_to_map(balances),
+            minters: HashMap::new(),
         }
     }
 }
@@ -96,6 +117,9 @@ pub(crate) fn caps_to_map(caps: &[(&String, &Uint128)]) -> HashMap<String, Uint1
 pub struct CyberswapFactoryQuerier {
     pairs: HashMap<String, PairInfo>,
     native_token_decimals: HashMap<String, u8>,
+    blocked_assets: Vec<AssetInfo>,
+    fee_config: Option<FeeConfigResponse>,
+    fee_config_unsupported: bool,
 }
 
 impl CyberswapFactoryQuerier {
@@ -103,6 +127,9 @@ impl CyberswapFactoryQuerier {
         CyberswapFactoryQuerier {
             pairs: pairs_to_map(pairs),
             native_token_decimals: native_token_decimals_to_map(native_token_decimals),
+            blocked_assets: vec![],
+            fee_config: None,
+            fee_config_unsupported: false,
         }
     }
 }
@@ -192,6 +219,18 @@ impl WasmMockQuerier {
                         }),
                     }
                 }
+                Ok(FactoryQueryMsg::PairByLiquidityToken { liquidity_token }) => match self
+                    .cyberswap_factory_querier
+                    .pairs
+                    .values()
+                    .find(|pair| pair.liquidity_token == liquidity_token)
+                {
+                    Some(v) => SystemResult::Ok(ContractResult::Ok(to_binary(v).unwrap())),
+                    None => SystemResult::Err(SystemError::InvalidRequest {
+                        error: "No pair info exists".to_string(),
+                        request: msg.as_slice().into(),
+                    }),
+                },
                 Ok(FactoryQueryMsg::NativeTokenDecimals { denom }) => {
                     match self
                         .cyberswap_factory_querier
@@ -210,9 +249,41 @@ impl WasmMockQuerier {
                         }),
                     }
                 }
+                Ok(FactoryQueryMsg::IsBlocked { asset_info }) => {
+                    SystemResult::Ok(ContractResult::from(to_binary(&IsBlockedResponse {
+                        blocked: self
+                            .cyberswap_factory_querier
+                            .blocked_assets
+                            .contains(&asset_info),
+                    })))
+                }
+                Ok(FactoryQueryMsg::FeeConfig { .. })
+                    if self.cyberswap_factory_querier.fee_config_unsupported =>
+                {
+                    SystemResult::Err(SystemError::InvalidRequest {
+                        error: "fee_config is not supported".to_string(),
+                        request: msg.clone(),
+                    })
+                }
+                Ok(FactoryQueryMsg::FeeConfig { .. }) => {
+                    let fee_config = self.cyberswap_factory_querier.fee_config.clone().unwrap_or(
+                        FeeConfigResponse {
+                            total_fee_bps: DEFAULT_TOTAL_FEE_BPS,
+                            protocol_fee_bps: 0,
+                            fee_treasury: None,
+                        },
+                    );
+                    SystemResult::Ok(ContractResult::from(to_binary(&fee_config)))
+                }
                 _ => match from_binary(msg) {
-                    Ok(PairQueryMsg::Pair {}) => {
-                        SystemResult::Ok(ContractResult::from(to_binary(&PairInfo {
+                    Ok(PairQueryMsg::Pair {}) => match self
+                        .cyberswap_factory_querier
+                        .pairs
+                        .values()
+                        .find(|pair| pair.contract_addr == *contract_addr)
+                    {
+                        Some(v) => SystemResult::Ok(ContractResult::from(to_binary(v))),
+                        None => SystemResult::Ok(ContractResult::from(to_binary(&PairInfo {
                             asset_infos: [
                                 AssetInfo::NativeToken {
                                     denom: "uluna".to_string(),
@@ -224,13 +295,17 @@ impl WasmMockQuerier {
                             asset_decimals: [6u8, 6u8],
                             contract_addr: "pair0000".to_string(),
                             liquidity_token: "liquidity0000".to_string(),
-                        })))
-                    }
+                        }))),
+                    },
                     Ok(PairQueryMsg::Simulation { offer_asset }) => {
+                        let (commission_rate, spread_rate) = self.pair_simulation;
+                        let spread_amount = offer_asset.amount * spread_rate;
+                        let commission_amount =
+                            (offer_asset.amount - spread_amount) * commission_rate;
                         SystemResult::Ok(ContractResult::from(to_binary(&SimulationResponse {
-                            return_amount: offer_asset.amount,
-                            commission_amount: Uint128::zero(),
-                            spread_amount: Uint128::zero(),
+                            return_amount: offer_asset.amount - spread_amount - commission_amount,
+                            commission_amount,
+                            spread_amount,
                         })))
                     }
                     Ok(PairQueryMsg::ReverseSimulation { ask_asset }) => SystemResult::Ok(
@@ -304,10 +379,89 @@ impl WasmMockQuerier {
                             ))
                         }
 
+                        Cw20QueryMsg::Minter {} => {
+                            match self.token_querier.minters.get(contract_addr) {
+                                Some(minter) => SystemResult::Ok(ContractResult::Ok(
+                                    to_binary(&Some(MinterResponse {
+                                        minter: minter.to_string(),
+                                        cap: None,
+                                    }))
+                                    .unwrap(),
+                                )),
+                                None => panic!("DO NOT ENTER HERE"),
+                            }
+                        }
                         _ => panic!("DO NOT ENTER HERE"),
                     },
                 },
             },
+            QueryRequest::Wasm(WasmQuery::ContractInfo { contract_addr }) => {
+                let admin = self.contract_admins.get(contract_addr);
+                match self.contract_infos.get(contract_addr) {
+                    None if admin.is_none() => SystemResult::Err(SystemError::NoSuchContract {
+                        addr: contract_addr.to_string(),
+                    }),
+                    info => {
+                        let mut res = ContractInfoResponse::default();
+                        res.code_id = info.map(|(code_id, _)| *code_id).unwrap_or_default();
+                        res.admin = admin.cloned();
+                        SystemResult::Ok(ContractResult::from(to_binary(&res)))
+                    }
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::CodeInfo { code_id }) => {
+                match self.code_checksums.get(code_id) {
+                    Some(checksum) => {
+                        let mut res = CodeInfoResponse::default();
+                        res.code_id = *code_id;
+                        res.checksum = checksum.clone();
+                        SystemResult::Ok(ContractResult::from(to_binary(&res)))
+                    }
+                    None => SystemResult::Err(SystemError::NoSuchCode { code_id: *code_id }),
+                }
+            }
+            QueryRequest::Wasm(WasmQuery::Raw { contract_addr, key })
+                if key.as_slice() == b"contract_info" =>
+            {
+                match self.contract_infos.get(contract_addr) {
+                    Some((_, version)) => {
+                        SystemResult::Ok(ContractResult::from(to_binary(&ContractVersion {
+                            contract: "crates.io:cyberswap-pair".to_string(),
+                            version: version.to_string(),
+                        })))
+                    }
+                    None => SystemResult::Ok(ContractResult::Ok(Binary::default())),
+                }
+            }
+            QueryRequest::Stargate { path, data } if path == DENOM_METADATA_QUERY_PATH => {
+                // QueryDenomMetadataRequest { denom = 1 }
+                let mut is = CodedInputStream::from_bytes(data.as_slice());
+                is.read_tag_unpack().unwrap();
+                let denom = is.read_string().unwrap();
+                match self.denom_metadata.get(&denom) {
+                    Some(exponent) => {
+                        SystemResult::Ok(ContractResult::from(to_binary(&DenomMetadataResponse {
+                            metadata: DenomMetadata {
+                                denom_units: vec![
+                                    DenomUnit {
+                                        denom: denom.to_string(),
+                                        exponent: 0,
+                                    },
+                                    DenomUnit {
+                                        denom: "display".to_string(),
+                                        exponent: *exponent,
+                                    },
+                                ],
+                                display: "display".to_string(),
+                            },
+                        })))
+                    }
+                    None => SystemResult::Ok(ContractResult::Err(format!(
+                        "client metadata for denom {} not found",
+                        denom
+                    ))),
+                }
+            }
             _ => self.base.handle_query(request),
         }
     }
@@ -320,6 +474,11 @@ impl WasmMockQuerier {
             token_querier: TokenQuerier::default(),
             tax_querier: TaxQuerier::default(),
             cyberswap_factory_querier: CyberswapFactoryQuerier::default(),
+            contract_infos: HashMap::new(),
+            contract_admins: HashMap::new(),
+            code_checksums: HashMap::new(),
+            denom_metadata: HashMap::new(),
+            pair_simulation: (Decimal::zero(), Decimal::zero()),
         }
     }
 
@@ -328,6 +487,15 @@ impl WasmMockQuerier {
         self.token_querier = TokenQuerier::new(balances);
     }
 
+    // configure the minters of the tokens
+    pub fn with_token_minters(&mut self, minters: &[(&String, &String)]) {
+        for (contract_addr, minter) in minters.iter() {
+            self.token_querier
+                .minters
+                .insert(contract_addr.to_string(), minter.to_string());
+        }
+    }
+
     // configure the token owner mock querier
     pub fn with_tax(&mut self, rate: Decimal, caps: &[(&String, &Uint128)]) {
         self.tax_querier = TaxQuerier::new(rate, caps);
@@ -342,6 +510,67 @@ impl WasmMockQuerier {
         self.cyberswap_factory_querier = CyberswapFactoryQuerier::new(pairs, native_token_decimals);
     }
 
+    // configure the assets blocked by the cyberswap factory
+    pub fn with_blocked_assets(&mut self, blocked_assets: &[AssetInfo]) {
+        self.cyberswap_factory_querier.blocked_assets = blocked_assets.to_vec();
+    }
+
+    // configure the fee returned by the cyberswap factory
+    pub fn with_fee_config(
+        &mut self,
+        total_fee_bps: u16,
+        protocol_fee_bps: u16,
+        fee_treasury: Option<String>,
+    ) {
+        self.cyberswap_factory_querier.fee_config_unsupported = false;
+        self.cyberswap_factory_querier.fee_config = Some(FeeConfigResponse {
+            total_fee_bps,
+            protocol_fee_bps,
+            fee_treasury,
+        });
+    }
+
+    // make the factory querier fail the fee config queries,
+    // like a contract which is not a factory
+    pub fn without_fee_config(&mut self) {
+        self.cyberswap_factory_querier.fee_config_unsupported = true;
+    }
+
+    // configure the code id and the cw2 version of the contracts
+    pub fn with_contract_infos(&mut self, contract_infos: &[(&String, u64, &String)]) {
+        for (contract_addr, code_id, version) in contract_infos.iter() {
+            self.contract_infos
+                .insert(contract_addr.to_string(), (*code_id, version.to_string()));
+        }
+    }
+
+    // configure the admin of the contracts
+    pub fn with_contract_admins(&mut self, contract_admins: &[(&String, &String)]) {
+        for (contract_addr, admin) in contract_admins.iter() {
+            self.contract_admins
+                .insert(contract_addr.to_string(), admin.to_string());
+        }
+    }
+
+    // configure the checksum of the codes
+    pub fn with_code_checksums(&mut self, code_checksums: &[(u64, &HexBinary)]) {
+        for (code_id, checksum) in code_checksums.iter() {
+            self.code_checksums.insert(*code_id, (*checksum).clone());
+        }
+    }
+
+    // configure the bank denom metadata
+    pub fn with_denom_metadata(&mut self, denom_metadata: &[(&String, u32)]) {
+        for (denom, exponent) in denom_metadata.iter() {
+            self.denom_metadata.insert(denom.to_string(), *exponent);
+        }
+    }
+
+    // configure the commission rate and the spread rate of the pair simulations
+    pub fn with_pair_simulation(&mut self, commission_rate: Decimal, spread_rate: Decimal) {
+        self.pair_simulation = (commission_rate, spread_rate);
+    }
+
     pub fn with_balance(&mut self, balances: &[(&String, Vec<Coin>)]) {
         for (addr, balance) in balances {
             self.base.update_balance(addr.to_string(), balance.clone());
@@ -34,6 +34,9 @@ pub enum ExecuteMsg {
         to: Option<String>,
         deadline: Option<u64>,
     },
+    /// UpdateFactory makes the factory administering the pair manage its fees,
+    /// e.g. after the pair is registered to it
+    UpdateFactory {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -1,14 +1,22 @@
 use crate::asset::{Asset, AssetInfo, PairInfo};
-use crate::factory::{NativeTokenDecimalsResponse, QueryMsg as FactoryQueryMsg};
+use crate::factory::{
+    FeeConfigResponse, IsBlockedResponse, NativeTokenDecimalsResponse, QueryMsg as FactoryQueryMsg,
+};
 use crate::pair::{QueryMsg as PairQueryMsg, ReverseSimulationResponse, SimulationResponse};
 
 use classic_bindings::{TerraQuerier, TerraQuery};
 use cosmwasm_std::{
-    to_binary, Addr, AllBalanceResponse, BalanceResponse, BankQuery, Coin, Decimal, QuerierWrapper,
-    QueryRequest, StdResult, Uint128, WasmQuery,
+    from_slice, to_binary, Addr, AllBalanceResponse, BalanceResponse, BankQuery, Binary, Coin,
+    Decimal, QuerierWrapper, QueryRequest, StdError, StdResult, Uint128, WasmQuery,
 };
+use cw2::ContractVersion;
+use protobuf::CodedOutputStream;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
+use cw20::{
+    BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, MinterResponse, TokenInfoResponse,
+};
 
 pub fn query_balance(
     querier: &QuerierWrapper<TerraQuery>,
@@ -63,6 +71,16 @@ pub fn query_token_info(
     Ok(token_info)
 }
 
+pub fn query_token_minter(
+    querier: &QuerierWrapper<TerraQuery>,
+    contract_addr: Addr,
+) -> StdResult<Option<MinterResponse>> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: contract_addr.to_string(),
+        msg: to_binary(&Cw20QueryMsg::Minter {})?,
+    }))
+}
+
 pub fn query_native_decimals(
     querier: &QuerierWrapper<TerraQuery>,
     factory_contract: Addr,
@@ -76,6 +94,31 @@ pub fn query_native_decimals(
     Ok(res.decimals)
 }
 
+pub fn query_is_blocked(
+    querier: &QuerierWrapper<TerraQuery>,
+    factory_contract: Addr,
+    asset_info: &AssetInfo,
+) -> StdResult<bool> {
+    let res: IsBlockedResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_binary(&FactoryQueryMsg::IsBlocked {
+            asset_info: asset_info.clone(),
+        })?,
+    }))?;
+    Ok(res.blocked)
+}
+
+pub fn query_fee_config(
+    querier: &QuerierWrapper<TerraQuery>,
+    factory_contract: Addr,
+    pair_addr: String,
+) -> StdResult<FeeConfigResponse> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_binary(&FactoryQueryMsg::FeeConfig { pair_addr })?,
+    }))
+}
+
 pub fn query_pair_info(
     querier: &QuerierWrapper<TerraQuery>,
     factory_contract: Addr,
@@ -89,6 +132,17 @@ pub fn query_pair_info(
     }))
 }
 
+pub fn query_pair_info_by_liquidity_token(
+    querier: &QuerierWrapper<TerraQuery>,
+    factory_contract: Addr,
+    liquidity_token: String,
+) -> StdResult<PairInfo> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_binary(&FactoryQueryMsg::PairByLiquidityToken { liquidity_token })?,
+    }))
+}
+
 pub fn simulate(
     querier: &QuerierWrapper<TerraQuery>,
     pair_contract: Addr,
@@ -127,6 +181,84 @@ pub fn query_pair_info_from_pair(
     Ok(pair_info)
 }
 
+/// Reads the cw2 version the contract claims to be, if any
+pub fn query_contract_version(
+    querier: &QuerierWrapper<TerraQuery>,
+    contract_addr: Addr,
+) -> StdResult<Option<ContractVersion>> {
+    querier
+        .query_wasm_raw(contract_addr, b"contract_info".as_slice())?
+        .map(|data| from_slice(&data))
+        .transpose()
+}
+
+pub const DENOM_METADATA_QUERY_PATH: &str = "/cosmos.bank.v1beta1.Query/DenomMetadata";
+
+/// JSON form of the bank QueryDenomMetadataResponse, reduced to the fields in use
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DenomMetadataResponse {
+    pub metadata: DenomMetadata,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DenomMetadata {
+    #[serde(default)]
+    pub denom_units: Vec<DenomUnit>,
+    #[serde(default)]
+    pub display: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DenomUnit {
+    pub denom: String,
+    #[serde(default)]
+    pub exponent: u32,
+}
+
+/// Reads the decimals of a native denom from the bank denom metadata,
+/// which is the exponent of its display unit.
+/// Returns None when the chain has no metadata for the denom or does not allow the query.
+pub fn query_denom_metadata_decimals(
+    querier: &QuerierWrapper<TerraQuery>,
+    denom: String,
+) -> StdResult<Option<u8>> {
+    // QueryDenomMetadataRequest { denom = 1 }
+    let mut data: Vec<u8> = vec![];
+    let mut os = CodedOutputStream::vec(&mut data);
+    os.write_string(1, &denom)
+        .and_then(|_| os.flush())
+        .map_err(|_| StdError::serialize_err("QueryDenomMetadataRequest", "failed to encode"))?;
+    drop(os);
+
+    let res: DenomMetadataResponse = match querier.query(&QueryRequest::Stargate {
+        path: DENOM_METADATA_QUERY_PATH.to_string(),
+        data: Binary::from(data),
+    }) {
+        Ok(res) => res,
+        Err(_) => return Ok(None),
+    };
+
+    let exponent = res
+        .metadata
+        .denom_units
+        .iter()
+        .find(|unit| unit.denom == res.metadata.display)
+        .or_else(|| {
+            res.metadata
+                .denom_units
+                .iter()
+                .max_by_key(|unit| unit.exponent)
+        })
+        .map(|unit| unit.exponent);
+
+    exponent
+        .map(|exponent| {
+            u8::try_from(exponent)
+                .map_err(|_| StdError::generic_err("denom metadata exponent is too large"))
+        })
+        .transpose()
+}
+
 static DECIMAL_FRACTION: Uint128 = Uint128::new(1_000_000_000_000_000_000u128);
 
 pub fn compute_tax(
@@ -1,10 +1,11 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Binary, Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
 
-use crate::asset::AssetInfo;
+use crate::asset::{Asset, AssetInfo};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -22,39 +23,193 @@ pub enum SwapOperation {
         offer_asset_info: AssetInfo,
         ask_asset_info: AssetInfo,
     },
+    /// Swap through a pair of the configured terraswap factory
+    TerraSwap {
+        offer_asset_info: AssetInfo,
+        ask_asset_info: AssetInfo,
+    },
+    /// Swap through a pair of the configured astroport factory
+    Astroport {
+        offer_asset_info: AssetInfo,
+        ask_asset_info: AssetInfo,
+    },
 }
 
 impl SwapOperation {
+    pub fn get_offer_asset_info(&self) -> AssetInfo {
+        match self {
+            SwapOperation::NativeSwap { offer_denom, .. } => AssetInfo::NativeToken {
+                denom: offer_denom.clone(),
+            },
+            SwapOperation::CyberSwap {
+                offer_asset_info, ..
+            }
+            | SwapOperation::TerraSwap {
+                offer_asset_info, ..
+            }
+            | SwapOperation::Astroport {
+                offer_asset_info, ..
+            } => offer_asset_info.clone(),
+        }
+    }
+
     pub fn get_target_asset_info(&self) -> AssetInfo {
         match self {
             SwapOperation::NativeSwap { ask_denom, .. } => AssetInfo::NativeToken {
                 denom: ask_denom.clone(),
             },
-            SwapOperation::CyberSwap { ask_asset_info, .. } => ask_asset_info.clone(),
+            SwapOperation::CyberSwap { ask_asset_info, .. }
+            | SwapOperation::TerraSwap { ask_asset_info, .. }
+            | SwapOperation::Astroport { ask_asset_info, .. } => ask_asset_info.clone(),
         }
     }
 }
 
+/// SplitRoute swaps a fraction of the offer asset along its operations
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SplitRoute {
+    pub fraction: Decimal,
+    pub operations: Vec<SwapOperation>,
+}
+
+/// BatchSwapInput swaps the offer asset along its operations,
+/// which are empty when the offer asset is the ask asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BatchSwapInput {
+    pub offer_asset: Asset,
+    pub operations: Vec<SwapOperation>,
+}
+
+/// IbcForward sends the return of the swap over the channel
+/// to the receiver on the counterparty chain, timeout is in seconds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct IbcForward {
+    pub channel: String,
+    pub receiver: String,
+    pub timeout: u64,
+}
+
+/// PostSwapHook delivers the return of the swap to the contract with msg_template,
+/// through a cw20 Send for tokens or as the funds of the execution for native coins
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PostSwapHook {
+    pub contract: String,
+    pub msg_template: Binary,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
-    /// Execute multiple BuyOperation
+    /// Execute multiple BuyOperation, the return is sent over IBC when ibc_forward is set
+    /// and refunded to the sender if the transfer fails, or delivered to post_swap_hook
     ExecuteSwapOperations {
         operations: Vec<SwapOperation>,
         minimum_receive: Option<Uint128>,
         to: Option<String>,
         deadline: Option<u64>,
+        ibc_forward: Option<IbcForward>,
+        post_swap_hook: Option<PostSwapHook>,
+    },
+    /// Swap the native offer asset along the route found by FindBestRoute
+    ExecuteBestSwap {
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+        max_hops: Option<u32>,
+        minimum_receive: Uint128,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
+    /// Swap the sent native offer asset along parallel routes,
+    /// the fractions of the routes must sum to one
+    ExecuteSplitSwap {
+        routes: Vec<SplitRoute>,
+        minimum_receive: Option<Uint128>,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
+    /// Swap the least sent native offer amount returning ask_amount,
+    /// the unused offer is refunded to the sender
+    ExecuteSwapOperationsExactOut {
+        operations: Vec<SwapOperation>,
+        ask_amount: Uint128,
+        max_offer: Uint128,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
+    /// Swap halves of the sent native offer asset into the pair assets
+    /// and provide them as liquidity
+    ZapIn {
+        offer_asset: Asset,
+        pair: [AssetInfo; 2],
+        operations_a: Vec<SwapOperation>,
+        operations_b: Vec<SwapOperation>,
+        min_lp: Uint128,
+        receiver: Option<String>,
+        deadline: Option<u64>,
+    },
+    /// Swap several inputs into the ask asset, native inputs are sent along
+    /// and token inputs are pulled from the sender allowances
+    ExecuteBatchSwap {
+        inputs: Vec<BatchSwapInput>,
+        ask_asset_info: AssetInfo,
+        minimum_receive: Option<Uint128>,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
+    /// UpdateConfig sets the intermediary assets explored by FindBestRoute,
+    /// the factories of the external DEXes and the rules the routes are validated by
+    UpdateConfig {
+        route_assets: Option<Vec<AssetInfo>>,
+        terraswap_factory: Option<String>,
+        astroport_factory: Option<String>,
+        max_hops: Option<u32>,
+        blocked_intermediate_assets: Option<Vec<AssetInfo>>,
+        forbid_pair_revisit: Option<bool>,
+    },
+    /// Sweep sends the router balance of the asset, stuck by past
+    /// transactions, to the receiver, defaults to the whole balance and the owner
+    Sweep {
+        asset_info: AssetInfo,
+        amount: Option<Uint128>,
+        receiver: Option<String>,
     },
 
     /// Internal use
-    /// Swap all offer tokens to ask token
+    /// Swap the amount, or the amount pending for the transaction, of offer token to ask token
     ExecuteSwapOperation {
         operation: SwapOperation,
+        amount: Option<Uint128>,
         to: Option<String>,
         deadline: Option<u64>,
     },
     /// Internal use
+    /// Provide the pending amounts of the pair assets as liquidity
+    ZapProvideLiquidity {
+        pair_contract: String,
+        receiver: String,
+    },
+    /// Internal use
+    /// Transfer the pending amount of the asset
+    TransferPending {
+        asset_info: AssetInfo,
+        receiver: String,
+    },
+    /// Internal use
+    /// Transfer the pending amount of the native asset over IBC,
+    /// the amount is refunded to refund_to if the transfer fails
+    IbcTransferPending {
+        asset_info: AssetInfo,
+        ibc_forward: IbcForward,
+        refund_to: String,
+    },
+    /// Internal use
+    /// Deliver the pending amount of the asset to the hook contract
+    ExecutePostSwapHook {
+        asset_info: AssetInfo,
+        post_swap_hook: PostSwapHook,
+    },
+    /// Internal use
     /// Check the swap amount is exceed minimum_receive
     AssertMinimumReceive {
         asset_info: AssetInfo,
@@ -72,6 +227,45 @@ pub enum Cw20HookMsg {
         minimum_receive: Option<Uint128>,
         to: Option<String>,
         deadline: Option<u64>,
+        ibc_forward: Option<IbcForward>,
+        post_swap_hook: Option<PostSwapHook>,
+    },
+    ExecuteBestSwap {
+        ask_asset_info: AssetInfo,
+        max_hops: Option<u32>,
+        minimum_receive: Uint128,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
+    ExecuteSplitSwap {
+        routes: Vec<SplitRoute>,
+        minimum_receive: Option<Uint128>,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
+    ExecuteSwapOperationsExactOut {
+        operations: Vec<SwapOperation>,
+        ask_amount: Uint128,
+        max_offer: Uint128,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
+    ZapIn {
+        pair: [AssetInfo; 2],
+        operations_a: Vec<SwapOperation>,
+        operations_b: Vec<SwapOperation>,
+        min_lp: Uint128,
+        receiver: Option<String>,
+        deadline: Option<u64>,
+    },
+    /// Withdraw the sent liquidity token and swap both pair assets
+    /// into the ask asset, operations_to holds the operations of each pair asset
+    ZapOut {
+        operations_to: [Vec<SwapOperation>; 2],
+        ask_asset_info: AssetInfo,
+        min_receive: Uint128,
+        receiver: Option<String>,
+        deadline: Option<u64>,
     },
 }
 
@@ -79,6 +273,13 @@ pub enum Cw20HookMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    /// FindBestRoute returns the cyberswap route with the highest return,
+    /// going through the configured route assets
+    FindBestRoute {
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+        max_hops: Option<u32>,
+    },
     SimulateSwapOperations {
         offer_amount: Uint128,
         operations: Vec<SwapOperation>,
@@ -87,12 +288,42 @@ pub enum QueryMsg {
         ask_amount: Uint128,
         operations: Vec<SwapOperation>,
     },
+    /// SimulateSwapOperationsDetailed simulates the operations
+    /// and returns the simulation of every hop
+    SimulateSwapOperationsDetailed {
+        offer_amount: Uint128,
+        operations: Vec<SwapOperation>,
+    },
+    /// SimulateSplitSwap simulates the routes with their fractions,
+    /// or with the fractions returning the most when optimize is set
+    SimulateSplitSwap {
+        offer_amount: Uint128,
+        routes: Vec<SplitRoute>,
+        optimize: Option<bool>,
+    },
+    /// ValidateRoute checks the operations against the route rules of the router
+    ValidateRoute {
+        operations: Vec<SwapOperation>,
+    },
+    /// SimulateMany simulates every route with the offer amount,
+    /// ranked by return with the failed routes last
+    SimulateMany {
+        offer_amount: Uint128,
+        routes: Vec<Vec<SwapOperation>>,
+    },
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct ConfigResponse {
     pub cyberswap_factory: String,
+    pub owner: String,
+    pub route_assets: Vec<AssetInfo>,
+    pub terraswap_factory: Option<String>,
+    pub astroport_factory: Option<String>,
+    pub max_hops: Option<u32>,
+    pub blocked_intermediate_assets: Vec<AssetInfo>,
+    pub forbid_pair_revisit: bool,
 }
 
 // We define a custom struct for each query response
@@ -101,6 +332,151 @@ pub struct SimulateSwapOperationsResponse {
     pub amount: Uint128,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SwapHopSimulation {
+    pub pair_addr: String,
+    pub offer_asset_info: AssetInfo,
+    pub ask_asset_info: AssetInfo,
+    pub offer_amount: Uint128,
+    pub return_amount: Uint128,
+    pub commission_amount: Uint128,
+    pub spread_amount: Uint128,
+    /// Share of the spot price return lost to the spread
+    pub price_impact: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateSwapOperationsDetailedResponse {
+    pub amount: Uint128,
+    pub hops: Vec<SwapHopSimulation>,
+    /// Return amount per offer amount
+    pub effective_price: Decimal,
+    /// Share of the return lost to the spreads of all hops
+    pub price_impact: Decimal,
+    /// Share of the return taken by the commissions and taxes of all hops
+    pub total_fee_rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FindBestRouteResponse {
+    pub operations: Vec<SwapOperation>,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateSplitSwapResponse {
+    pub amount: Uint128,
+    /// The simulated routes, without the ones left out by the optimization
+    pub routes: Vec<SplitRoute>,
+    pub route_amounts: Vec<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RouteSimulation {
+    /// Index of the route in the query
+    pub index: u32,
+    pub amount: Option<Uint128>,
+    /// Why the simulation of the route failed
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateManyResponse {
+    pub routes: Vec<RouteSimulation>,
+}
+
+/// RouteError tells the rule the route breaks, hop is the index of the failing operation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteError {
+    NoOperations {},
+    TooManyHops {
+        hops: u32,
+        max_hops: u32,
+    },
+    Discontinuous {
+        hop: u32,
+        expected_asset: AssetInfo,
+        offer_asset: AssetInfo,
+    },
+    BlockedAsset {
+        hop: u32,
+        asset_info: AssetInfo,
+    },
+    BlockedIntermediateAsset {
+        hop: u32,
+        asset_info: AssetInfo,
+    },
+    PairRevisited {
+        hop: u32,
+        first_hop: u32,
+    },
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RouteError::NoOperations {} => write!(f, "must provide operations"),
+            RouteError::TooManyHops { hops, max_hops } => {
+                write!(f, "{} hops exceed the maximum of {}", hops, max_hops)
+            }
+            RouteError::Discontinuous {
+                hop,
+                expected_asset,
+                offer_asset,
+            } => write!(
+                f,
+                "hop {} offers {} instead of {}",
+                hop, offer_asset, expected_asset
+            ),
+            RouteError::BlockedAsset { hop, asset_info } => {
+                write!(
+                    f,
+                    "hop {} goes through the blocked asset {}",
+                    hop, asset_info
+                )
+            }
+            RouteError::BlockedIntermediateAsset { hop, asset_info } => write!(
+                f,
+                "hop {} returns {} which cannot be an intermediate asset",
+                hop, asset_info
+            ),
+            RouteError::PairRevisited { hop, first_hop } => {
+                write!(f, "hop {} revisits the pair of hop {}", hop, first_hop)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ValidateRouteResponse {
+    pub valid: bool,
+    pub error: Option<RouteError>,
+}
+
+/// SudoMsg holds the IBC lifecycle callbacks of the chain
+/// for the transfers sent by the router
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    IbcLifecycleComplete(IbcLifecycleComplete),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IbcLifecycleComplete {
+    IbcAck {
+        channel: String,
+        sequence: u64,
+        ack: String,
+        success: bool,
+    },
+    IbcTimeout {
+        channel: String,
+        sequence: u64,
+    },
+}
+
 /// We currently take no arguments for migrations
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct MigrateMsg {}
@@ -1,10 +1,10 @@
 use crate::asset::AssetInfo;
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub staking_asset: AssetInfo,
     pub reward_asset: AssetInfo,
@@ -16,6 +16,10 @@ pub struct InstantiateMsg {
     pub referral_rate: u64,
     pub referral_lock_days: u64,
     pub enabled: bool,
+    /// Percentage of the stake forfeited by an early unstake right after staking,
+    /// decreasing linearly to zero at the end of the lock
+    pub early_unstake_penalty_rate: u64,
+    pub penalty_destination: PenaltyDestination,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -23,7 +27,6 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     /// UpdateConfig update relevant code IDs
     UpdateConfig {
-        owner: Option<String>,
         staking_asset: Option<AssetInfo>,
         reward_asset: Option<AssetInfo>,
         router_address: Option<Addr>,
@@ -34,25 +37,71 @@ pub enum ExecuteMsg {
         referral_rate: Option<u64>,
         referral_lock_days: Option<u64>,
         enabled: Option<bool>,
+        early_unstake_penalty_rate: Option<u64>,
+        penalty_destination: Option<PenaltyDestination>,
     },
     Receive(Cw20ReceiveMsg),
+    /// Stake opens a new lock position, leaving the existing ones untouched
     Stake {
         lock_week: u64,
         amount: Uint128,
         referral_addr: Addr,
     },
+    /// Unstake withdraws from an unlocked position with its rewards
     Unstake {
+        position_id: u64,
+        amount: Uint128,
+    },
+    /// EarlyUnstake withdraws from a position still in lock, forfeiting a penalty
+    /// and the rewards accrued by the withdrawn amount
+    EarlyUnstake {
+        position_id: u64,
         amount: Uint128,
     },
-    ClaimReward {},
+    /// ClaimReward claims the rewards of an unlocked position
+    ClaimReward {
+        position_id: u64,
+    },
     ClaimReferralReward {},
     Withdraw {},
+    /// ProposeNewOwner proposes a new owner, who has to claim the ownership
+    /// within expires_in seconds
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    /// DropOwnershipProposal removes the pending ownership proposal
+    DropOwnershipProposal {},
+    /// ClaimOwnership transfers the ownership to the proposed owner
+    ClaimOwnership {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ReceiveMsg {
-    Stake { lock_week: u64, referral_addr: Addr },
+    /// Stake for the staker instead of the cw20 sender,
+    /// only the configured router may stake for another address
+    Stake {
+        lock_week: u64,
+        referral_addr: Addr,
+        staker: Option<String>,
+    },
+}
+
+/// PenaltyDestination receives the penalties forfeited by early unstakes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PenaltyDestination {
+    /// Send the penalties to the treasury address
+    Treasury { address: Addr },
+    /// Share the penalties among the other stakers, pro rata to their stake
+    Redistribute {},
+}
+
+impl Default for PenaltyDestination {
+    fn default() -> Self {
+        PenaltyDestination::Redistribute {}
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -62,10 +111,24 @@ pub enum QueryMsg {
     StakerInfo {
         address: Addr,
     },
+    Position {
+        address: Addr,
+        position_id: u64,
+    },
+    Positions {
+        address: Addr,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
     StakingList {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    SimulateEarlyUnstake {
+        address: Addr,
+        position_id: u64,
+        amount: Uint128,
+    },
     StakedDetail {},
     Now {},
 }
@@ -84,6 +147,8 @@ pub struct ConfigResponse {
     pub referral_rate: u64,
     pub referral_lock_days: u64,
     pub enabled: bool,
+    pub early_unstake_penalty_rate: u64,
+    pub penalty_destination: PenaltyDestination,
 }
 
 /// We currently take no arguments for migrations
@@ -92,40 +157,55 @@ pub struct MigrateMsg {}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct StakerInfo {
-    pub lock_week: u64,
+    /// Total amount staked over all the positions
     pub stake_amount: Uint128,
-    pub reward_amount: Uint128,
-    pub last_reward: Uint128,
+    /// Number of positions opened so far, the id of the latest one
+    pub position_count: u64,
     pub referral_addr: Addr,
     pub referral_reward: Uint128,
     pub referral_count: u64,
-    pub staked_timestamp: u64,
-    pub reward_timestamp: u64,
     pub referral_timestamp: u64,
 }
 
 impl Default for StakerInfo {
     fn default() -> Self {
         StakerInfo {
-            lock_week: 0,
             stake_amount: Uint128::zero(),
-            reward_amount: Uint128::zero(),
-            last_reward: Uint128::zero(),
+            position_count: 0,
             referral_addr: Addr::unchecked(""),
             referral_reward: Uint128::zero(),
             referral_count: 0,
-            staked_timestamp: 0,
-            reward_timestamp: 0,
             referral_timestamp: 0,
         }
     }
 }
 
+/// StakingPosition is a single stake with its own lock and reward accrual
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakingPosition {
+    pub id: u64,
+    pub lock_week: u64,
+    pub stake_amount: Uint128,
+    pub reward_amount: Uint128,
+    pub last_reward: Uint128,
+    pub staked_timestamp: u64,
+    pub reward_timestamp: u64,
+    /// Penalty index of the stake detail when the share was last settled
+    #[serde(default)]
+    pub penalty_index: Decimal,
+    /// Redistributed penalties settled to the position, paid out with the stake
+    #[serde(default)]
+    pub penalty_reward: Uint128,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct StakedDetail {
     pub total_acc: Uint128,
     pub total_wcc: Uint128,
     pub total_staked: Uint128,
+    /// Redistributed penalties per staked amount
+    #[serde(default)]
+    pub penalty_index: Decimal,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -134,6 +214,26 @@ pub struct StakingResponse {
     pub info: StakerInfo,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionResponse {
+    pub address: String,
+    pub position: StakingPosition,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionsResponse {
+    pub address: String,
+    pub positions: Vec<StakingPosition>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateEarlyUnstakeResponse {
+    pub penalty: Uint128,
+    pub return_amount: Uint128,
+    pub forfeited_reward: Uint128,
+    pub remaining_lock: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct StakingListResponse {
     pub list: Vec<StakingList>,